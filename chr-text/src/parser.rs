@@ -0,0 +1,291 @@
+//! Parser for Borland BGI stroke fonts (`.CHR` files).
+//!
+//! Loosely based on <https://www.fileformat.info/format/borland-chr/corion.htm>.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+use stroke_font::{FontMetrics, Glyph, GlyphScale, PackedPoint, StrokeFont, Strokes};
+
+const MAGIC: [u8; 8] = [b'P', b'K', 0x08, 0x08, b'B', b'G', b'I', b' '];
+
+/// Marks the end of the free-form description after the magic.
+const DESCRIPTION_END: u8 = 26;
+
+const SIGNATURE: u8 = b'+';
+
+/// Size of the font header, which is followed by the offset table.
+const FONT_HEADER_LEN: usize = 0x10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChrError {
+    /// The file ended in the middle of something.
+    Truncated,
+    /// The file doesn't start with the `PK\x08\x08BGI ` magic.
+    BadMagic,
+    /// The font header doesn't start with `+`.
+    BadSignature(u8),
+    /// A glyph's strokes start outside the file.
+    BadOffset { character: u8, offset: usize },
+    /// A glyph uses the scan opcode, which stroke fonts shouldn't have.
+    ScanOpcode { character: u8 },
+    /// A glyph is wider than glyph coordinates can reach.
+    BadWidth { character: u8, width: u8 },
+}
+
+impl fmt::Display for ChrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChrError::Truncated => write!(f, "file is truncated"),
+            ChrError::BadMagic => write!(f, "not a BGI font file"),
+            ChrError::BadSignature(s) => write!(f, "bad font header signature {:#04x}", s),
+            ChrError::BadOffset { character, offset } => write!(
+                f,
+                "strokes for character {} at {:#x} are outside the file",
+                character, offset
+            ),
+            ChrError::ScanOpcode { character } => {
+                write!(f, "character {} uses the scan opcode", character)
+            }
+            ChrError::BadWidth { character, width } => {
+                write!(f, "character {} is too wide at {}", character, width)
+            }
+        }
+    }
+}
+
+impl core::error::Error for ChrError {}
+
+struct Cursor<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+struct PackedCoord {
+    opcode: u8,
+    x: i8,
+    y: i8,
+}
+
+fn parse_7bit_signed(input: u8) -> i8 {
+    let input = input & 0x7F;
+
+    if input & 0x40 != 0 {
+        // Sign-extend the 7th bit into the 8th bit
+        (input | 0x80) as i8
+    } else {
+        input as i8
+    }
+}
+
+impl<'a> Cursor<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn read_slice(&mut self, len: usize) -> Result<&'a [u8], ChrError> {
+        let end = self.pos.checked_add(len).ok_or(ChrError::Truncated)?;
+        let slice = self.buf.get(self.pos..end).ok_or(ChrError::Truncated)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, ChrError> {
+        Ok(self.read_slice(1)?[0])
+    }
+
+    fn read_u16_le(&mut self) -> Result<u16, ChrError> {
+        let bytes = self.read_slice(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn skip_to(&mut self, pos: usize) -> Result<(), ChrError> {
+        if pos > self.buf.len() {
+            return Err(ChrError::Truncated);
+        }
+        self.pos = pos;
+        Ok(())
+    }
+
+    fn read_coord(&mut self) -> Result<PackedCoord, ChrError> {
+        let data = self.read_slice(2)?;
+
+        let op1 = (data[0] >> 7) & 0b1;
+        let op2 = (data[1] >> 7) & 0b1;
+
+        let x = parse_7bit_signed(data[0]);
+        // Glyph y coordinates are flipped so that y grows downwards
+        let y = parse_7bit_signed(data[1]).wrapping_neg();
+
+        Ok(PackedCoord {
+            opcode: op1 << 1 | op2,
+            x,
+            y,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct GlyphEntry {
+    width: i8,
+    /// Range of this glyph's points in `ChrFont::points`.
+    start: usize,
+    end: usize,
+}
+
+/// A `.CHR` stroke font loaded at runtime.
+#[derive(Debug, Clone)]
+pub struct ChrFont {
+    description: String,
+    name: String,
+    metrics: FontMetrics,
+    first_char: u8,
+    glyphs: Vec<GlyphEntry>,
+    points: Vec<PackedPoint>,
+}
+
+impl ChrFont {
+    /// Load a font from the contents of a `.CHR` file.
+    pub fn parse(input: &[u8]) -> Result<Self, ChrError> {
+        let mut cur = Cursor::new(input);
+
+        if cur.read_slice(MAGIC.len())? != MAGIC {
+            return Err(ChrError::BadMagic);
+        }
+
+        // Free-form description, usually a copyright notice
+        let desc_len = input[cur.pos..]
+            .iter()
+            .position(|&b| b == DESCRIPTION_END)
+            .ok_or(ChrError::Truncated)?;
+        let description = String::from_utf8_lossy(cur.read_slice(desc_len)?)
+            .trim_end()
+            .into();
+        cur.read_u8()?;
+
+        let header_len = cur.read_u16_le()? as usize;
+        let name = String::from_utf8_lossy(cur.read_slice(4)?)
+            .trim_end_matches(['\0', ' '])
+            .into();
+
+        // Then the file size, driver version and a header end marker, none
+        // of which matter here
+
+        // Parse font details
+        cur.skip_to(header_len)?;
+
+        let signature = cur.read_u8()?;
+        if signature != SIGNATURE {
+            return Err(ChrError::BadSignature(signature));
+        }
+
+        let num_characters = cur.read_u16_le()? as usize;
+        cur.read_u8()?;
+        let first_char = cur.read_u8()?;
+        let stroke_offset = cur.read_u16_le()? as usize;
+        let _scan_flag = cur.read_u8()?;
+
+        // Distance from origin to top of capital letter
+        let origin_to_top = cur.read_u8()? as i8 as i16;
+        // Distance from origin to baseline
+        let origin_to_baseline = cur.read_u8()? as i8 as i16;
+        // Distance from origin to bottom of descender
+        let origin_to_descender = cur.read_u8()? as i8 as i16;
+
+        let metrics = FontMetrics {
+            baseline: -origin_to_baseline,
+            line_height: origin_to_top - origin_to_descender,
+            ascent: origin_to_top - origin_to_baseline,
+            descent: origin_to_baseline - origin_to_descender,
+            cap_height: origin_to_top - origin_to_baseline,
+        };
+
+        // Offsets to stroke data for each character, then their widths
+        cur.skip_to(header_len + FONT_HEADER_LEN)?;
+        let offsets = cur.read_slice(num_characters * 2)?;
+        let widths = cur.read_slice(num_characters)?;
+
+        // The rest of the file is character definitions
+        let data_start = header_len + stroke_offset;
+
+        let mut glyphs = Vec::with_capacity(num_characters);
+        let mut points = Vec::new();
+
+        for i in 0..num_characters {
+            let character = first_char.wrapping_add(i as u8);
+            let offset =
+                data_start + u16::from_le_bytes([offsets[2 * i], offsets[2 * i + 1]]) as usize;
+
+            cur.skip_to(offset)
+                .map_err(|_| ChrError::BadOffset { character, offset })?;
+
+            let start = points.len();
+
+            loop {
+                let coord = cur.read_coord()?;
+
+                match coord.opcode {
+                    // End of character definition
+                    0b00 => break,
+                    // "Do scan"
+                    0b01 => return Err(ChrError::ScanOpcode { character }),
+                    // 0b10 moves the pointer to X, Y, 0b11 draws to it
+                    _ => points.push(PackedPoint {
+                        x: coord.x,
+                        y: coord.y,
+                        pen: coord.opcode == 0b11,
+                    }),
+                }
+            }
+
+            let width = i8::try_from(widths[i]).map_err(|_| ChrError::BadWidth {
+                character,
+                width: widths[i],
+            })?;
+
+            glyphs.push(GlyphEntry {
+                width,
+                start,
+                end: points.len(),
+            });
+        }
+
+        Ok(Self {
+            description,
+            name,
+            metrics,
+            first_char,
+            glyphs,
+            points,
+        })
+    }
+
+    /// The description from the file header, usually a version and
+    /// copyright notice.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// The four character short name, like `LITT`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl StrokeFont for ChrFont {
+    fn glyph(&self, c: char) -> Option<Glyph<'_>> {
+        let index = (c as u32).checked_sub(self.first_char as u32)?;
+        let entry = self.glyphs.get(index as usize)?;
+
+        Some(Glyph {
+            left: 0,
+            right: entry.width,
+            scale: GlyphScale::IDENTITY,
+            strokes: Strokes::Points(&self.points[entry.start..entry.end]),
+        })
+    }
+
+    fn metrics(&self) -> FontMetrics {
+        self.metrics
+    }
+}
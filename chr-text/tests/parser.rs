@@ -0,0 +1,130 @@
+use std::collections::BTreeSet;
+
+use chr_text::{ChrError, ChrFont, Litt, StrokeFont};
+use stroke_font::Glyph;
+
+const LITT_CHR: &[u8] = include_bytes!("../data/LITT.CHR");
+
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+/// The lines a glyph draws, ignoring order and direction, as the shortest
+/// steps between whole coordinates. The baked in glyphs have their strokes
+/// reordered and straight runs merged, but should draw the same lines.
+/// Repeated points in the middle of strokes don't count.
+fn segments(glyph: &Glyph) -> BTreeSet<((i32, i32), (i32, i32))> {
+    let mut segments = BTreeSet::new();
+    let mut at = (0, 0);
+    for p in glyph.strokes.iter() {
+        let to = (p.x as i32, p.y as i32);
+        if p.pen && to != at {
+            let (dx, dy) = (to.0 - at.0, to.1 - at.1);
+            let steps = gcd(dx, dy);
+            for i in 0..steps {
+                let a = (at.0 + dx * i / steps, at.1 + dy * i / steps);
+                let b = (at.0 + dx * (i + 1) / steps, at.1 + dy * (i + 1) / steps);
+                segments.insert((a.min(b), a.max(b)));
+            }
+        }
+        at = to;
+    }
+    segments
+}
+
+#[test]
+fn parses_litt() {
+    let font = ChrFont::parse(LITT_CHR).unwrap();
+
+    assert_eq!(font.name(), "LITT");
+    assert_eq!(font.metrics(), Litt.metrics());
+
+    for c in (0..=255u8).map(char::from) {
+        let parsed = font.glyph(c);
+        let baked = Litt.glyph(c);
+        assert_eq!(parsed.is_some(), baked.is_some(), "{:?}", c);
+        if let (Some(parsed), Some(baked)) = (parsed, baked) {
+            assert_eq!((parsed.left, parsed.right), (baked.left, baked.right));
+            assert_eq!(segments(&parsed), segments(&baked), "{:?}", c);
+        }
+    }
+}
+
+#[test]
+fn rejects_bad_magic() {
+    let mut data = LITT_CHR.to_vec();
+    data[0] = b'X';
+    assert_eq!(ChrFont::parse(&data).unwrap_err(), ChrError::BadMagic);
+}
+
+#[test]
+fn rejects_bad_signature() {
+    let mut data = LITT_CHR.to_vec();
+    data[0x80] = b'K';
+    assert_eq!(
+        ChrFont::parse(&data).unwrap_err(),
+        ChrError::BadSignature(b'K')
+    );
+}
+
+#[test]
+fn rejects_scan_opcode() {
+    // The first stroke of the first glyph, with its opcode changed to a scan
+    let mut data = LITT_CHR.to_vec();
+    data[0x1B0] &= 0x7F;
+    data[0x1B1] |= 0x80;
+    assert_eq!(
+        ChrFont::parse(&data).unwrap_err(),
+        ChrError::ScanOpcode { character: b' ' }
+    );
+}
+
+#[test]
+fn rejects_offsets_past_the_end() {
+    let mut data = LITT_CHR.to_vec();
+    data[0x90..0x92].copy_from_slice(&0xFFFFu16.to_le_bytes());
+    assert!(matches!(
+        ChrFont::parse(&data).unwrap_err(),
+        ChrError::BadOffset {
+            character: b' ',
+            ..
+        }
+    ));
+}
+
+#[test]
+fn rejects_widths_past_i8() {
+    // The width of the first glyph, after the offsets of every glyph
+    let num_characters = u16::from_le_bytes([LITT_CHR[0x81], LITT_CHR[0x82]]) as usize;
+    let mut data = LITT_CHR.to_vec();
+    data[0x90 + 2 * num_characters] = 200;
+    assert_eq!(
+        ChrFont::parse(&data).unwrap_err(),
+        ChrError::BadWidth {
+            character: b' ',
+            width: 200
+        }
+    );
+
+    data[0x90 + 2 * num_characters] = 127;
+    let font = ChrFont::parse(&data).unwrap();
+    assert_eq!(font.glyph(' ').unwrap().right, 127);
+}
+
+#[test]
+fn truncated_files_are_errors() {
+    for len in 0..LITT_CHR.len() {
+        assert!(ChrFont::parse(&LITT_CHR[..len]).is_err(), "length {}", len);
+    }
+}
+
+#[test]
+fn corrupt_bytes_never_panic() {
+    for i in 0..LITT_CHR.len() {
+        for value in [0x00, 0x7F, 0x80, 0xFF] {
+            let mut data = LITT_CHR.to_vec();
+            data[i] = value;
+            let _ = ChrFont::parse(&data);
+        }
+    }
+}
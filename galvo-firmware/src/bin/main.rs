@@ -7,12 +7,14 @@
 )]
 
 use alloc::boxed::Box;
-use alloc::string::String;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
+use core::cell::RefCell;
 use embassy_executor::Spawner;
 use embassy_net::{DhcpConfig, StackResources};
-use embassy_time::{Duration, Timer};
+use embassy_time::{Duration, Instant, Timer};
 use esp_backtrace as _;
+use esp_bootloader_esp_idf::partitions;
 use esp_hal::clock::CpuClock;
 use esp_hal::delay::Delay;
 use esp_hal::ledc::timer::TimerIFace;
@@ -23,17 +25,25 @@ use esp_hal::rtc_cntl::Rtc;
 use esp_hal::time::Rate;
 use esp_hal::timer::timg::TimerGroup;
 use esp_radio::Controller;
+use esp_storage::FlashStorage;
 use galvo_driver::network::{
-    RtcTimeSource, SharedRtc, connection, get_mastodon_status, get_time_ntp, net_task,
+    RtcTimeSource, SharedDac, SharedDmx, SharedIdn, SharedRtc, artnet_listener, connection,
+    ether_dream_beacon, ether_dream_server, get_mastodon_status, get_time_ntp, idn_server,
+    net_task, sacn_listener,
 };
 use galvo_driver::nunchuck::Nunchuck;
 use galvo_driver::protocol::{Command, Response};
-use usb_device::prelude::{UsbDeviceBuilder, UsbVidPid};
+use galvo_net::dmx::{DmxListener, NORMAL_SPEED};
+use galvo_net::ether_dream::EtherDream;
+use galvo_net::idn::IdnServer;
+use usb_device::prelude::{UsbDevice, UsbDeviceBuilder, UsbError, UsbVidPid};
 use usbd_serial::{SerialPort, USB_CLASS_CDC};
+use vector_apps::apps::VectorApp;
 use vector_apps::apps::clock::Clock;
-use vector_apps::apps::{self, VectorApp};
+use vector_apps::storage::Storage;
+use vector_apps::storage::flash::FlashStore;
 
-use log::info;
+use log::{info, warn};
 
 use embassy_sync::blocking_mutex::Mutex;
 use galvo_driver::lasers::Lasers;
@@ -45,6 +55,7 @@ use vector_apps::apps::cube::CubeDemo;
 use vector_apps::apps::cycle::Cycle;
 use vector_apps::apps::ilda::Ilda;
 use vector_apps::apps::maps::Maps;
+use vector_apps::apps::message::Message;
 
 extern crate alloc;
 
@@ -64,6 +75,41 @@ macro_rules! mk_static {
 
 static mut EP_MEMORY: [u32; 1024] = [0; 1024];
 
+// How often app state is written back to flash. Unchanged values are skipped,
+// so this mostly bounds how much progress is lost on a power cut.
+const SAVE_INTERVAL_FRAMES: u64 = 2000;
+
+// Points taken from a network stream at a time, between polling everything
+// else.
+const STREAM_CHUNK_POINTS: usize = 256;
+
+// What the projector calls itself on the network.
+const HOSTNAME: &str = "laser-esp32";
+
+// How long a serial reply can wait for the host to read it before the rest is
+// dropped, so a host that stops reading can't stall the lasers.
+const SERIAL_WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+// Write all of `bytes` to the serial port, polling the USB device whenever its
+// buffer is full.
+fn write_serial<B: usb_device::bus::UsbBus>(
+    usb_dev: &mut UsbDevice<'_, B>,
+    serial: &mut SerialPort<'_, B>,
+    mut bytes: &[u8],
+) -> Result<(), UsbError> {
+    let deadline = Instant::now() + SERIAL_WRITE_TIMEOUT;
+    while !bytes.is_empty() {
+        match serial.write(bytes) {
+            Ok(count) => bytes = &bytes[count..],
+            Err(UsbError::WouldBlock) if Instant::now() < deadline => {
+                usb_dev.poll(&mut [&mut *serial]);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
 #[esp_rtos::main]
 async fn main(spawner: Spawner) -> ! {
     // generator version: 1.0.1
@@ -129,9 +175,10 @@ async fn main(spawner: Spawner) -> ! {
         esp_radio::wifi::new(esp_radio_ctrl, peripherals.WIFI, Default::default()).unwrap();
 
     let wifi_interface = interfaces.sta;
+    let mac = wifi_interface.mac_address();
 
     let mut dhcp_options: DhcpConfig = Default::default();
-    dhcp_options.hostname = Some(heapless::String::try_from("laser-esp32").unwrap());
+    dhcp_options.hostname = Some(heapless::String::try_from(HOSTNAME).unwrap());
     let config = embassy_net::Config::dhcpv4(dhcp_options);
 
     let rng = Rng::new();
@@ -148,6 +195,23 @@ async fn main(spawner: Spawner) -> ! {
     spawner.spawn(connection(controller)).ok();
     spawner.spawn(net_task(runner)).ok();
 
+    // Laser software can stream to the projector as if it were an Ether Dream
+    let dac = mk_static!(SharedDac, Mutex::new(RefCell::new(EtherDream::new())));
+    spawner.spawn(ether_dream_beacon(stack, dac, mac)).ok();
+    spawner.spawn(ether_dream_server(stack, dac)).ok();
+
+    // Or speak IDN to it
+    let idn = mk_static!(
+        SharedIdn,
+        Mutex::new(RefCell::new(IdnServer::new(mac, HOSTNAME)))
+    );
+    spawner.spawn(idn_server(stack, idn)).ok();
+
+    // Lighting desks run it as a fixture, patched once storage is up
+    let dmx = mk_static!(SharedDmx, Mutex::new(RefCell::new(DmxListener::new())));
+    spawner.spawn(artnet_listener(stack, dmx)).ok();
+    spawner.spawn(sacn_listener(stack, dmx)).ok();
+
     stack.wait_config_up().await;
 
     indicator.set_color(smart_leds::colors::YELLOW);
@@ -168,10 +232,38 @@ async fn main(spawner: Spawner) -> ! {
     // apps.push(Box::new(Align::new()));
     apps.push(Box::new(Clock::new(RtcTimeSource::new(rtc))));
 
-    let mut active_demo: Box<dyn apps::VectorApp> = Box::new(Cycle::new(apps));
+    let mut active_demo = Cycle::new(apps);
     // let mut active_demo: Box<dyn apps::VectorApp> = Box::new(Asteroids::new());
 
+    // App state lives in the "nvs" data partition of the default partition table
+    let mut flash = FlashStorage::new(peripherals.FLASH);
+    let mut pt_mem = [0u8; partitions::PARTITION_TABLE_MAX_LEN];
+    let pt = partitions::read_partition_table(&mut flash, &mut pt_mem).unwrap();
+    let nvs = pt
+        .find_partition(partitions::PartitionType::Data(
+            partitions::DataPartitionSubType::Nvs,
+        ))
+        .unwrap()
+        .unwrap();
+    let mut storage = FlashStore::new(nvs.as_embedded_storage(&mut flash)).unwrap();
+
+    active_demo.load_state(&storage);
+
+    if let (Some(universe), Some(address)) = (
+        storage.get_u32("dmx.universe"),
+        storage.get_u32("dmx.address"),
+    ) && let Err(e) = dmx.lock(|dmx| dmx.borrow_mut().set_patch(universe as u16, address as u16))
+    {
+        warn!("ignoring saved DMX patch: {}", e);
+    }
+
+    // Shown instead of the apps while set
+    let mut message: Option<Message> = None;
+
     let mut frameno: u64 = 0;
+    // Frames the apps have been through, in 1/NORMAL_SPEED frames, so a
+    // desk can speed them up or slow them down
+    let mut app_ticks: u64 = 0;
 
     indicator.set_color(smart_leds::colors::GREEN);
 
@@ -199,16 +291,55 @@ async fn main(spawner: Spawner) -> ! {
                     if let Ok(s) = core::str::from_utf8(json_bytes)
                         && let Ok(cmd) = serde_json::from_str::<Command>(s)
                     {
+                        let mut result = Response {
+                            success: true,
+                            storage: None,
+                            error: None,
+                        };
+
                         match cmd {
                             Command::SetIndicatorLight { r, g, b } => {
                                 indicator.set_color(smart_leds::RGB { r, g, b });
                             }
+                            Command::DumpStorage => {
+                                result.storage = Some(storage.entries().clone());
+                            }
+                            Command::ClearStorage => {
+                                result.success = storage.clear().is_ok();
+                            }
+                            Command::ShowMessage { text } => match Message::new(&text) {
+                                Ok(m) => message = Some(m),
+                                Err(e) => {
+                                    result.success = false;
+                                    result.error = Some(e.to_string());
+                                }
+                            },
+                            Command::ClearMessage => {
+                                message = None;
+                            }
+                            Command::SetDmxPatch { universe, address } => {
+                                let patched =
+                                    dmx.lock(|dmx| dmx.borrow_mut().set_patch(universe, address));
+                                result.success = match patched {
+                                    Ok(()) => storage
+                                        .set_u32("dmx.universe", universe as u32)
+                                        .and_then(|_| {
+                                            storage.set_u32("dmx.address", address as u32)
+                                        })
+                                        .is_ok(),
+                                    Err(e) => {
+                                        result.error = Some(e.to_string());
+                                        false
+                                    }
+                                };
+                            }
                         }
 
-                        let result = Response { success: true };
-
                         let response = serde_json::to_string(&result).unwrap();
-                        let _ = serial.write(response.as_bytes());
+                        if let Err(e) = write_serial(&mut usb_dev, &mut serial, response.as_bytes())
+                        {
+                            warn!("failed to send serial response: {:?}", e);
+                        }
                     }
 
                     // Remove processed message
@@ -225,9 +356,46 @@ async fn main(spawner: Spawner) -> ! {
             active_demo.handle_controls(controls);
         }
 
+        if frameno % SAVE_INTERVAL_FRAMES == 0
+            && let Err(e) = active_demo.save_state(&mut storage)
+        {
+            warn!("failed to save app state: {:?}", e);
+        }
+
         frameno += 1;
 
-        for p in active_demo.get_path(frameno) {
+        let now_us = Instant::now().as_micros();
+        let desk = dmx.lock(|dmx| dmx.borrow_mut().controls(now_us));
+        if let Some(idx) = desk.and_then(|desk| desk.app_index(active_demo.app_count())) {
+            active_demo.select(idx);
+        }
+        app_ticks += desk.map_or(NORMAL_SPEED, |desk| desk.speed) as u64;
+
+        // A network stream takes over from the apps while it plays
+        let mut streamed = dac.lock(|dac| dac.borrow_mut().next_path(STREAM_CHUNK_POINTS));
+        if streamed.is_empty() {
+            streamed = idn.lock(|idn| idn.borrow_mut().next_path(now_us, STREAM_CHUNK_POINTS));
+        }
+        let path = if !streamed.is_empty() {
+            &streamed
+        } else {
+            match &mut message {
+                Some(message) => message.get_path(frameno),
+                None => active_demo.get_path(app_ticks / NORMAL_SPEED as u64),
+            }
+        };
+
+        // With the desk's dimmer, color and movement on top
+        let from_desk;
+        let path = match desk {
+            Some(desk) => {
+                from_desk = desk.apply(path);
+                &from_desk
+            }
+            None => path,
+        };
+
+        for p in path {
             // Output coordinates
             lasers.display(p);
 
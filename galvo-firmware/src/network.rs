@@ -1,4 +1,5 @@
-use core::net::{IpAddr, SocketAddr};
+use core::cell::RefCell;
+use core::net::{IpAddr, Ipv4Addr, SocketAddr};
 
 use alloc::{format, string::String, vec::Vec};
 use embassy_net::{
@@ -8,7 +9,8 @@ use embassy_net::{
     udp::{PacketMetadata, UdpSocket},
 };
 use embassy_sync::blocking_mutex::{Mutex, raw::CriticalSectionRawMutex};
-use embassy_time::{Duration, Timer};
+use embassy_time::{Duration, Instant, Timer, with_timeout};
+use embedded_io_async::Write;
 use embedded_tls::{Aes128GcmSha256, NoVerify, TlsConfig, TlsConnection, TlsContext};
 use esp_hal::{
     rng::{Rng, Trng},
@@ -17,6 +19,9 @@ use esp_hal::{
 use esp_radio::wifi::{
     ClientConfig, ModeConfig, WifiController, WifiDevice, WifiEvent, WifiStaState,
 };
+use galvo_net::dmx::{ARTNET_PORT, DmxListener, SACN_PORT};
+use galvo_net::ether_dream::{BEACON_PORT, COMMAND_PORT, EtherDream};
+use galvo_net::idn::{IDN_PORT, IdnServer};
 use rand_core::{CryptoRng, RngCore};
 use serde_json::Value;
 use sntpc::{NtpContext, NtpTimestampGenerator, get_time};
@@ -28,6 +33,9 @@ const SSID: &str = "doggirl daycare";
 const PASSWORD: &str = "puppykittenT4T";
 
 pub type SharedRtc = Mutex<CriticalSectionRawMutex, Rtc<'static>>;
+pub type SharedDac = Mutex<CriticalSectionRawMutex, RefCell<EtherDream>>;
+pub type SharedIdn = Mutex<CriticalSectionRawMutex, RefCell<IdnServer>>;
+pub type SharedDmx = Mutex<CriticalSectionRawMutex, RefCell<DmxListener>>;
 
 const USEC_IN_SEC: u64 = 1_000_000;
 
@@ -329,3 +337,174 @@ pub async fn get_mastodon_status(stack: &Stack<'_>) -> String {
 pub async fn net_task(mut runner: Runner<'static, WifiDevice<'static>>) {
     runner.run().await
 }
+
+/// Announce the Ether Dream server to laser software on the network, once a
+/// second as the original hardware does.
+#[embassy_executor::task]
+pub async fn ether_dream_beacon(stack: Stack<'static>, dac: &'static SharedDac, mac: [u8; 6]) {
+    let mut rx_meta = [PacketMetadata::EMPTY; 1];
+    let mut rx_buffer = [0; 64];
+    let mut tx_meta = [PacketMetadata::EMPTY; 1];
+    let mut tx_buffer = [0; 64];
+
+    let mut socket = UdpSocket::new(
+        stack,
+        &mut rx_meta,
+        &mut rx_buffer,
+        &mut tx_meta,
+        &mut tx_buffer,
+    );
+    socket.bind(BEACON_PORT).unwrap();
+
+    loop {
+        let beacon = dac.lock(|dac| dac.borrow().beacon(mac));
+        let _ = socket
+            .send_to(&beacon, (Ipv4Addr::BROADCAST, BEACON_PORT))
+            .await;
+        Timer::after(Duration::from_secs(1)).await;
+    }
+}
+
+/// Take Ether Dream commands and points from one client at a time. The
+/// main loop draws the points while the client has playback going.
+#[embassy_executor::task]
+pub async fn ether_dream_server(stack: Stack<'static>, dac: &'static SharedDac) {
+    let mut rx_buffer = [0u8; 4096];
+    let mut tx_buffer = [0u8; 1024];
+    let mut buf = [0u8; 512];
+    let mut out = Vec::new();
+
+    loop {
+        let mut socket = TcpSocket::new(stack, &mut rx_buffer, &mut tx_buffer);
+        if socket.accept(COMMAND_PORT).await.is_err() {
+            continue;
+        }
+
+        dac.lock(|dac| dac.borrow_mut().connected(&mut out));
+        loop {
+            if socket.write_all(&out).await.is_err() {
+                break;
+            }
+            out.clear();
+
+            match socket.read(&mut buf).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => dac.lock(|dac| dac.borrow_mut().receive(&buf[..n], &mut out)),
+            }
+        }
+
+        dac.lock(|dac| dac.borrow_mut().disconnected());
+        out.clear();
+        socket.close();
+        let _ = socket.flush().await;
+    }
+}
+
+/// Answer IDN discovery and take IDN streams. The main loop draws what
+/// arrives while a stream is going.
+#[embassy_executor::task]
+pub async fn idn_server(stack: Stack<'static>, idn: &'static SharedIdn) {
+    let mut rx_meta = [PacketMetadata::EMPTY; 8];
+    let mut rx_buffer = [0; 8192];
+    let mut tx_meta = [PacketMetadata::EMPTY; 2];
+    let mut tx_buffer = [0; 256];
+    let mut buf = [0; 1500];
+    let mut out = Vec::new();
+
+    let mut socket = UdpSocket::new(
+        stack,
+        &mut rx_meta,
+        &mut rx_buffer,
+        &mut tx_meta,
+        &mut tx_buffer,
+    );
+    socket.bind(IDN_PORT).unwrap();
+
+    loop {
+        let Ok((n, meta)) = socket.recv_from(&mut buf).await else {
+            continue;
+        };
+        // Bad packets are dropped, but senders asking still hear about it
+        let now_us = Instant::now().as_micros();
+        let _ = idn.lock(|idn| idn.borrow_mut().receive(&buf[..n], now_us, &mut out));
+        if !out.is_empty() {
+            let _ = socket.send_to(&out, meta.endpoint).await;
+            out.clear();
+        }
+    }
+}
+
+/// Take DMX levels from lighting desks over Art-Net, broadcast or sent
+/// straight to us.
+#[embassy_executor::task]
+pub async fn artnet_listener(stack: Stack<'static>, dmx: &'static SharedDmx) {
+    let mut rx_meta = [PacketMetadata::EMPTY; 4];
+    let mut rx_buffer = [0; 2048];
+    let mut tx_meta = [PacketMetadata::EMPTY; 1];
+    let mut tx_buffer = [0; 64];
+    let mut buf = [0; 1024];
+
+    let mut socket = UdpSocket::new(
+        stack,
+        &mut rx_meta,
+        &mut rx_buffer,
+        &mut tx_meta,
+        &mut tx_buffer,
+    );
+    socket.bind(ARTNET_PORT).unwrap();
+
+    loop {
+        let Ok((n, _)) = socket.recv_from(&mut buf).await else {
+            continue;
+        };
+        let now_us = Instant::now().as_micros();
+        let _ = dmx.lock(|dmx| dmx.borrow_mut().receive_artnet(&buf[..n], now_us));
+    }
+}
+
+/// Take DMX levels from lighting desks over sACN, joining the multicast
+/// group of whichever universe we're patched to.
+#[embassy_executor::task]
+pub async fn sacn_listener(stack: Stack<'static>, dmx: &'static SharedDmx) {
+    let mut rx_meta = [PacketMetadata::EMPTY; 4];
+    let mut rx_buffer = [0; 2048];
+    let mut tx_meta = [PacketMetadata::EMPTY; 1];
+    let mut tx_buffer = [0; 64];
+    let mut buf = [0; 1024];
+    let mut joined = None;
+
+    let mut socket = UdpSocket::new(
+        stack,
+        &mut rx_meta,
+        &mut rx_buffer,
+        &mut tx_meta,
+        &mut tx_buffer,
+    );
+    socket.bind(SACN_PORT).unwrap();
+
+    loop {
+        let universe = dmx.lock(|dmx| dmx.borrow().universe());
+        if joined != Some(universe) {
+            if let Some(old) = joined {
+                let _ = stack.leave_multicast_group(sacn_group(old));
+            }
+            if stack.join_multicast_group(sacn_group(universe)).is_ok() {
+                joined = Some(universe);
+            }
+        }
+
+        // Wake up now and then to follow patch changes
+        let Ok(Ok((n, _))) = with_timeout(Duration::from_secs(1), socket.recv_from(&mut buf)).await
+        else {
+            continue;
+        };
+        let now_us = Instant::now().as_micros();
+        let _ = dmx.lock(|dmx| dmx.borrow_mut().receive_sacn(&buf[..n], now_us));
+    }
+}
+
+/// The multicast address sACN sends `universe` to.
+fn sacn_group(universe: u16) -> Ipv4Addr {
+    let [hi, lo] = universe.to_be_bytes();
+    Ipv4Addr::new(239, 255, hi, lo)
+}
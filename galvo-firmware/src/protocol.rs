@@ -1,12 +1,26 @@
+use alloc::{collections::btree_map::BTreeMap, string::String, vec::Vec};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize)]
 #[serde(tag = "cmd")]
 pub enum Command {
     SetIndicatorLight { r: u8, g: u8, b: u8 },
+    DumpStorage,
+    ClearStorage,
+    /// Show `text`, written in vector_apps' inline markup, instead of the
+    /// apps until `ClearMessage`.
+    ShowMessage { text: String },
+    ClearMessage,
+    /// Take DMX control channels from `address` of `universe`, over
+    /// Art-Net or sACN. Universes count from 1, as sACN numbers them.
+    SetDmxPatch { universe: u16, address: u16 },
 }
 
 #[derive(Serialize)]
 pub struct Response {
     pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage: Option<BTreeMap<String, Vec<u8>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
@@ -0,0 +1,120 @@
+use std::{fmt::Write as _, fs, io, path::Path as FsPath};
+
+use vector_apps::point::{Path, Point};
+
+const BLANK: (u8, u8, u8) = (0, 0, 0);
+
+/// One frame of app output.
+#[derive(Clone)]
+pub struct Frame {
+    pub number: u64,
+    pub points: Path,
+}
+
+impl Frame {
+    /// A hash of every point's position, color and delay. This is FNV-1a over
+    /// a fixed byte layout, so it is stable across platforms and Rust versions
+    /// and can be checked in as a golden value.
+    pub fn hash(&self) -> u64 {
+        const OFFSET: u64 = 0xcbf29ce484222325;
+        const PRIME: u64 = 0x100000001b3;
+
+        let mut hash = OFFSET;
+        for p in &self.points {
+            let [d0, d1] = p.delay.to_le_bytes();
+            for byte in [p.x, p.y, p.color.0, p.color.1, p.color.2, d0, d1] {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(PRIME);
+            }
+        }
+        hash
+    }
+
+    /// Lit segments as `(from, to)`. A segment takes the color of the point
+    /// it moves to, which is what the lasers show while the galvos travel.
+    fn segments(&self) -> impl Iterator<Item = (&Point, &Point)> {
+        self.points
+            .windows(2)
+            .filter(|w| w[1].color != BLANK)
+            .map(|w| (&w[0], &w[1]))
+    }
+
+    pub fn to_svg(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 256 256\" width=\"512\" height=\"512\">\n",
+        );
+        out.push_str("  <rect width=\"256\" height=\"256\" fill=\"black\"/>\n");
+
+        for (a, b) in self.segments() {
+            let _ = writeln!(
+                out,
+                "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#{:02x}{:02x}{:02x}\" stroke-linecap=\"round\"/>",
+                a.x, a.y, b.x, b.y, b.color.0, b.color.1, b.color.2
+            );
+        }
+
+        // Points the beam dwells on without moving still show up as dots
+        for p in &self.points {
+            if p.color != BLANK && p.delay > 0 {
+                let _ = writeln!(
+                    out,
+                    "  <circle cx=\"{}\" cy=\"{}\" r=\"0.5\" fill=\"#{:02x}{:02x}{:02x}\"/>",
+                    p.x, p.y, p.color.0, p.color.1, p.color.2
+                );
+            }
+        }
+
+        out.push_str("</svg>\n");
+        out
+    }
+
+    /// Rasterize the frame into an RGB8 buffer of `size * size` pixels.
+    pub fn to_rgb(&self, size: u32) -> Vec<u8> {
+        let mut pixels = vec![0u8; (size * size * 3) as usize];
+        let scale = size as f32 / 256.0;
+
+        let mut plot = |x: f32, y: f32, (r, g, b): (u8, u8, u8)| {
+            let px = (x * scale) as u32;
+            let py = (y * scale) as u32;
+            if px < size && py < size {
+                let i = ((py * size + px) * 3) as usize;
+                pixels[i] = pixels[i].max(r);
+                pixels[i + 1] = pixels[i + 1].max(g);
+                pixels[i + 2] = pixels[i + 2].max(b);
+            }
+        };
+
+        for (a, b) in self.segments() {
+            let (x0, y0, x1, y1) = (a.x as f32, a.y as f32, b.x as f32, b.y as f32);
+            let steps = ((x1 - x0).abs().max((y1 - y0).abs()) * scale)
+                .ceil()
+                .max(1.0) as u32;
+
+            for s in 0..=steps {
+                let t = s as f32 / steps as f32;
+                plot(x0 + (x1 - x0) * t + 0.5, y0 + (y1 - y0) * t + 0.5, b.color);
+            }
+        }
+
+        pixels
+    }
+
+    pub fn write_svg(&self, path: impl AsRef<FsPath>) -> io::Result<()> {
+        fs::write(path, self.to_svg())
+    }
+
+    pub fn write_png(&self, path: impl AsRef<FsPath>, size: u32) -> io::Result<()> {
+        let file = io::BufWriter::new(fs::File::create(path)?);
+
+        let mut encoder = png::Encoder::new(file, size, size);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.to_rgb(size)))
+            .map_err(io::Error::other)
+    }
+}
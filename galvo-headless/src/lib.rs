@@ -0,0 +1,13 @@
+//! Run any `VectorApp` without a window or the hardware.
+//!
+//! A [`Runner`] steps an app with scripted controls and time, and every
+//! [`Frame`] it produces can be exported to SVG or PNG and hashed, which is
+//! enough to write golden-frame regression tests.
+
+pub mod frame;
+pub mod runner;
+pub mod time;
+
+pub use frame::Frame;
+pub use runner::{Runner, Script};
+pub use time::ScriptedTime;
@@ -0,0 +1,66 @@
+use std::{env, fs, path::PathBuf, process};
+
+use galvo_headless::{Runner, ScriptedTime};
+use vector_apps::{
+    apps::{
+        VectorApp, align::Align, alphabet::AlphabetDemo, asteroids::Asteroids, clock::Clock,
+        cube::CubeDemo, ilda::Ilda, maps::Maps, marquee::Marquee, mbta::Mbta,
+    },
+    utils::ilda::{IldaFormat, IldaWriter},
+};
+
+const PNG_SIZE: u32 = 512;
+
+fn make_app(name: &str) -> Option<Box<dyn VectorApp>> {
+    Some(match name {
+        "align" => Box::new(Align::new()),
+        "alphabet" => Box::new(AlphabetDemo::new("ABCDEFGH".to_string())),
+        "asteroids" => Box::new(Asteroids::new()),
+        "clock" => Box::new(Clock::new(ScriptedTime::new(0))),
+        "cube" => Box::new(CubeDemo::new()),
+        "ilda" => Box::new(Ilda::new()),
+        "maps" => Box::new(Maps::new()),
+        "marquee" => Box::new(Marquee::new("HELLO FROM THE GALVOS")),
+        "mbta" => Box::new(Mbta::new()),
+        _ => return None,
+    })
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() < 2 {
+        eprintln!("usage: {} <app> [frames] [out-dir]", args[0]);
+        process::exit(2);
+    }
+
+    let Some(app) = make_app(&args[1]) else {
+        eprintln!("unknown app: {}", args[1]);
+        process::exit(2);
+    };
+
+    let frames: usize = args.get(2).and_then(|n| n.parse().ok()).unwrap_or(1);
+    let out_dir = PathBuf::from(args.get(3).map(String::as_str).unwrap_or("frames"));
+    fs::create_dir_all(&out_dir).unwrap();
+
+    let mut runner = Runner::new(app);
+    // Every frame also goes into an animation for other laser software
+    let mut ilda = IldaWriter::new(IldaFormat::TrueColor2d).with_name(&args[1]);
+
+    for frame in runner.run(frames) {
+        ilda.push_frame(&frame.points);
+        let stem = format!("{}-{:05}", args[1], frame.number);
+        frame
+            .write_svg(out_dir.join(format!("{}.svg", stem)))
+            .unwrap();
+        frame
+            .write_png(out_dir.join(format!("{}.png", stem)), PNG_SIZE)
+            .unwrap();
+        println!("{} {:016x}", stem, frame.hash());
+    }
+
+    match ilda.to_bytes() {
+        Ok(file) => fs::write(out_dir.join(format!("{}.ild", args[1])), file).unwrap(),
+        Err(e) => eprintln!("can't save an ILDA file: {}", e),
+    }
+}
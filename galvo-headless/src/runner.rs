@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use vector_apps::apps::{Controls, VectorApp};
+
+use crate::{frame::Frame, time::ScriptedTime};
+
+/// How often the firmware polls the nunchuck, in frames.
+pub const FIRMWARE_CONTROL_INTERVAL: u64 = 4;
+
+/// Scripted input: the controls held from a given frame onwards, and when
+/// the scripted clock jumps.
+#[derive(Clone, Debug, Default)]
+pub struct Script {
+    controls: BTreeMap<u64, Controls>,
+    times: BTreeMap<u64, u64>,
+}
+
+impl Script {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold `controls` from `frame` until the next scripted change.
+    pub fn controls(mut self, frame: u64, controls: Controls) -> Self {
+        self.controls.insert(frame, controls);
+        self
+    }
+
+    /// Press `controls` at `frame` for a single control poll, then release.
+    pub fn press(mut self, frame: u64, controls: Controls) -> Self {
+        self.controls.insert(frame, controls);
+        self.controls
+            .entry(frame + FIRMWARE_CONTROL_INTERVAL)
+            .or_default();
+        self
+    }
+
+    /// Set the scripted clock to `seconds` at `frame`.
+    pub fn time(mut self, frame: u64, seconds: u64) -> Self {
+        self.times.insert(frame, seconds);
+        self
+    }
+
+    fn controls_at(&self, frame: u64) -> Controls {
+        self.controls
+            .range(..=frame)
+            .next_back()
+            .map(|(_, c)| *c)
+            .unwrap_or_default()
+    }
+}
+
+/// Steps a `VectorApp` the same way the firmware main loop does: controls are
+/// polled every `control_interval` frames, then the frame counter advances
+/// and the path is fetched.
+pub struct Runner<A: VectorApp> {
+    app: A,
+    script: Script,
+    frame: u64,
+    control_interval: u64,
+    time: Option<(ScriptedTime, u64)>,
+}
+
+impl<A: VectorApp> Runner<A> {
+    pub fn new(app: A) -> Self {
+        Self {
+            app,
+            script: Script::new(),
+            frame: 0,
+            control_interval: FIRMWARE_CONTROL_INTERVAL,
+            time: None,
+        }
+    }
+
+    pub fn with_script(mut self, script: Script) -> Self {
+        self.script = script;
+        self
+    }
+
+    /// Poll controls every `interval` frames (0 disables polling).
+    pub fn with_control_interval(mut self, interval: u64) -> Self {
+        self.control_interval = interval;
+        self
+    }
+
+    /// Advance `time` by `seconds_per_frame` after every frame, on top of any
+    /// jumps in the script.
+    pub fn with_time(mut self, time: ScriptedTime, seconds_per_frame: u64) -> Self {
+        self.time = Some((time, seconds_per_frame));
+        self
+    }
+
+    pub fn app(&mut self) -> &mut A {
+        &mut self.app
+    }
+
+    /// Run a single frame.
+    pub fn step(&mut self) -> Frame {
+        if let Some((time, _)) = &self.time
+            && let Some(&seconds) = self.script.times.get(&self.frame)
+        {
+            time.set(seconds);
+        }
+
+        if self.control_interval != 0 && self.frame.is_multiple_of(self.control_interval) {
+            self.app
+                .handle_controls(self.script.controls_at(self.frame));
+        }
+
+        self.frame += 1;
+
+        let frame = Frame {
+            number: self.frame,
+            points: self.app.get_path(self.frame).clone(),
+        };
+
+        if let Some((time, seconds_per_frame)) = &self.time {
+            time.advance(*seconds_per_frame);
+        }
+
+        frame
+    }
+
+    /// Run `n` frames and collect them all.
+    pub fn run(&mut self, n: usize) -> Vec<Frame> {
+        (0..n).map(|_| self.step()).collect()
+    }
+}
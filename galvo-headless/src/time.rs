@@ -0,0 +1,33 @@
+use std::{cell::Cell, rc::Rc};
+
+use vector_apps::apps::clock::TimeSource;
+
+/// A `TimeSource` whose clock is advanced by the [`Runner`](crate::Runner)
+/// instead of the wall clock. Clones share the same time, so one handle can
+/// be given to the app and another kept for the runner.
+#[derive(Clone, Debug, Default)]
+pub struct ScriptedTime {
+    now: Rc<Cell<u64>>,
+}
+
+impl ScriptedTime {
+    pub fn new(start: u64) -> Self {
+        Self {
+            now: Rc::new(Cell::new(start)),
+        }
+    }
+
+    pub fn set(&self, seconds: u64) {
+        self.now.set(seconds);
+    }
+
+    pub fn advance(&self, seconds: u64) {
+        self.now.set(self.now.get() + seconds);
+    }
+}
+
+impl TimeSource for ScriptedTime {
+    fn now(&self) -> u64 {
+        self.now.get()
+    }
+}
@@ -0,0 +1,347 @@
+//! Golden-frame regression tests.
+//!
+//! Each test renders some frames and compares their hashes with the values
+//! checked in under `tests/golden/`. After an intentional visual change, run
+//! with `GALVO_BLESS=1` to rewrite the golden files. On a mismatch, the
+//! offending frames are written as SVGs to the cargo test temp directory.
+
+use std::{env, fs, path::PathBuf};
+
+use galvo_headless::{Frame, Runner, Script, ScriptedTime};
+use vector_apps::{
+    apps::{
+        Controls, alphabet::AlphabetDemo, asteroids::Asteroids, clock::Clock, cube::CubeDemo,
+        ilda::Ilda, maps::Maps, marquee::Marquee, message::Message,
+    },
+    utils::{
+        font_style::FontStyle,
+        ilda::read_ilda,
+        layout::{HAlign, Rect, TextLayout, VAlign},
+        math::Affine,
+        path_text::{Polyline, text_along_path},
+        scanner::ScannerProfile,
+        text::{DEFAULT_FONT, UNICODE_FONT, text_to_path},
+        text_animation::{TextAnimation, TextEffect},
+    },
+};
+
+fn check(name: &str, frames: &[Frame]) {
+    let golden = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.txt", name));
+
+    let actual: String = frames
+        .iter()
+        .map(|f| format!("{} {:016x}\n", f.number, f.hash()))
+        .collect();
+
+    if env::var_os("GALVO_BLESS").is_some() {
+        fs::write(&golden, &actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&golden).unwrap_or_else(|_| {
+        panic!(
+            "missing golden file {}, run with GALVO_BLESS=1 to create it",
+            golden.display()
+        )
+    });
+
+    if expected != actual {
+        let out_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+        fs::create_dir_all(&out_dir).unwrap();
+
+        for (frame, line) in frames.iter().zip(actual.lines()) {
+            if !expected.lines().any(|l| l == line) {
+                frame
+                    .write_svg(out_dir.join(format!("{:05}.svg", frame.number)))
+                    .unwrap();
+            }
+        }
+
+        panic!(
+            "{} does not match its golden frames, see {}\nexpected:\n{}actual:\n{}",
+            name,
+            out_dir.display(),
+            expected,
+            actual
+        );
+    }
+}
+
+fn still(points: vector_apps::point::Path) -> Frame {
+    Frame { number: 0, points }
+}
+
+#[test]
+fn text() {
+    check(
+        "text",
+        &[
+            still(text_to_path(
+                DEFAULT_FONT,
+                "Hello",
+                0,
+                16,
+                1.0,
+                1.0,
+                (255, 0, 0),
+            )),
+            still(text_to_path(
+                DEFAULT_FONT,
+                "Aa1!?",
+                20,
+                128,
+                2.0,
+                1.5,
+                (0, 255, 0),
+            )),
+            still(text_to_path(DEFAULT_FONT, "", 0, 0, 1.0, 1.0, (0, 0, 255))),
+        ],
+    );
+}
+
+#[test]
+fn fallback() {
+    check(
+        "fallback",
+        &[
+            // Past the end of every font's table, and a control character
+            still(text_to_path(
+                DEFAULT_FONT,
+                "\u{10FFFF}\u{7}é",
+                0,
+                32,
+                4.0,
+                4.0,
+                (255, 0, 0),
+            )),
+            still(text_to_path(
+                UNICODE_FONT,
+                "Ωé→✓",
+                0,
+                128,
+                2.0,
+                2.0,
+                (0, 255, 0),
+            )),
+        ],
+    );
+}
+
+#[test]
+fn path_text() {
+    use core::f32::consts::PI;
+
+    let arc = Polyline::arc((128.0, 128.0), 100.0, PI, 2.0 * PI);
+    let zigzag = Polyline::new(vec![(0.0, 200.0), (128.0, 140.0), (256.0, 200.0)]);
+
+    check(
+        "path_text",
+        &[
+            still(text_along_path(
+                DEFAULT_FONT,
+                "AROUND THE CLOCK",
+                &arc,
+                HAlign::Center,
+                3.0,
+                |_| (255, 255, 0),
+            )),
+            still(text_along_path(
+                DEFAULT_FONT,
+                "Main St",
+                &zigzag,
+                HAlign::Left,
+                4.0,
+                |_| (0, 255, 255),
+            )),
+        ],
+    );
+}
+
+#[test]
+fn alphabet() {
+    let mut runner = Runner::new(AlphabetDemo::new("ABCDEFGHIJKLMNOP".to_string()));
+    let frames = runner.run(80);
+    check(
+        "alphabet",
+        &frames.iter().step_by(16).cloned().collect::<Vec<_>>(),
+    );
+}
+
+#[test]
+fn text_animation() {
+    let layout = TextLayout::new(DEFAULT_FONT, Rect::new(0.0, 0.0, 256.0, 256.0))
+        .align(HAlign::Center, VAlign::Middle)
+        .scale(4.0, 4.0);
+
+    let effects = [
+        TextEffect::Typewriter { rate: 0.5 },
+        TextEffect::Wave {
+            amplitude: 2.0,
+            wavelength: 4.0,
+            speed: 0.03,
+        },
+        TextEffect::Rainbow {
+            spread: 0.1,
+            speed: 0.01,
+        },
+        TextEffect::Jitter {
+            amount: 1.0,
+            every: 3.0,
+        },
+        TextEffect::ScaleIn {
+            duration: 8.0,
+            stagger: 2.0,
+        },
+    ];
+
+    let frames: Vec<_> = effects
+        .into_iter()
+        .flat_map(|effect| {
+            let animation = TextAnimation::new().with(effect);
+            let layout = &layout;
+            [0u64, 5, 20].map(move |time| Frame {
+                number: time,
+                points: layout.to_path_animated("Lively", &animation, time as f32, (255, 255, 255)),
+            })
+        })
+        .collect();
+
+    check("text_animation", &frames);
+}
+
+#[test]
+fn markup() {
+    let mut runner = Runner::new(
+        Message::new("{red}HELLO {size=2}{font=hershey:romans}world\n{cyan}{{braces}} {/}ok")
+            .unwrap(),
+    );
+    check("markup", &runner.run(1));
+}
+
+#[test]
+fn font_style() {
+    let layout = |style| {
+        TextLayout::new(UNICODE_FONT, Rect::new(0.0, 64.0, 256.0, 128.0))
+            .align(HAlign::Center, VAlign::Middle)
+            .scale(2.0, 2.0)
+            .fit(true)
+            .style(style)
+    };
+    let vertical = FontStyle::new().with_vertical(true).to_path(
+        UNICODE_FONT,
+        "UP",
+        &Affine::scale(2.0, 2.0).then(Affine::translate(128.0, 32.0)),
+        (255, 128, 0),
+    );
+
+    check(
+        "font_style",
+        &[
+            still(layout(FontStyle::new().with_bold(2, 1.0)).to_path("Bold", (255, 255, 0))),
+            still(layout(FontStyle::new().with_italic(0.25)).to_path("Italic", (0, 255, 255))),
+            still(layout(FontStyle::new().with_rotation(-0.5)).to_path("Tilt", (0, 255, 0))),
+            still(vertical),
+        ],
+    );
+}
+
+#[test]
+fn cube() {
+    let mut runner = Runner::new(CubeDemo::new());
+    check("cube", &runner.run(8));
+}
+
+#[test]
+fn maps() {
+    let right = Controls {
+        x: 1,
+        ..Default::default()
+    };
+    let up = Controls {
+        y: 1,
+        ..Default::default()
+    };
+
+    let script = Script::new()
+        .controls(4, right)
+        .controls(12, up)
+        .controls(20, Controls::default());
+    let mut runner = Runner::new(Maps::new()).with_script(script);
+
+    let frames = runner.run(24);
+    check(
+        "maps",
+        &[frames[0].clone(), frames[11].clone(), frames[23].clone()],
+    );
+}
+
+#[test]
+fn asteroids() {
+    let thrust = Controls {
+        x: -1,
+        y: 1,
+        ..Default::default()
+    };
+    let fire = Controls {
+        b: true,
+        ..Default::default()
+    };
+
+    let script = Script::new()
+        .controls(0, thrust)
+        .press(40, fire)
+        .press(60, fire);
+    let mut runner = Runner::new(Asteroids::new()).with_script(script);
+
+    let frames = runner.run(100);
+    check(
+        "asteroids",
+        &frames[..].iter().step_by(10).cloned().collect::<Vec<_>>(),
+    );
+}
+
+#[test]
+fn clock() {
+    let time = ScriptedTime::new(0);
+    let script = Script::new().time(0, 1_700_000_000).time(3, 1_700_003_599);
+    let mut runner = Runner::new(Clock::new(time.clone()))
+        .with_script(script)
+        .with_time(time, 1);
+
+    check("clock", &runner.run(5));
+}
+
+#[test]
+fn marquee() {
+    let mut runner = Runner::new(Marquee::new("Hello, world!").with_speed(3.0));
+    let mut frames = runner.run(60);
+
+    runner.app().set_message("Bye");
+    frames.extend(runner.run(40));
+
+    check(
+        "marquee",
+        &frames.iter().step_by(10).cloned().collect::<Vec<_>>(),
+    );
+}
+
+#[test]
+fn ilda() {
+    let mut runner = Runner::new(Ilda::new());
+    check("ilda", &runner.run(1));
+}
+
+#[test]
+fn ilda_parser() {
+    let profile = ScannerProfile::new().with_points_per_second(30_000);
+    let paths = read_ilda(
+        include_bytes!("../../vector-apps/src/apps/ildatest.ild"),
+        &profile,
+    )
+    .unwrap();
+
+    let frames: Vec<Frame> = paths.into_values().map(still).collect();
+    check("ilda_parser", &frames);
+}
@@ -0,0 +1,334 @@
+//! DMX over Art-Net and sACN (E1.31), so a lighting desk can run the
+//! projector like any other fixture.
+//!
+//! The projector takes [`CHANNELS`] channels from its start address:
+//!
+//! | Channel | Control                                             |
+//! |---------|-----------------------------------------------------|
+//! | 1       | Master dimmer                                       |
+//! | 2       | App select: 0 leaves it alone, then 8 values an app |
+//! | 3-5     | Color override, red, green and blue; all 0 for off  |
+//! | 6       | Size, 255 for full size                             |
+//! | 7-8     | X and Y position, 128 for the middle                |
+//! | 9       | Rotation, a whole turn over the range               |
+//! | 10      | Effect speed, 128 for normal and 0 for stopped      |
+
+use core::{f32::consts::TAU, fmt};
+
+use vector_apps::{
+    point::{Path, Point},
+    utils::math::Affine,
+};
+
+pub const ARTNET_PORT: u16 = 6454;
+pub const SACN_PORT: u16 = 5568;
+
+pub const CHANNELS: usize = 10;
+/// Slots in a DMX universe.
+pub const UNIVERSE_LEN: usize = 512;
+/// The highest universe sACN can send, counting from 1.
+pub const MAX_UNIVERSE: u16 = 63999;
+/// The effect speed channel's value for apps running as they normally do.
+pub const NORMAL_SPEED: u8 = 128;
+
+/// Desks that stop sending for this long have let go, the data loss
+/// timeout sACN gives.
+pub const TIMEOUT_US: u64 = 2_500_000;
+
+const ARTNET_ID: &[u8; 8] = b"Art-Net\0";
+const OP_DMX: u16 = 0x5000;
+const ARTNET_HEADER_LEN: usize = 18;
+
+const ACN_ID: &[u8; 12] = b"ASC-E1.17\0\0\0";
+const VECTOR_ROOT_DATA: u32 = 0x04;
+const VECTOR_FRAMING_DATA: u32 = 0x02;
+const SACN_HEADER_LEN: usize = 126;
+/// Options bit of data meant for visualizers rather than fixtures.
+const OPTION_PREVIEW: u8 = 0x80;
+/// Options bit of the last packet a source will send.
+const OPTION_TERMINATED: u8 = 0x40;
+/// The start code of ordinary dimmer data.
+const START_CODE_DMX: u8 = 0x00;
+
+/// Values a desk gives each app on the app select channel.
+const APP_SELECT_STEP: u8 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DmxError {
+    /// The packet is shorter than its headers say.
+    Truncated,
+    /// The packet isn't Art-Net or sACN.
+    Unrecognized,
+    /// A start address that doesn't leave room for every channel.
+    BadAddress(u16),
+    /// A universe outside the 1 to [`MAX_UNIVERSE`] sACN can send.
+    BadUniverse(u16),
+}
+
+impl fmt::Display for DmxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DmxError::Truncated => write!(f, "packet ends early"),
+            DmxError::Unrecognized => write!(f, "not an Art-Net or sACN packet"),
+            DmxError::BadAddress(address) => write!(
+                f,
+                "start address {} doesn't leave room for {} channels",
+                address, CHANNELS
+            ),
+            DmxError::BadUniverse(universe) => write!(
+                f,
+                "universe {} isn't between 1 and {}",
+                universe, MAX_UNIVERSE
+            ),
+        }
+    }
+}
+
+impl core::error::Error for DmxError {}
+
+/// Levels for one universe out of a packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DmxData<'a> {
+    pub universe: u16,
+    /// Levels from address 1, possibly fewer than a whole universe.
+    pub slots: &'a [u8],
+    /// The source has stopped sending.
+    pub terminated: bool,
+}
+
+/// Levels in an ArtDmx packet, or `None` for other Art-Net packets.
+pub fn parse_artnet(packet: &[u8]) -> Result<Option<DmxData<'_>>, DmxError> {
+    let id = packet.get(..ARTNET_ID.len()).ok_or(DmxError::Truncated)?;
+    if id != ARTNET_ID {
+        return Err(DmxError::Unrecognized);
+    }
+    let header = packet.get(..ARTNET_HEADER_LEN).ok_or(DmxError::Truncated)?;
+    if u16::from_le_bytes([header[8], header[9]]) != OP_DMX {
+        return Ok(None);
+    }
+
+    // Sub-net and universe, then net, making a 15 bit port address
+    let universe = u16::from_le_bytes([header[14], header[15] & 0x7f]);
+    let len = u16::from_be_bytes([header[16], header[17]]) as usize;
+    let slots = packet
+        .get(ARTNET_HEADER_LEN..ARTNET_HEADER_LEN + len)
+        .ok_or(DmxError::Truncated)?;
+    Ok(Some(DmxData {
+        universe,
+        slots,
+        terminated: false,
+    }))
+}
+
+/// Levels in an E1.31 data packet, or `None` for sync and discovery
+/// packets, preview data and alternate start codes.
+pub fn parse_sacn(packet: &[u8]) -> Result<Option<DmxData<'_>>, DmxError> {
+    let id = packet.get(4..16).ok_or(DmxError::Truncated)?;
+    if id != ACN_ID {
+        return Err(DmxError::Unrecognized);
+    }
+    let header = packet.get(..SACN_HEADER_LEN).ok_or(DmxError::Truncated)?;
+    let vector = |at: usize| u32::from_be_bytes(header[at..at + 4].try_into().unwrap());
+    if vector(18) != VECTOR_ROOT_DATA || vector(40) != VECTOR_FRAMING_DATA {
+        return Ok(None);
+    }
+
+    let options = header[112];
+    let universe = u16::from_be_bytes([header[113], header[114]]);
+    // The start code counts as a property value
+    let count = u16::from_be_bytes([header[123], header[124]]) as usize;
+    if options & OPTION_PREVIEW != 0 || header[125] != START_CODE_DMX {
+        return Ok(None);
+    }
+    let slots = packet
+        .get(SACN_HEADER_LEN..SACN_HEADER_LEN + count.saturating_sub(1))
+        .ok_or(DmxError::Truncated)?;
+    Ok(Some(DmxData {
+        universe,
+        slots,
+        terminated: options & OPTION_TERMINATED != 0,
+    }))
+}
+
+/// What the desk has the projector's channels at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DmxControls {
+    pub dimmer: u8,
+    pub app: u8,
+    pub color: (u8, u8, u8),
+    pub size: u8,
+    pub x: u8,
+    pub y: u8,
+    pub rotation: u8,
+    pub speed: u8,
+}
+
+impl DmxControls {
+    /// Read the channels from the projector's own `slots`, in order.
+    pub fn from_slots(slots: &[u8; CHANNELS]) -> Self {
+        let [dimmer, app, r, g, b, size, x, y, rotation, speed] = *slots;
+        Self {
+            dimmer,
+            app,
+            color: (r, g, b),
+            size,
+            x,
+            y,
+            rotation,
+            speed,
+        }
+    }
+
+    /// Which of `count` apps the desk has picked, if any. Values past the
+    /// last app pick the last app.
+    pub fn app_index(&self, count: usize) -> Option<usize> {
+        if self.app == 0 || count == 0 {
+            return None;
+        }
+        Some((((self.app - 1) / APP_SELECT_STEP) as usize).min(count - 1))
+    }
+
+    /// Where the size, position and rotation channels move points to.
+    pub fn transform(&self) -> Affine {
+        let scale = self.size as f32 / 255.0;
+        let angle = self.rotation as f32 / 256.0 * TAU;
+        // Our y runs down, and desks expect raising it to go up
+        let (dx, dy) = (self.x as f32 - 128.0, 128.0 - self.y as f32);
+        Affine::translate(-127.5, -127.5)
+            .then(Affine::scale(scale, scale))
+            .then(Affine::rotate(angle))
+            .then(Affine::translate(127.5 + dx, 127.5 + dy))
+    }
+
+    /// `path` as the desk has it: moved, recolored and dimmed. Blanked
+    /// points stay blanked.
+    pub fn apply(&self, path: &[Point]) -> Path {
+        let transform = self.transform();
+        let dim = |c: u8| (c as u16 * self.dimmer as u16 / 255) as u8;
+
+        path.iter()
+            .map(|p| {
+                let (x, y) = transform.apply(p.x as f32, p.y as f32);
+                let lit = p.color != (0, 0, 0);
+                let (r, g, b) = if lit && self.color != (0, 0, 0) {
+                    self.color
+                } else {
+                    p.color
+                };
+                Point {
+                    x: libm::roundf(x).clamp(0.0, 255.0) as u8,
+                    y: libm::roundf(y).clamp(0.0, 255.0) as u8,
+                    color: (dim(r), dim(g), dim(b)),
+                    delay: p.delay,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Picks the projector's channels out of whatever Art-Net and sACN arrives.
+pub struct DmxListener {
+    universe: u16,
+    address: u16,
+    controls: Option<DmxControls>,
+    /// When the last levels came, in the caller's microseconds.
+    last_us: u64,
+}
+
+impl Default for DmxListener {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DmxListener {
+    /// Listen from address 1 of universe 1.
+    pub fn new() -> Self {
+        Self {
+            universe: 1,
+            address: 1,
+            controls: None,
+            last_us: 0,
+        }
+    }
+
+    /// Listen from `address` of `universe`, counting universes from 1 as
+    /// sACN does. Art-Net counts from 0, so it's port address `universe - 1`
+    /// there.
+    pub fn with_patch(mut self, universe: u16, address: u16) -> Result<Self, DmxError> {
+        self.set_patch(universe, address)?;
+        Ok(self)
+    }
+
+    /// Move to `address` of `universe`, forgetting levels from before.
+    pub fn set_patch(&mut self, universe: u16, address: u16) -> Result<(), DmxError> {
+        if !(1..=MAX_UNIVERSE).contains(&universe) {
+            return Err(DmxError::BadUniverse(universe));
+        }
+        if address == 0 || address as usize + CHANNELS - 1 > UNIVERSE_LEN {
+            return Err(DmxError::BadAddress(address));
+        }
+        self.universe = universe;
+        self.address = address;
+        self.controls = None;
+        Ok(())
+    }
+
+    pub fn universe(&self) -> u16 {
+        self.universe
+    }
+
+    pub fn address(&self) -> u16 {
+        self.address
+    }
+
+    /// The Art-Net port address our universe goes out on.
+    pub fn artnet_port(&self) -> u16 {
+        self.universe - 1
+    }
+
+    /// Handle an Art-Net packet that arrived at `now_us`.
+    pub fn receive_artnet(&mut self, packet: &[u8], now_us: u64) -> Result<(), DmxError> {
+        if let Some(data) = parse_artnet(packet)?
+            && data.universe == self.artnet_port()
+        {
+            self.take(data, now_us);
+        }
+        Ok(())
+    }
+
+    /// Handle an sACN packet that arrived at `now_us`.
+    pub fn receive_sacn(&mut self, packet: &[u8], now_us: u64) -> Result<(), DmxError> {
+        if let Some(data) = parse_sacn(packet)?
+            && data.universe == self.universe
+        {
+            self.take(data, now_us);
+        }
+        Ok(())
+    }
+
+    fn take(&mut self, data: DmxData, now_us: u64) {
+        if data.terminated {
+            self.controls = None;
+            return;
+        }
+
+        // Desks may send fewer slots than a whole universe, which are at 0
+        let mut slots = [0; CHANNELS];
+        let start = self.address as usize - 1;
+        for (slot, &level) in slots.iter_mut().zip(data.slots.iter().skip(start)) {
+            *slot = level;
+        }
+        self.controls = Some(DmxControls::from_slots(&slots));
+        self.last_us = now_us;
+    }
+
+    /// What the desk has the channels at, at `now_us`, or `None` when no
+    /// desk is sending.
+    pub fn controls(&mut self, now_us: u64) -> Option<DmxControls> {
+        if now_us.saturating_sub(self.last_us) > TIMEOUT_US {
+            self.controls = None;
+        }
+        self.controls
+    }
+}
@@ -0,0 +1,503 @@
+//! The Ether Dream DAC protocol, so laser software that drives an Ether
+//! Dream can drive the projector instead.
+//!
+//! The DAC broadcasts [`EtherDream::beacon`] on UDP port [`BEACON_PORT`]
+//! once a second. Software connects on TCP port [`COMMAND_PORT`] and sends
+//! commands, each answered with an ACK or NAK and the DAC's status. Points
+//! are queued in a buffer, then drawn from it at the point rate once
+//! playback begins.
+
+use alloc::{collections::VecDeque, vec::Vec};
+
+use vector_apps::point::{Path, Point};
+
+pub const BEACON_PORT: u16 = 7654;
+pub const COMMAND_PORT: u16 = 7765;
+
+/// Points the buffer holds, the same as the original hardware.
+pub const DEFAULT_CAPACITY: u16 = 1799;
+pub const DEFAULT_MAX_POINT_RATE: u32 = 30_000;
+/// Rate changes that can be queued ahead of the points that use them.
+const RATE_QUEUE_LEN: usize = 16;
+
+const HARDWARE_REVISION: u16 = 0;
+const SOFTWARE_REVISION: u16 = 2;
+
+pub const ACK: u8 = b'a';
+/// NAK for data that doesn't fit in the buffer, or a full rate queue.
+pub const NAK_FULL: u8 = b'F';
+/// NAK for a command that's unknown or not allowed in the playback state.
+pub const NAK_INVALID: u8 = b'I';
+/// NAK for a command refused because of an emergency stop.
+pub const NAK_STOP: u8 = b'!';
+
+pub const PREPARE: u8 = b'p';
+pub const BEGIN: u8 = b'b';
+pub const QUEUE_RATE: u8 = b'q';
+pub const DATA: u8 = b'd';
+pub const STOP: u8 = b's';
+pub const EMERGENCY_STOP: u8 = 0x00;
+/// Also an emergency stop, for software that sends all ones.
+pub const EMERGENCY_STOP_ALT: u8 = 0xff;
+pub const CLEAR_EMERGENCY_STOP: u8 = b'c';
+pub const PING: u8 = b'?';
+
+/// Point control bit that makes the point take the next queued rate.
+pub const RATE_CHANGE_BIT: u16 = 0x8000;
+
+/// Light engine flag of an emergency stop sent over the network.
+pub const LIGHT_ENGINE_ESTOP_NETWORK: u16 = 1 << 0;
+/// Playback flag of the shutter being open, which it is while playing.
+pub const PLAYBACK_SHUTTER_OPEN: u16 = 1 << 0;
+/// Playback flag of the buffer running out while playing.
+pub const PLAYBACK_UNDERFLOW: u16 = 1 << 1;
+/// Playback flag of playback ending in an emergency stop.
+pub const PLAYBACK_ESTOP: u16 = 1 << 2;
+
+pub const STATUS_LEN: usize = 20;
+pub const RESPONSE_LEN: usize = 2 + STATUS_LEN;
+pub const BEACON_LEN: usize = 16 + STATUS_LEN;
+pub const POINT_LEN: usize = 18;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LightEngine {
+    Ready = 0,
+    Warmup = 1,
+    Cooldown = 2,
+    EmergencyStop = 3,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Playback {
+    Idle = 0,
+    /// Taking points, but not drawing them yet.
+    Prepared = 1,
+    Playing = 2,
+}
+
+/// What the DAC sends with every response and beacon.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DacStatus {
+    pub light_engine: LightEngine,
+    pub playback: Playback,
+    pub light_engine_flags: u16,
+    pub playback_flags: u16,
+    /// Points in the buffer.
+    pub buffer_fullness: u16,
+    pub point_rate: u32,
+    /// Points drawn since playback began.
+    pub point_count: u32,
+}
+
+impl DacStatus {
+    pub fn encode(&self) -> [u8; STATUS_LEN] {
+        let mut out = [0; STATUS_LEN];
+        out[0] = 0; // protocol
+        out[1] = self.light_engine as u8;
+        out[2] = self.playback as u8;
+        out[3] = 0; // source, which is always the network
+        out[4..6].copy_from_slice(&self.light_engine_flags.to_le_bytes());
+        out[6..8].copy_from_slice(&self.playback_flags.to_le_bytes());
+        // Source flags stay zero
+        out[10..12].copy_from_slice(&self.buffer_fullness.to_le_bytes());
+        out[12..16].copy_from_slice(&self.point_rate.to_le_bytes());
+        out[16..20].copy_from_slice(&self.point_count.to_le_bytes());
+        out
+    }
+
+    /// The status in `bytes`, or `None` if a state is out of range.
+    pub fn decode(bytes: &[u8; STATUS_LEN]) -> Option<Self> {
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+        let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        let light_engine = match bytes[1] {
+            0 => LightEngine::Ready,
+            1 => LightEngine::Warmup,
+            2 => LightEngine::Cooldown,
+            3 => LightEngine::EmergencyStop,
+            _ => return None,
+        };
+        let playback = match bytes[2] {
+            0 => Playback::Idle,
+            1 => Playback::Prepared,
+            2 => Playback::Playing,
+            _ => return None,
+        };
+        Some(Self {
+            light_engine,
+            playback,
+            light_engine_flags: u16_at(4),
+            playback_flags: u16_at(6),
+            buffer_fullness: u16_at(10),
+            point_rate: u32_at(12),
+            point_count: u32_at(16),
+        })
+    }
+}
+
+/// A point as the protocol sends it, with coordinates from -32768 to 32767
+/// up and to the right and 16 bit colors.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct DacPoint {
+    pub control: u16,
+    pub x: i16,
+    pub y: i16,
+    pub r: u16,
+    pub g: u16,
+    pub b: u16,
+    /// Intensity, which has no laser of its own here.
+    pub i: u16,
+    pub u1: u16,
+    pub u2: u16,
+}
+
+impl DacPoint {
+    pub fn encode(&self) -> [u8; POINT_LEN] {
+        let mut out = [0; POINT_LEN];
+        let fields = [
+            self.control,
+            self.x as u16,
+            self.y as u16,
+            self.r,
+            self.g,
+            self.b,
+            self.i,
+            self.u1,
+            self.u2,
+        ];
+        for (bytes, field) in out.chunks_exact_mut(2).zip(fields) {
+            bytes.copy_from_slice(&field.to_le_bytes());
+        }
+        out
+    }
+
+    pub fn decode(bytes: &[u8; POINT_LEN]) -> Self {
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+        Self {
+            control: u16_at(0),
+            x: u16_at(2) as i16,
+            y: u16_at(4) as i16,
+            r: u16_at(6),
+            g: u16_at(8),
+            b: u16_at(10),
+            i: u16_at(12),
+            u1: u16_at(14),
+            u2: u16_at(16),
+        }
+    }
+}
+
+/// A point waiting in the buffer, already in DAC units, which takes a
+/// quarter of the memory of a [`DacPoint`].
+#[derive(Clone, Copy)]
+struct Queued {
+    point: Point,
+    rate_change: bool,
+}
+
+impl Queued {
+    fn new(point: &DacPoint) -> Self {
+        let dac = |v: i16| ((v as i32 + 32768) >> 8) as u8;
+        let color = |v: u16| (v >> 8) as u8;
+        Self {
+            point: Point {
+                x: dac(point.x),
+                // Our y runs down
+                y: 255 - dac(point.y),
+                color: (color(point.r), color(point.g), color(point.b)),
+                delay: 0,
+            },
+            rate_change: point.control & RATE_CHANGE_BIT != 0,
+        }
+    }
+}
+
+/// Where the command stream is up to.
+#[derive(Copy, Clone)]
+enum Reading {
+    Command,
+    /// Points of a data command, which is answered with `response` once
+    /// they've all arrived.
+    Points {
+        left: u16,
+        response: u8,
+    },
+}
+
+/// Bytes in the command starting with `command`, counting itself. Unknown
+/// commands are just the one byte.
+fn command_len(command: u8) -> usize {
+    match command {
+        BEGIN => 7,
+        QUEUE_RATE => 5,
+        DATA => 3,
+        _ => 1,
+    }
+}
+
+/// The DAC end of the protocol. One client is served at a time, as the
+/// original hardware does.
+pub struct EtherDream {
+    capacity: u16,
+    max_point_rate: u32,
+    light_engine: LightEngine,
+    playback: Playback,
+    light_engine_flags: u16,
+    playback_flags: u16,
+    point_rate: u32,
+    point_count: u32,
+    points: VecDeque<Queued>,
+    rates: VecDeque<u32>,
+    /// Received bytes that don't make a whole command or point yet.
+    pending: Vec<u8>,
+    reading: Reading,
+}
+
+impl Default for EtherDream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EtherDream {
+    pub fn new() -> Self {
+        Self {
+            capacity: DEFAULT_CAPACITY,
+            max_point_rate: DEFAULT_MAX_POINT_RATE,
+            light_engine: LightEngine::Ready,
+            playback: Playback::Idle,
+            light_engine_flags: 0,
+            playback_flags: 0,
+            point_rate: 0,
+            point_count: 0,
+            points: VecDeque::new(),
+            rates: VecDeque::new(),
+            pending: Vec::new(),
+            reading: Reading::Command,
+        }
+    }
+
+    /// Points the buffer holds.
+    pub fn with_capacity(mut self, capacity: u16) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Fastest point rate clients can ask for.
+    pub fn with_max_point_rate(mut self, rate: u32) -> Self {
+        self.max_point_rate = rate;
+        self
+    }
+
+    pub fn status(&self) -> DacStatus {
+        DacStatus {
+            light_engine: self.light_engine,
+            playback: self.playback,
+            light_engine_flags: self.light_engine_flags,
+            playback_flags: self.playback_flags,
+            buffer_fullness: self.points.len() as u16,
+            point_rate: self.point_rate,
+            point_count: self.point_count,
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playback == Playback::Playing
+    }
+
+    /// The broadcast that lets software find the DAC, from the device with
+    /// hardware address `mac`.
+    pub fn beacon(&self, mac: [u8; 6]) -> [u8; BEACON_LEN] {
+        let mut out = [0; BEACON_LEN];
+        out[..6].copy_from_slice(&mac);
+        out[6..8].copy_from_slice(&HARDWARE_REVISION.to_le_bytes());
+        out[8..10].copy_from_slice(&SOFTWARE_REVISION.to_le_bytes());
+        out[10..12].copy_from_slice(&self.capacity.to_le_bytes());
+        out[12..16].copy_from_slice(&self.max_point_rate.to_le_bytes());
+        out[16..].copy_from_slice(&self.status().encode());
+        out
+    }
+
+    fn respond(&self, response: u8, command: u8, out: &mut Vec<u8>) {
+        out.extend([response, command]);
+        out.extend(self.status().encode());
+    }
+
+    /// A client connected, which is greeted with the status as if it had
+    /// pinged.
+    pub fn connected(&mut self, out: &mut Vec<u8>) {
+        self.pending.clear();
+        self.reading = Reading::Command;
+        self.respond(ACK, PING, out);
+    }
+
+    /// The client went away, which stops playback.
+    pub fn disconnected(&mut self) {
+        self.stop();
+        self.pending.clear();
+        self.reading = Reading::Command;
+    }
+
+    /// Handle `bytes` from the client, writing responses to `out`. Commands
+    /// can be split across calls anywhere.
+    pub fn receive(&mut self, bytes: &[u8], out: &mut Vec<u8>) {
+        self.pending.extend_from_slice(bytes);
+
+        let mut at = 0;
+        loop {
+            let rest = &self.pending[at..];
+            match self.reading {
+                Reading::Points { left: 0, response } => {
+                    self.reading = Reading::Command;
+                    self.respond(response, DATA, out);
+                }
+                Reading::Points { left, response } => {
+                    let Some(bytes) = rest.first_chunk::<POINT_LEN>() else {
+                        break;
+                    };
+                    if response == ACK {
+                        self.points.push_back(Queued::new(&DacPoint::decode(bytes)));
+                    }
+                    at += POINT_LEN;
+                    self.reading = Reading::Points {
+                        left: left - 1,
+                        response,
+                    };
+                }
+                Reading::Command => {
+                    let Some(&command) = rest.first() else {
+                        break;
+                    };
+                    let len = command_len(command);
+                    if rest.len() < len {
+                        break;
+                    }
+                    let mut args = [0; 6];
+                    args[..len - 1].copy_from_slice(&rest[1..len]);
+                    at += len;
+                    self.command(command, &args, out);
+                }
+            }
+        }
+
+        self.pending.drain(..at);
+    }
+
+    fn command(&mut self, command: u8, args: &[u8; 6], out: &mut Vec<u8>) {
+        let u16_at = |i: usize| u16::from_le_bytes([args[i], args[i + 1]]);
+        let u32_at = |i: usize| u32::from_le_bytes(args[i..i + 4].try_into().unwrap());
+        let stopped = self.light_engine == LightEngine::EmergencyStop;
+        let valid_rate = |rate: u32| (1..=self.max_point_rate).contains(&rate);
+
+        let response = match command {
+            PING => ACK,
+            PREPARE if stopped => NAK_STOP,
+            PREPARE if self.playback == Playback::Idle => {
+                self.playback = Playback::Prepared;
+                self.playback_flags &= !PLAYBACK_UNDERFLOW;
+                self.point_count = 0;
+                ACK
+            }
+            BEGIN if stopped => NAK_STOP,
+            // The low water mark, at args[0..2], is only a hint
+            BEGIN if self.playback == Playback::Prepared && valid_rate(u32_at(2)) => {
+                self.playback = Playback::Playing;
+                self.playback_flags |= PLAYBACK_SHUTTER_OPEN;
+                self.point_rate = u32_at(2);
+                ACK
+            }
+            QUEUE_RATE if stopped => NAK_STOP,
+            QUEUE_RATE if self.playback == Playback::Idle || !valid_rate(u32_at(0)) => NAK_INVALID,
+            QUEUE_RATE if self.rates.len() >= RATE_QUEUE_LEN => NAK_FULL,
+            QUEUE_RATE => {
+                self.rates.push_back(u32_at(0));
+                ACK
+            }
+            DATA => {
+                let count = u16_at(0);
+                let free = self.capacity as usize - self.points.len();
+                let response = if stopped {
+                    NAK_STOP
+                } else if self.playback == Playback::Idle {
+                    NAK_INVALID
+                } else if count as usize > free {
+                    NAK_FULL
+                } else {
+                    ACK
+                };
+                // Answered once the points have arrived
+                self.reading = Reading::Points {
+                    left: count,
+                    response,
+                };
+                return;
+            }
+            STOP if self.playback != Playback::Idle => {
+                self.stop();
+                ACK
+            }
+            EMERGENCY_STOP | EMERGENCY_STOP_ALT => {
+                if self.playback != Playback::Idle {
+                    self.playback_flags |= PLAYBACK_ESTOP;
+                }
+                self.stop();
+                self.light_engine = LightEngine::EmergencyStop;
+                self.light_engine_flags |= LIGHT_ENGINE_ESTOP_NETWORK;
+                ACK
+            }
+            CLEAR_EMERGENCY_STOP => {
+                self.light_engine = LightEngine::Ready;
+                self.light_engine_flags = 0;
+                self.playback_flags &= !(PLAYBACK_ESTOP | PLAYBACK_UNDERFLOW);
+                ACK
+            }
+            _ => NAK_INVALID,
+        };
+        self.respond(response, command, out);
+    }
+
+    /// Go back to idle, throwing away anything queued.
+    fn stop(&mut self) {
+        self.playback = Playback::Idle;
+        self.playback_flags &= !PLAYBACK_SHUTTER_OPEN;
+        self.points.clear();
+        self.rates.clear();
+    }
+
+    /// The next point to draw, held for the point rate, or `None` if
+    /// nothing is playing. Running out of points while playing stops
+    /// playback, which the client sees as an underflow.
+    pub fn next_point(&mut self) -> Option<Point> {
+        if self.playback != Playback::Playing {
+            return None;
+        }
+        let Some(queued) = self.points.pop_front() else {
+            self.stop();
+            self.playback_flags |= PLAYBACK_UNDERFLOW;
+            return None;
+        };
+
+        if queued.rate_change
+            && let Some(rate) = self.rates.pop_front()
+        {
+            self.point_rate = rate;
+        }
+        self.point_count = self.point_count.wrapping_add(1);
+
+        let delay = (1_000_000 / self.point_rate.max(1)).min(u16::MAX as u32) as u16;
+        Some(Point {
+            delay,
+            ..queued.point
+        })
+    }
+
+    /// Up to `max` points to draw next.
+    pub fn next_path(&mut self, max: usize) -> Path {
+        let mut path = Path::new();
+        while path.len() < max
+            && let Some(point) = self.next_point()
+        {
+            path.push(point);
+        }
+        path
+    }
+}
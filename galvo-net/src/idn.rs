@@ -0,0 +1,432 @@
+//! The ILDA Digital Network protocol, so laser software that speaks IDN
+//! can find the projector and stream points to it.
+//!
+//! Everything arrives as UDP datagrams on [`IDN_PORT`], starting with an
+//! IDN-Hello header. Discovery packets are answered with a reply to send
+//! back. Channel messages carry IDN-Stream chunks: a channel configuration
+//! saying how samples are laid out, then wave samples to draw in turn or
+//! frames to repeat until the next one.
+
+use core::fmt;
+
+use alloc::{collections::VecDeque, collections::btree_map::BTreeMap, vec::Vec};
+
+use vector_apps::point::{Path, Point};
+
+pub const IDN_PORT: u16 = 7255;
+
+pub const PING_REQUEST: u8 = 0x08;
+pub const PING_RESPONSE: u8 = 0x09;
+pub const SCAN_REQUEST: u8 = 0x10;
+pub const SCAN_RESPONSE: u8 = 0x11;
+pub const SERVICEMAP_REQUEST: u8 = 0x28;
+pub const SERVICEMAP_RESPONSE: u8 = 0x29;
+pub const CHANNEL_MESSAGE: u8 = 0x40;
+pub const CHANNEL_MESSAGE_ACKREQ: u8 = 0x41;
+pub const CLOSE: u8 = 0x44;
+pub const CLOSE_ACKREQ: u8 = 0x45;
+pub const ACKNOWLEDGE: u8 = 0x47;
+
+/// Content ID bit of a channel message.
+const CONTENT_CHANNEL_MESSAGE: u16 = 0x8000;
+/// Content ID bit of a channel configuration coming before the chunk.
+const CONTENT_CONFIG: u16 = 0x4000;
+
+pub const CHUNK_VOID: u8 = 0x00;
+pub const CHUNK_WAVE: u8 = 0x01;
+pub const CHUNK_FRAME: u8 = 0x02;
+
+/// Channel configuration flag that ends the channel.
+const CONFIG_CLOSE: u8 = 0x02;
+/// Frame chunk flag of a frame drawn once rather than repeated.
+const FRAME_ONCE: u8 = 0x01;
+
+/// Scan response status bit of a unit that takes real-time streams.
+const STATUS_REALTIME: u8 = 0x01;
+const PROTOCOL_VERSION: u8 = 0x10;
+/// Service type of a laser projector.
+const SERVICE_LASER_PROJECTOR: u8 = 0x80;
+/// Service map flag of the service that unrouted channels go to.
+const SERVICE_DEFAULT: u8 = 0x01;
+const SERVICE_ID: u8 = 1;
+
+const TAG_VOID: u16 = 0x0000;
+/// Widens the field before it to 16 bits.
+const TAG_PRECISION: u16 = 0x4010;
+const TAG_X: u16 = 0x4200;
+const TAG_Y: u16 = 0x4210;
+/// Colors are tagged with their wavelength in the low bits.
+const TAG_COLOR: u16 = 0x5000;
+const TAG_COLOR_MASK: u16 = 0xfc00;
+
+/// Streams that go quiet for this long without closing are dropped, so a
+/// crashed sender doesn't leave the beam parked on one frame.
+pub const TIMEOUT_US: u64 = 1_000_000;
+/// Wave samples queued to draw, a few frames' worth. Samples sent faster
+/// than they're drawn are dropped past this.
+pub const DEFAULT_CAPACITY: usize = 4096;
+
+const NAME_LEN: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdnError {
+    /// The packet is shorter than its headers say.
+    Truncated,
+    /// Samples arrived on a channel that hasn't been configured.
+    Unconfigured { channel: u8 },
+    /// A channel configuration has no X and Y, so nothing could be drawn.
+    BadConfig { channel: u8 },
+}
+
+impl fmt::Display for IdnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdnError::Truncated => write!(f, "packet ends early"),
+            IdnError::Unconfigured { channel } => {
+                write!(f, "channel {} hasn't been configured", channel)
+            }
+            IdnError::BadConfig { channel } => {
+                write!(f, "channel {} has no X and Y", channel)
+            }
+        }
+    }
+}
+
+impl core::error::Error for IdnError {}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Field {
+    X,
+    Y,
+    Red,
+    Green,
+    Blue,
+    /// Something we don't draw, like Z or intensity.
+    Skip,
+}
+
+/// How samples on a channel are laid out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ChannelConfig {
+    /// Each field and how many bytes it takes.
+    fields: Vec<(Field, usize)>,
+    sample_len: usize,
+}
+
+impl ChannelConfig {
+    fn from_tags(tags: impl Iterator<Item = u16>) -> Self {
+        let mut fields: Vec<(Field, usize)> = Vec::new();
+        for tag in tags {
+            let field = match tag {
+                TAG_VOID => continue,
+                TAG_PRECISION => {
+                    if let Some(last) = fields.last_mut() {
+                        last.1 = 2;
+                    }
+                    continue;
+                }
+                TAG_X => Field::X,
+                TAG_Y => Field::Y,
+                _ if tag & TAG_COLOR_MASK == TAG_COLOR => match tag & !TAG_COLOR_MASK {
+                    600.. => Field::Red,
+                    500.. => Field::Green,
+                    _ => Field::Blue,
+                },
+                _ => Field::Skip,
+            };
+            fields.push((field, 1));
+        }
+
+        let sample_len = fields.iter().map(|f| f.1).sum();
+        Self { fields, sample_len }
+    }
+
+    fn can_draw(&self) -> bool {
+        let has = |field| self.fields.iter().any(|f| f.0 == field);
+        has(Field::X) && has(Field::Y)
+    }
+
+    fn decode(&self, sample: &[u8], delay: u16) -> Point {
+        let (mut x, mut y) = (0i16, 0i16);
+        let mut color = (0, 0, 0);
+        let mut at = 0;
+        for &(field, len) in &self.fields {
+            // Everything is big endian, with 8 bit values the top byte
+            let value = if len == 2 {
+                u16::from_be_bytes([sample[at], sample[at + 1]])
+            } else {
+                (sample[at] as u16) << 8
+            };
+            at += len;
+
+            let high = (value >> 8) as u8;
+            match field {
+                Field::X => x = value as i16,
+                Field::Y => y = value as i16,
+                Field::Red => color.0 = color.0.max(high),
+                Field::Green => color.1 = color.1.max(high),
+                Field::Blue => color.2 = color.2.max(high),
+                Field::Skip => {}
+            }
+        }
+
+        let dac = |v: i16| ((v as i32 + 32768) >> 8) as u8;
+        Point {
+            x: dac(x),
+            // Our y runs down
+            y: 255 - dac(y),
+            color,
+            delay,
+        }
+    }
+}
+
+/// What the stream has to draw, and which channel it came from.
+enum Output {
+    Nothing,
+    /// Samples to draw once each, in order.
+    Wave {
+        channel: u8,
+        queue: VecDeque<Point>,
+    },
+    /// A frame to draw until the next one, or just once.
+    Frame {
+        channel: u8,
+        path: Path,
+        once: bool,
+    },
+}
+
+impl Output {
+    fn channel(&self) -> Option<u8> {
+        match self {
+            Output::Nothing => None,
+            Output::Wave { channel, .. } | Output::Frame { channel, .. } => Some(*channel),
+        }
+    }
+}
+
+/// The projector end of IDN, as a single laser projector service.
+pub struct IdnServer {
+    unit_id: [u8; 16],
+    name: [u8; NAME_LEN],
+    channels: BTreeMap<u8, ChannelConfig>,
+    output: Output,
+    capacity: usize,
+    /// When the last channel message came, in the caller's microseconds.
+    last_message_us: u64,
+}
+
+impl IdnServer {
+    /// A server for the device with hardware address `mac`, which is
+    /// listed as `name` to software scanning for it.
+    pub fn new(mac: [u8; 6], name: &str) -> Self {
+        // Length, then the category of a MAC address, then the address
+        let mut unit_id = [0; 16];
+        unit_id[0] = 7;
+        unit_id[1] = 1;
+        unit_id[2..8].copy_from_slice(&mac);
+
+        let mut name_bytes = [0; NAME_LEN];
+        let len = name.len().min(NAME_LEN);
+        name_bytes[..len].copy_from_slice(&name.as_bytes()[..len]);
+
+        Self {
+            unit_id,
+            name: name_bytes,
+            channels: BTreeMap::new(),
+            output: Output::Nothing,
+            capacity: DEFAULT_CAPACITY,
+            last_message_us: 0,
+        }
+    }
+
+    /// Wave samples to queue before dropping them.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    pub fn is_streaming(&self) -> bool {
+        !matches!(self.output, Output::Nothing)
+    }
+
+    /// Handle a datagram that arrived at `now_us`, writing any reply to
+    /// `out` to send back where it came from. Replies are sent even when
+    /// the packet's contents were bad.
+    pub fn receive(
+        &mut self,
+        packet: &[u8],
+        now_us: u64,
+        out: &mut Vec<u8>,
+    ) -> Result<(), IdnError> {
+        let Some((header, body)) = packet.split_first_chunk::<4>() else {
+            return Err(IdnError::Truncated);
+        };
+        let [command, flags, seq_hi, seq_lo] = *header;
+        let reply = |out: &mut Vec<u8>, command: u8| out.extend([command, flags, seq_hi, seq_lo]);
+
+        match command {
+            PING_REQUEST => {
+                reply(out, PING_RESPONSE);
+                out.extend_from_slice(body);
+                Ok(())
+            }
+            SCAN_REQUEST => {
+                reply(out, SCAN_RESPONSE);
+                out.extend([40, PROTOCOL_VERSION, STATUS_REALTIME, 0]);
+                out.extend(self.unit_id);
+                out.extend(self.name);
+                Ok(())
+            }
+            SERVICEMAP_REQUEST => {
+                reply(out, SERVICEMAP_RESPONSE);
+                // No relays, and the one service
+                out.extend([4, 4 + NAME_LEN as u8, 0, 1]);
+                out.extend([SERVICE_ID, SERVICE_LASER_PROJECTOR, SERVICE_DEFAULT, 0]);
+                out.extend(self.name);
+                Ok(())
+            }
+            CHANNEL_MESSAGE | CHANNEL_MESSAGE_ACKREQ => {
+                self.last_message_us = now_us;
+                let result = self.channel_message(body);
+                if command == CHANNEL_MESSAGE_ACKREQ {
+                    reply(out, ACKNOWLEDGE);
+                    out.extend([4, if result.is_ok() { 0 } else { 0xff }, 0, 0]);
+                }
+                result
+            }
+            CLOSE | CLOSE_ACKREQ => {
+                self.close();
+                if command == CLOSE_ACKREQ {
+                    reply(out, ACKNOWLEDGE);
+                    out.extend([4, 0, 0, 0]);
+                }
+                Ok(())
+            }
+            // Replies to other units, and whatever newer versions add
+            _ => Ok(()),
+        }
+    }
+
+    /// Forget every channel and stop drawing.
+    pub fn close(&mut self) {
+        self.channels.clear();
+        self.output = Output::Nothing;
+    }
+
+    fn channel_message(&mut self, message: &[u8]) -> Result<(), IdnError> {
+        let Some((header, _)) = message.split_first_chunk::<8>() else {
+            return Err(IdnError::Truncated);
+        };
+        let total = u16::from_be_bytes([header[0], header[1]]) as usize;
+        let content = u16::from_be_bytes([header[2], header[3]]);
+        // The timestamp, in header[4..8], only matters for syncing
+        // several projectors
+        if content & CONTENT_CHANNEL_MESSAGE == 0 {
+            return Ok(());
+        }
+        let mut rest = message.get(8..total).ok_or(IdnError::Truncated)?;
+
+        let channel = ((content >> 8) & 0x3f) as u8;
+        let chunk = content as u8;
+
+        if content & CONTENT_CONFIG != 0 {
+            let (&[words, flags, _service, _mode], tags) =
+                rest.split_first_chunk::<4>().ok_or(IdnError::Truncated)?;
+            let tags = tags.get(..words as usize * 4).ok_or(IdnError::Truncated)?;
+            rest = &rest[4 + tags.len()..];
+
+            if flags & CONFIG_CLOSE != 0 {
+                self.channels.remove(&channel);
+                if self.output.channel() == Some(channel) {
+                    self.output = Output::Nothing;
+                }
+                return Ok(());
+            }
+            let config = ChannelConfig::from_tags(
+                tags.chunks_exact(2)
+                    .map(|t| u16::from_be_bytes([t[0], t[1]])),
+            );
+            if !config.can_draw() {
+                return Err(IdnError::BadConfig { channel });
+            }
+            self.channels.insert(channel, config);
+        }
+
+        let config = self
+            .channels
+            .get(&channel)
+            .ok_or(IdnError::Unconfigured { channel })?;
+        if chunk != CHUNK_WAVE && chunk != CHUNK_FRAME {
+            // Void chunks just keep the channel open. Frames split over
+            // several packets aren't put back together, so senders should
+            // use wave samples for anything bigger than a packet.
+            return Ok(());
+        }
+
+        let (&[flags, d0, d1, d2], samples) =
+            rest.split_first_chunk::<4>().ok_or(IdnError::Truncated)?;
+        let duration_us = u32::from_be_bytes([0, d0, d1, d2]);
+        let count = samples.len() / config.sample_len;
+        let delay = (duration_us / count.max(1) as u32).min(u16::MAX as u32) as u16;
+        let points = samples
+            .chunks_exact(config.sample_len)
+            .map(|s| config.decode(s, delay));
+
+        if chunk == CHUNK_WAVE {
+            match &mut self.output {
+                Output::Wave {
+                    channel: playing,
+                    queue,
+                } if *playing == channel => {
+                    let room = self.capacity.saturating_sub(queue.len());
+                    queue.extend(points.take(room));
+                }
+                output => {
+                    *output = Output::Wave {
+                        channel,
+                        queue: points.take(self.capacity).collect(),
+                    }
+                }
+            }
+        } else {
+            self.output = Output::Frame {
+                channel,
+                path: points.collect(),
+                once: flags & FRAME_ONCE != 0,
+            };
+        }
+        Ok(())
+    }
+
+    /// What to draw next at `now_us`: the latest frame, or up to `max`
+    /// wave samples. Empty once the stream has ended or timed out.
+    pub fn next_path(&mut self, now_us: u64, max: usize) -> Path {
+        if now_us.saturating_sub(self.last_message_us) > TIMEOUT_US {
+            self.close();
+        }
+
+        match &mut self.output {
+            Output::Nothing => Path::new(),
+            Output::Wave { queue, .. } => {
+                let take = max.min(queue.len());
+                let path: Path = queue.drain(..take).collect();
+                if queue.is_empty() {
+                    self.output = Output::Nothing;
+                }
+                path
+            }
+            Output::Frame {
+                path, once: true, ..
+            } => {
+                let path = core::mem::take(path);
+                self.output = Output::Nothing;
+                path
+            }
+            Output::Frame {
+                path, once: false, ..
+            } => path.clone(),
+        }
+    }
+}
@@ -0,0 +1,11 @@
+//! Network protocols laser software uses to drive projectors, without any
+//! sockets, so they can be tested on the host. The firmware passes them
+//! what arrives and sends back what they write.
+
+#![no_std]
+
+extern crate alloc;
+
+pub mod dmx;
+pub mod ether_dream;
+pub mod idn;
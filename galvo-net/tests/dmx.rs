@@ -0,0 +1,238 @@
+use galvo_net::dmx::{
+    CHANNELS, DmxControls, DmxData, DmxError, DmxListener, MAX_UNIVERSE, NORMAL_SPEED, TIMEOUT_US,
+    parse_artnet, parse_sacn,
+};
+use vector_apps::point::Point;
+
+/// An ArtDmx packet for `universe` carrying `slots`.
+fn artnet(universe: u16, slots: &[u8]) -> Vec<u8> {
+    let mut packet = b"Art-Net\0".to_vec();
+    // OpDmx, protocol version 14, sequence and physical port
+    packet.extend([0x00, 0x50, 0x00, 0x0e, 0x01, 0x00]);
+    packet.extend(universe.to_le_bytes());
+    packet.extend((slots.len() as u16).to_be_bytes());
+    packet.extend(slots);
+    packet
+}
+
+/// An E1.31 data packet for `universe` carrying `slots`, with `options`.
+fn sacn(universe: u16, slots: &[u8], options: u8) -> Vec<u8> {
+    let pdu = |len: usize| (0x7000 | len as u16).to_be_bytes();
+    let len = 126 + slots.len();
+
+    // Root layer
+    let mut packet = vec![0x00, 0x10, 0x00, 0x00];
+    packet.extend(b"ASC-E1.17\0\0\0");
+    packet.extend(pdu(len - 16));
+    packet.extend(4u32.to_be_bytes());
+    packet.extend([0x42; 16]);
+    // Framing layer: source name, priority, sync address, sequence
+    packet.extend(pdu(len - 38));
+    packet.extend(2u32.to_be_bytes());
+    let mut name = [0; 64];
+    name[..4].copy_from_slice(b"desk");
+    packet.extend(name);
+    packet.extend([100, 0, 0, 7, options]);
+    packet.extend(universe.to_be_bytes());
+    // DMP layer, with the start code first
+    packet.extend(pdu(len - 115));
+    packet.extend([0x02, 0xa1, 0x00, 0x00, 0x00, 0x01]);
+    packet.extend((slots.len() as u16 + 1).to_be_bytes());
+    packet.push(0x00);
+    packet.extend(slots);
+    packet
+}
+
+/// Levels for a whole universe, with `channels` from `address`.
+fn universe(address: usize, channels: [u8; CHANNELS]) -> Vec<u8> {
+    let mut slots = vec![0xee; 512];
+    slots[address - 1..address - 1 + CHANNELS].copy_from_slice(&channels);
+    slots
+}
+
+const CENTERED: [u8; CHANNELS] = [255, 0, 0, 0, 0, 255, 128, 128, 0, NORMAL_SPEED];
+
+#[test]
+fn parses_artnet() {
+    let packet = artnet(0x0123, &[1, 2, 3]);
+    assert_eq!(
+        parse_artnet(&packet),
+        Ok(Some(DmxData {
+            universe: 0x0123,
+            slots: &[1, 2, 3],
+            terminated: false,
+        }))
+    );
+
+    // ArtPoll is for someone else to answer
+    let mut poll = b"Art-Net\0".to_vec();
+    poll.extend([0x00, 0x20, 0x00, 0x0e, 0x00, 0x00]);
+    poll.extend([0; 4]);
+    assert_eq!(parse_artnet(&poll), Ok(None));
+
+    assert_eq!(parse_artnet(&packet[..20]), Err(DmxError::Truncated));
+    assert_eq!(parse_artnet(b"Art"), Err(DmxError::Truncated));
+    assert_eq!(parse_artnet(&sacn(1, &[1], 0)), Err(DmxError::Unrecognized));
+}
+
+#[test]
+fn parses_sacn() {
+    let packet = sacn(7, &[9, 8, 7], 0);
+    assert_eq!(packet.len(), 129);
+    assert_eq!(
+        parse_sacn(&packet),
+        Ok(Some(DmxData {
+            universe: 7,
+            slots: &[9, 8, 7],
+            terminated: false,
+        }))
+    );
+    assert!(parse_sacn(&sacn(7, &[], 0x40)).unwrap().unwrap().terminated);
+
+    // Preview data, and levels that aren't dimmer data
+    assert_eq!(parse_sacn(&sacn(7, &[1], 0x80)), Ok(None));
+    let mut text = packet.clone();
+    text[125] = 0x17;
+    assert_eq!(parse_sacn(&text), Ok(None));
+    // A sync packet
+    let mut sync = packet.clone();
+    sync[18..22].copy_from_slice(&8u32.to_be_bytes());
+    assert_eq!(parse_sacn(&sync), Ok(None));
+
+    assert_eq!(parse_sacn(&packet[..127]), Err(DmxError::Truncated));
+    assert_eq!(
+        parse_sacn(&artnet(1, &[0; 200])),
+        Err(DmxError::Unrecognized)
+    );
+}
+
+#[test]
+fn reads_its_channels() {
+    let mut dmx = DmxListener::new().with_patch(3, 101).unwrap();
+    assert_eq!(dmx.controls(0), None);
+
+    // Art-Net counts universes from 0
+    let channels = [200, 17, 255, 0, 64, 128, 10, 20, 64, 192];
+    dmx.receive_artnet(&artnet(3, &universe(101, [0; CHANNELS])), 0)
+        .unwrap();
+    assert_eq!(dmx.controls(0), None);
+    dmx.receive_artnet(&artnet(2, &universe(101, channels)), 0)
+        .unwrap();
+    let controls = dmx.controls(0).unwrap();
+    assert_eq!(controls, DmxControls::from_slots(&channels));
+    assert_eq!(
+        (
+            controls.dimmer,
+            controls.app,
+            controls.color,
+            controls.speed
+        ),
+        (200, 17, (255, 0, 64), 192)
+    );
+
+    // Other universes are ignored, and sACN works the same
+    let other = [0; CHANNELS];
+    dmx.receive_sacn(&sacn(4, &universe(101, other), 0), 10)
+        .unwrap();
+    assert_eq!(dmx.controls(10), Some(controls));
+    dmx.receive_sacn(&sacn(3, &universe(101, other), 0), 20)
+        .unwrap();
+    assert_eq!(dmx.controls(20), Some(DmxControls::from_slots(&other)));
+
+    // Short universes leave the channels past the end at 0
+    dmx.receive_artnet(&artnet(2, &[0xff; 102]), 30).unwrap();
+    let short = dmx.controls(30).unwrap();
+    assert_eq!(
+        (short.dimmer, short.app, short.color),
+        (255, 255, (0, 0, 0))
+    );
+}
+
+#[test]
+fn lets_go_of_quiet_desks() {
+    let mut dmx = DmxListener::new();
+    dmx.receive_sacn(&sacn(1, &CENTERED, 0), 1_000).unwrap();
+    assert!(dmx.controls(1_000 + TIMEOUT_US).is_some());
+    assert!(dmx.controls(1_001 + TIMEOUT_US).is_none());
+
+    // Or ones that say they're done
+    dmx.receive_sacn(&sacn(1, &CENTERED, 0), 2_000_000).unwrap();
+    dmx.receive_sacn(&sacn(1, &[], 0x40), 2_000_010).unwrap();
+    assert!(dmx.controls(2_000_020).is_none());
+}
+
+#[test]
+fn checks_the_patch() {
+    let mut dmx = DmxListener::new();
+    assert_eq!(dmx.set_patch(1, 0), Err(DmxError::BadAddress(0)));
+    assert_eq!(dmx.set_patch(1, 504), Err(DmxError::BadAddress(504)));
+    assert_eq!(dmx.set_patch(0, 1), Err(DmxError::BadUniverse(0)));
+    assert_eq!(
+        dmx.set_patch(MAX_UNIVERSE + 1, 1),
+        Err(DmxError::BadUniverse(MAX_UNIVERSE + 1))
+    );
+    assert_eq!(dmx.set_patch(MAX_UNIVERSE, 1), Ok(()));
+    assert_eq!(dmx.set_patch(2, 503), Ok(()));
+    assert_eq!((dmx.universe(), dmx.address()), (2, 503));
+    assert_eq!(dmx.artnet_port(), 1);
+
+    dmx.receive_artnet(&artnet(1, &universe(503, CENTERED)), 0)
+        .unwrap();
+    assert!(dmx.controls(0).is_some());
+}
+
+#[test]
+fn picks_apps() {
+    let app = |app| DmxControls::from_slots(&[0, app, 0, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(app(0).app_index(5), None);
+    assert_eq!(app(1).app_index(5), Some(0));
+    assert_eq!(app(8).app_index(5), Some(0));
+    assert_eq!(app(9).app_index(5), Some(1));
+    assert_eq!(app(255).app_index(5), Some(4));
+    assert_eq!(app(9).app_index(0), None);
+}
+
+fn point(x: u8, y: u8, color: (u8, u8, u8)) -> Point {
+    Point {
+        x,
+        y,
+        color,
+        delay: 100,
+    }
+}
+
+fn summary(path: &[Point]) -> Vec<(u8, u8, (u8, u8, u8))> {
+    path.iter().map(|p| (p.x, p.y, p.color)).collect()
+}
+
+#[test]
+fn moves_and_dims_paths() {
+    let path = [point(0, 0, (0, 0, 0)), point(255, 128, (255, 128, 0))];
+
+    // Centered at full size and brightness changes nothing
+    let same = DmxControls::from_slots(&CENTERED).apply(&path);
+    assert_eq!(summary(&same), summary(&path));
+    assert!(same.iter().all(|p| p.delay == 100));
+
+    // Half size, half brightness, moved right and up
+    let mut channels = CENTERED;
+    channels[0] = 128;
+    channels[5] = 128;
+    channels[6] = 160;
+    channels[7] = 192;
+    let moved = DmxControls::from_slots(&channels).apply(&path);
+    assert_eq!(
+        summary(&moved),
+        [(96, 0, (0, 0, 0)), (224, 64, (128, 64, 0))]
+    );
+
+    // A quarter turn, with lit points recolored
+    let mut channels = CENTERED;
+    channels[2..5].copy_from_slice(&[0, 0, 255]);
+    channels[8] = 64;
+    let turned = DmxControls::from_slots(&channels).apply(&path);
+    assert_eq!(
+        summary(&turned),
+        [(255, 0, (0, 0, 0)), (127, 255, (0, 0, 255))]
+    );
+}
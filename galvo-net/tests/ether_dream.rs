@@ -0,0 +1,279 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+};
+
+use galvo_net::ether_dream::{
+    ACK, BEACON_LEN, BEGIN, CLEAR_EMERGENCY_STOP, DATA, DacPoint, DacStatus, EMERGENCY_STOP,
+    EtherDream, LightEngine, NAK_FULL, NAK_INVALID, NAK_STOP, PING, PLAYBACK_ESTOP,
+    PLAYBACK_SHUTTER_OPEN, PLAYBACK_UNDERFLOW, PREPARE, Playback, QUEUE_RATE, RATE_CHANGE_BIT,
+    RESPONSE_LEN, STATUS_LEN, STOP,
+};
+
+fn begin(rate: u32) -> Vec<u8> {
+    let mut out = vec![BEGIN, 0, 0];
+    out.extend(rate.to_le_bytes());
+    out
+}
+
+fn queue_rate(rate: u32) -> Vec<u8> {
+    let mut out = vec![QUEUE_RATE];
+    out.extend(rate.to_le_bytes());
+    out
+}
+
+fn data(points: &[DacPoint]) -> Vec<u8> {
+    let mut out = vec![DATA];
+    out.extend((points.len() as u16).to_le_bytes());
+    for point in points {
+        out.extend(point.encode());
+    }
+    out
+}
+
+fn point(x: i16, y: i16) -> DacPoint {
+    DacPoint {
+        x,
+        y,
+        r: 0xffff,
+        g: 0x8000,
+        ..DacPoint::default()
+    }
+}
+
+/// The responses in `bytes`, as the response, the command and the status.
+fn responses(bytes: &[u8]) -> Vec<(u8, u8, DacStatus)> {
+    assert_eq!(bytes.len() % RESPONSE_LEN, 0);
+    bytes
+        .chunks_exact(RESPONSE_LEN)
+        .map(|r| {
+            let status = DacStatus::decode(r[2..].try_into().unwrap()).unwrap();
+            (r[0], r[1], status)
+        })
+        .collect()
+}
+
+/// Send `bytes` and give the one response.
+fn send(dac: &mut EtherDream, bytes: &[u8]) -> (u8, u8, DacStatus) {
+    let mut out = Vec::new();
+    dac.receive(bytes, &mut out);
+    let mut responses = responses(&out);
+    assert_eq!(responses.len(), 1);
+    responses.remove(0)
+}
+
+#[test]
+fn greets_clients() {
+    let mut dac = EtherDream::new();
+    let mut out = Vec::new();
+    dac.connected(&mut out);
+
+    let (response, command, status) = responses(&out)[0];
+    assert_eq!((response, command), (ACK, PING));
+    assert_eq!(status.light_engine, LightEngine::Ready);
+    assert_eq!(status.playback, Playback::Idle);
+    assert_eq!(send(&mut dac, &[PING]).0, ACK);
+}
+
+#[test]
+fn plays_points() {
+    let mut dac = EtherDream::new();
+    assert_eq!(send(&mut dac, &[PREPARE]).0, ACK);
+
+    // One byte at a time, which has to be put back together
+    let mut out = Vec::new();
+    let points = [point(-32768, 32767), point(0, 0), point(32767, -32768)];
+    for byte in data(&points) {
+        dac.receive(&[byte], &mut out);
+    }
+    let (response, command, status) = responses(&out)[0];
+    assert_eq!((response, command), (ACK, DATA));
+    assert_eq!(status.buffer_fullness, 3);
+
+    // Nothing is drawn until playback begins
+    assert!(dac.next_point().is_none());
+    let (response, _, status) = send(&mut dac, &begin(10_000));
+    assert_eq!(response, ACK);
+    assert_eq!(status.playback, Playback::Playing);
+    assert_eq!(status.playback_flags, PLAYBACK_SHUTTER_OPEN);
+
+    let path = dac.next_path(10);
+    let drawn: Vec<_> = path.iter().map(|p| (p.x, p.y, p.color, p.delay)).collect();
+    assert_eq!(
+        drawn,
+        [
+            (0, 0, (255, 128, 0), 100),
+            (128, 127, (255, 128, 0), 100),
+            (255, 255, (255, 128, 0), 100),
+        ]
+    );
+
+    // Running out ends playback, and the client hears about it
+    let (_, _, status) = send(&mut dac, &[PING]);
+    assert_eq!(status.playback, Playback::Idle);
+    assert_eq!(status.playback_flags, PLAYBACK_UNDERFLOW);
+    assert_eq!(status.point_count, 3);
+}
+
+#[test]
+fn changes_rates_on_marked_points() {
+    let mut dac = EtherDream::new();
+    let mut marked = point(0, 0);
+    marked.control = RATE_CHANGE_BIT;
+
+    send(&mut dac, &[PREPARE]);
+    assert_eq!(send(&mut dac, &queue_rate(20_000)).0, ACK);
+    send(&mut dac, &data(&[point(0, 0), marked, point(0, 0)]));
+    send(&mut dac, &begin(10_000));
+
+    let delays: Vec<_> = dac.next_path(3).iter().map(|p| p.delay).collect();
+    assert_eq!(delays, [100, 50, 50]);
+    assert_eq!(dac.status().point_rate, 20_000);
+}
+
+#[test]
+fn refuses_commands_out_of_turn() {
+    let mut dac = EtherDream::new()
+        .with_capacity(4)
+        .with_max_point_rate(20_000);
+
+    // Data while idle is skipped over, so the next command still reads
+    let mut out = Vec::new();
+    dac.receive(&data(&[point(0, 0); 2]), &mut out);
+    dac.receive(&[PING], &mut out);
+    let answers: Vec<_> = responses(&out).iter().map(|r| (r.0, r.1)).collect();
+    assert_eq!(answers, [(NAK_INVALID, DATA), (ACK, PING)]);
+
+    assert_eq!(send(&mut dac, &begin(10_000)).0, NAK_INVALID);
+    assert_eq!(send(&mut dac, &[STOP]).0, NAK_INVALID);
+    assert_eq!(send(&mut dac, &queue_rate(10_000)).0, NAK_INVALID);
+    assert_eq!(send(&mut dac, b"x").0, NAK_INVALID);
+
+    send(&mut dac, &[PREPARE]);
+    assert_eq!(send(&mut dac, &[PREPARE]).0, NAK_INVALID);
+    assert_eq!(send(&mut dac, &begin(50_000)).0, NAK_INVALID);
+    assert_eq!(send(&mut dac, &begin(0)).0, NAK_INVALID);
+
+    // Only what fits is taken
+    let (response, _, status) = send(&mut dac, &data(&[point(0, 0); 5]));
+    assert_eq!((response, status.buffer_fullness), (NAK_FULL, 0));
+    let (response, _, status) = send(&mut dac, &data(&[point(0, 0); 4]));
+    assert_eq!((response, status.buffer_fullness), (ACK, 4));
+
+    let (response, _, status) = send(&mut dac, &[STOP]);
+    assert_eq!(
+        (response, status.playback, status.buffer_fullness),
+        (ACK, Playback::Idle, 0)
+    );
+}
+
+#[test]
+fn stops_in_an_emergency() {
+    let mut dac = EtherDream::new();
+    send(&mut dac, &[PREPARE]);
+    send(&mut dac, &data(&[point(0, 0); 10]));
+    send(&mut dac, &begin(10_000));
+
+    let (response, _, status) = send(&mut dac, &[EMERGENCY_STOP]);
+    assert_eq!(response, ACK);
+    assert_eq!(status.light_engine, LightEngine::EmergencyStop);
+    assert_eq!(status.playback, Playback::Idle);
+    assert_eq!(status.playback_flags, PLAYBACK_ESTOP);
+    assert!(dac.next_point().is_none());
+
+    assert_eq!(send(&mut dac, &[PREPARE]).0, NAK_STOP);
+    assert_eq!(send(&mut dac, &[0xff]).0, ACK);
+
+    let (response, _, status) = send(&mut dac, &[CLEAR_EMERGENCY_STOP]);
+    assert_eq!(response, ACK);
+    assert_eq!(status.light_engine, LightEngine::Ready);
+    assert_eq!((status.light_engine_flags, status.playback_flags), (0, 0));
+    assert_eq!(send(&mut dac, &[PREPARE]).0, ACK);
+}
+
+#[test]
+fn stops_when_the_client_leaves() {
+    let mut dac = EtherDream::new();
+    send(&mut dac, &[PREPARE]);
+    // Half a command, which the next client mustn't inherit
+    let mut out = Vec::new();
+    dac.receive(&[DATA, 1], &mut out);
+    dac.disconnected();
+
+    assert_eq!(dac.status().playback, Playback::Idle);
+    dac.connected(&mut out);
+    assert_eq!(send(&mut dac, &[PING]).0, ACK);
+}
+
+#[test]
+fn describes_itself_in_beacons() {
+    let dac = EtherDream::new()
+        .with_capacity(1000)
+        .with_max_point_rate(25_000);
+    let beacon = dac.beacon([1, 2, 3, 4, 5, 6]);
+
+    assert_eq!(beacon.len(), BEACON_LEN);
+    assert_eq!(beacon[..6], [1, 2, 3, 4, 5, 6]);
+    assert_eq!(u16::from_le_bytes([beacon[10], beacon[11]]), 1000);
+    assert_eq!(
+        u32::from_le_bytes(beacon[12..16].try_into().unwrap()),
+        25_000
+    );
+    let status: &[u8; STATUS_LEN] = beacon[16..].try_into().unwrap();
+    assert_eq!(DacStatus::decode(status), Some(dac.status()));
+}
+
+/// Serve one client on `listener` the way the firmware does, then give
+/// back the DAC.
+fn serve(listener: TcpListener) -> EtherDream {
+    let mut dac = EtherDream::new();
+    let (mut stream, _) = listener.accept().unwrap();
+    let mut out = Vec::new();
+    dac.connected(&mut out);
+
+    let mut buf = [0; 64];
+    loop {
+        stream.write_all(&out).unwrap();
+        out.clear();
+        match stream.read(&mut buf).unwrap() {
+            0 => break,
+            n => dac.receive(&buf[..n], &mut out),
+        }
+    }
+    dac.disconnected();
+    dac
+}
+
+/// Send `bytes` from a client and read the response.
+fn request(stream: &mut TcpStream, bytes: &[u8]) -> (u8, u8, DacStatus) {
+    stream.write_all(bytes).unwrap();
+    let mut response = [0; RESPONSE_LEN];
+    stream.read_exact(&mut response).unwrap();
+    responses(&response)[0]
+}
+
+#[test]
+fn serves_a_local_client() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = thread::spawn(move || serve(listener));
+
+    let mut client = TcpStream::connect(address).unwrap();
+    let mut greeting = [0; RESPONSE_LEN];
+    client.read_exact(&mut greeting).unwrap();
+    assert_eq!(greeting[..2], [ACK, PING]);
+
+    assert_eq!(request(&mut client, &[PREPARE]).0, ACK);
+    // Bigger than the server reads at once
+    let points: Vec<_> = (0..100).map(|i| point(i * 300, 0)).collect();
+    let (response, _, status) = request(&mut client, &data(&points));
+    assert_eq!((response, status.buffer_fullness), (ACK, 100));
+    assert_eq!(request(&mut client, &begin(20_000)).0, ACK);
+    drop(client);
+
+    // Leaving stops playback and throws the points away
+    let mut dac = server.join().unwrap();
+    assert_eq!(dac.status().playback, Playback::Idle);
+    assert!(dac.next_point().is_none());
+}
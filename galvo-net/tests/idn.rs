@@ -0,0 +1,257 @@
+use galvo_net::idn::{
+    ACKNOWLEDGE, CLOSE, IdnError, IdnServer, PING_RESPONSE, SCAN_RESPONSE, SERVICEMAP_RESPONSE,
+    TIMEOUT_US,
+};
+
+const MAC: [u8; 6] = [0x24, 0x0a, 0xc4, 0x01, 0x02, 0x03];
+
+/// A scan request, sequence number 0x1234.
+const SCAN: [u8; 4] = [0x10, 0x00, 0x12, 0x34];
+
+/// A service map request.
+const SERVICEMAP: [u8; 4] = [0x28, 0x00, 0x00, 0x07];
+
+/// A wave chunk on channel 0 with its configuration: 16 bit X and Y, then
+/// 638nm, 532nm and 460nm lasers, padded with a void tag. Two samples over
+/// 500µs, at the left top lit red and at the right bottom lit blue.
+fn wave() -> Vec<u8> {
+    [
+        // IDN-Hello: channel message, sequence 1
+        &[0x40, 0x00, 0x00, 0x01][..],
+        // Channel message: 46 bytes, configured wave chunk on channel 0
+        &[0x00, 0x2e, 0xc0, 0x01, 0x00, 0x00, 0x10, 0x00],
+        // Configuration: 4 words of tags, routed to service 1, continuous
+        &[0x04, 0x01, 0x01, 0x01],
+        &[0x42, 0x00, 0x40, 0x10, 0x42, 0x10, 0x40, 0x10],
+        &[0x52, 0x7e, 0x52, 0x14, 0x51, 0xcc, 0x00, 0x00],
+        // Wave chunk over 500µs
+        &[0x00, 0x00, 0x01, 0xf4],
+        &[0x80, 0x00, 0x7f, 0xff, 0xff, 0x00, 0x00],
+        &[0x7f, 0xff, 0x80, 0x00, 0x00, 0x00, 0x80],
+    ]
+    .concat()
+}
+
+/// More samples on channel 0, laid out as configured before: one in the
+/// middle lit green, over 100µs.
+fn more_wave() -> Vec<u8> {
+    [
+        &[0x40, 0x00, 0x00, 0x02][..],
+        // 19 bytes, wave chunk on channel 0 with no configuration
+        &[0x00, 0x13, 0x80, 0x01, 0x00, 0x00, 0x11, 0x00],
+        &[0x00, 0x00, 0x00, 0x64],
+        &[0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x00],
+    ]
+    .concat()
+}
+
+/// A frame chunk on channel 2 asking for an acknowledgement, with 8 bit X
+/// and Y and a 638nm laser: a blanked point then a lit one, over 200µs.
+fn frame() -> Vec<u8> {
+    [
+        &[0x41, 0x00, 0x00, 0x03][..],
+        &[0x00, 0x1e, 0xc2, 0x02, 0x00, 0x00, 0x20, 0x00],
+        // 2 words of tags, discrete
+        &[0x02, 0x01, 0x01, 0x02],
+        &[0x42, 0x00, 0x42, 0x10, 0x52, 0x7e, 0x00, 0x00],
+        // Frame chunk to repeat, over 200µs
+        &[0x00, 0x00, 0x00, 0xc8],
+        &[0x00, 0x00, 0x00, 0x40, 0x40, 0xff],
+    ]
+    .concat()
+}
+
+/// The configuration of `frame()` with the close flag, on `channel` and
+/// with no samples.
+fn close(channel: u8) -> Vec<u8> {
+    [
+        &[0x40, 0x00, 0x00, 0x04][..],
+        // 20 bytes, configured void chunk
+        &[0x00, 0x14, 0xc0 | channel, 0x00, 0x00, 0x00, 0x30, 0x00],
+        &[0x02, 0x03, 0x01, 0x02],
+        &[0x42, 0x00, 0x42, 0x10, 0x52, 0x7e, 0x00, 0x00],
+    ]
+    .concat()
+}
+
+/// A point as its position, color and delay.
+type Drawn = (u8, u8, (u8, u8, u8), u16);
+
+fn points(path: &[vector_apps::point::Point]) -> Vec<Drawn> {
+    path.iter().map(|p| (p.x, p.y, p.color, p.delay)).collect()
+}
+
+fn server() -> IdnServer {
+    IdnServer::new(MAC, "galvo")
+}
+
+#[test]
+fn answers_scans() {
+    let mut idn = server();
+    let mut out = Vec::new();
+    idn.receive(&SCAN, 0, &mut out).unwrap();
+
+    assert_eq!(out.len(), 44);
+    assert_eq!(out[..4], [SCAN_RESPONSE, 0x00, 0x12, 0x34]);
+    // Structure size, then status saying it takes real-time streams
+    assert_eq!(out[4], 40);
+    assert_eq!(out[6] & 0x01, 0x01);
+    assert_eq!(out[8..16], [7, 1, 0x24, 0x0a, 0xc4, 0x01, 0x02, 0x03]);
+    assert_eq!(&out[24..29], b"galvo");
+    assert!(out[29..].iter().all(|&b| b == 0));
+}
+
+#[test]
+fn lists_its_service() {
+    let mut idn = server();
+    let mut out = Vec::new();
+    idn.receive(&SERVICEMAP, 0, &mut out).unwrap();
+
+    assert_eq!(out[..4], [SERVICEMAP_RESPONSE, 0x00, 0x00, 0x07]);
+    // No relays and one laser projector service
+    assert_eq!(out[4..8], [4, 24, 0, 1]);
+    assert_eq!(out[8..10], [1, 0x80]);
+    assert_eq!(&out[12..17], b"galvo");
+    assert_eq!(out.len(), 32);
+
+    out.clear();
+    idn.receive(&[0x08, 0x00, 0x00, 0x09, 1, 2, 3], 0, &mut out)
+        .unwrap();
+    assert_eq!(out, [PING_RESPONSE, 0x00, 0x00, 0x09, 1, 2, 3]);
+}
+
+#[test]
+fn streams_wave_samples() {
+    let mut idn = server();
+    let mut out = Vec::new();
+    assert!(!idn.is_streaming());
+    idn.receive(&wave(), 0, &mut out).unwrap();
+    idn.receive(&more_wave(), 10, &mut out).unwrap();
+    // Nobody asked for acknowledgements
+    assert!(out.is_empty());
+    assert!(idn.is_streaming());
+
+    assert_eq!(
+        points(&idn.next_path(20, 2)),
+        [(0, 0, (255, 0, 0), 250), (255, 255, (0, 0, 128), 250)]
+    );
+    assert_eq!(
+        points(&idn.next_path(30, 2)),
+        [(128, 127, (0, 255, 0), 100)]
+    );
+    // Drawn once each
+    assert!(idn.next_path(40, 2).is_empty());
+    assert!(!idn.is_streaming());
+}
+
+#[test]
+fn drops_samples_past_capacity() {
+    let mut idn = server().with_capacity(2);
+    idn.receive(&wave(), 0, &mut Vec::new()).unwrap();
+    // Full, so the green sample is dropped
+    idn.receive(&more_wave(), 10, &mut Vec::new()).unwrap();
+    assert_eq!(
+        points(&idn.next_path(20, 10)),
+        [(0, 0, (255, 0, 0), 250), (255, 255, (0, 0, 128), 250)]
+    );
+
+    // Room for the first of the next two
+    idn.receive(&more_wave(), 30, &mut Vec::new()).unwrap();
+    idn.receive(&wave(), 40, &mut Vec::new()).unwrap();
+    assert_eq!(
+        points(&idn.next_path(50, 10)),
+        [(128, 127, (0, 255, 0), 100), (0, 0, (255, 0, 0), 250)]
+    );
+}
+
+#[test]
+fn repeats_frames() {
+    let mut idn = server();
+    let mut out = Vec::new();
+    idn.receive(&frame(), 0, &mut out).unwrap();
+    assert_eq!(out, [ACKNOWLEDGE, 0x00, 0x00, 0x03, 4, 0, 0, 0]);
+
+    let frame = [(128, 127, (0, 0, 0), 100), (192, 63, (255, 0, 0), 100)];
+    assert_eq!(points(&idn.next_path(10, 1)), frame);
+    assert_eq!(points(&idn.next_path(20, 1)), frame);
+
+    // Closing the session stops it
+    out.clear();
+    idn.receive(&[CLOSE, 0x00, 0x00, 0x04], 30, &mut out)
+        .unwrap();
+    assert!(out.is_empty());
+    assert!(idn.next_path(40, 1).is_empty());
+}
+
+#[test]
+fn drops_quiet_streams() {
+    let mut idn = server();
+    idn.receive(&frame(), 1_000, &mut Vec::new()).unwrap();
+    assert!(!idn.next_path(1_000 + TIMEOUT_US, 1).is_empty());
+    assert!(idn.next_path(1_001 + TIMEOUT_US, 1).is_empty());
+    assert!(!idn.is_streaming());
+}
+
+#[test]
+fn rejects_bad_packets() {
+    let mut idn = server();
+    let mut out = Vec::new();
+
+    // Samples before any configuration
+    assert_eq!(
+        idn.receive(&more_wave(), 0, &mut out),
+        Err(IdnError::Unconfigured { channel: 0 })
+    );
+    assert_eq!(
+        idn.receive(&[0x10, 0x00], 0, &mut out),
+        Err(IdnError::Truncated)
+    );
+    assert_eq!(
+        idn.receive(&wave()[..30], 0, &mut out),
+        Err(IdnError::Truncated)
+    );
+
+    // A configuration with nothing to draw, still acknowledged
+    let mut colors_only = frame();
+    colors_only[16..20].copy_from_slice(&[0x52, 0x14, 0x51, 0xcc]);
+    assert_eq!(
+        idn.receive(&colors_only, 0, &mut out),
+        Err(IdnError::BadConfig { channel: 2 })
+    );
+    assert_eq!(out[..5], [ACKNOWLEDGE, 0x00, 0x00, 0x03, 4]);
+    assert_ne!(out[5], 0);
+    assert_eq!(out.len(), 8);
+    assert!(!idn.is_streaming());
+}
+
+#[test]
+fn closes_channels() {
+    let mut idn = server();
+    idn.receive(&wave(), 0, &mut Vec::new()).unwrap();
+    idn.receive(&frame(), 0, &mut Vec::new()).unwrap();
+
+    // Closing a channel that isn't playing leaves the frame alone
+    idn.receive(&close(0), 10, &mut Vec::new()).unwrap();
+    assert!(idn.is_streaming());
+    assert_eq!(idn.next_path(10, 1).len(), 2);
+    assert_eq!(
+        idn.receive(&more_wave(), 10, &mut Vec::new()),
+        Err(IdnError::Unconfigured { channel: 0 })
+    );
+
+    idn.receive(&close(2), 10, &mut Vec::new()).unwrap();
+    assert!(!idn.is_streaming());
+
+    // Samples after that need configuring again
+    let samples = [
+        &[0x40, 0x00, 0x00, 0x05][..],
+        &[0x00, 0x0f, 0x82, 0x02, 0x00, 0x00, 0x40, 0x00],
+        &[0x00, 0x00, 0x00, 0x64],
+        &[0x00, 0x00, 0xff],
+    ]
+    .concat();
+    assert_eq!(
+        idn.receive(&samples, 20, &mut Vec::new()),
+        Err(IdnError::Unconfigured { channel: 2 })
+    );
+}
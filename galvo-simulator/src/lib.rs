@@ -0,0 +1,3 @@
+//! Pieces of the simulator that don't need a window.
+
+pub mod storage;
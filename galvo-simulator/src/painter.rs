@@ -14,11 +14,17 @@ use vector_apps::{
         cycle::Cycle,
         ilda::Ilda,
         maps::Maps,
+        marquee::Marquee,
         mbta::Mbta,
     },
     point::Point,
 };
 
+use galvo_simulator::storage::FileStorage;
+
+const STATE_FILE: &str = "galvo-state.txt";
+const SAVE_INTERVAL_FRAMES: u64 = 2000;
+
 pub struct SystemTimeSource;
 impl TimeSource for SystemTimeSource {
     fn now(&self) -> u64 {
@@ -35,11 +41,15 @@ pub fn painter(tx: Sender<Point>, rx: Receiver<Controls>) {
         // Box::new(CubeDemo::new()),
         // Box::new(Asteroids::new()),
         // Box::new(Maps::new()),
+        // Box::new(Marquee::new("HELLO FROM THE GALVOS")),
         // Box::new(Ilda::new()),
         // Box::new(Mbta::new()),
         // Box::new(Clock::new(SystemTimeSource)),
     ]);
 
+    let mut storage = FileStorage::open(STATE_FILE);
+    app.load_state(&storage);
+
     let mut frame = 0;
     loop {
         let path = app.get_path(frame);
@@ -53,6 +63,12 @@ pub fn painter(tx: Sender<Point>, rx: Receiver<Controls>) {
             app.handle_controls(controls);
         }
 
+        if frame % SAVE_INTERVAL_FRAMES == 0
+            && let Err(e) = app.save_state(&mut storage)
+        {
+            eprintln!("failed to save app state: {:?}", e);
+        }
+
         frame += 1;
     }
 }
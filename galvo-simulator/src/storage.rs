@@ -0,0 +1,82 @@
+use std::{fs, path::PathBuf};
+
+use vector_apps::storage::{MemoryStorage, Storage, StorageError};
+
+/// App state persisted to a text file, one `key value-in-hex` pair per line.
+pub struct FileStorage {
+    path: PathBuf,
+    cache: MemoryStorage,
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+impl FileStorage {
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let mut cache = MemoryStorage::new();
+
+        if let Ok(contents) = fs::read_to_string(&path) {
+            for line in contents.lines() {
+                if let Some((key, value)) = line.rsplit_once(' ')
+                    && let Some(value) = from_hex(value)
+                {
+                    let _ = cache.set(key, &value);
+                }
+            }
+        }
+
+        Self { path, cache }
+    }
+
+    fn flush(&self) -> Result<(), StorageError> {
+        let contents: String = self
+            .cache
+            .iter()
+            .map(|(key, value)| format!("{} {}\n", key, to_hex(value)))
+            .collect();
+
+        fs::write(&self.path, contents).map_err(|_| StorageError::Io)
+    }
+}
+
+impl Storage for FileStorage {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        self.cache.get(key)
+    }
+
+    fn set(&mut self, key: &str, value: &[u8]) -> Result<(), StorageError> {
+        if self.cache.get(key).as_deref() == Some(value) {
+            return Ok(());
+        }
+
+        self.cache.set(key, value)?;
+        self.flush()
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), StorageError> {
+        self.cache.remove(key)?;
+        self.flush()
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.cache.keys()
+    }
+
+    fn clear(&mut self) -> Result<(), StorageError> {
+        self.cache.clear()?;
+        self.flush()
+    }
+}
@@ -0,0 +1,64 @@
+use std::{fs, path::PathBuf};
+
+use galvo_simulator::storage::FileStorage;
+use vector_apps::storage::Storage;
+
+/// A state file of our own in the temp directory, removed when dropped.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "galvo-simulator-{}-{}.txt",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        Self(path)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn keeps_values_across_runs() {
+    let file = TempFile::new("runs");
+    let mut storage = FileStorage::open(&file.0);
+    assert!(storage.keys().is_empty());
+    storage.set_u32("cycle.idx", 5).unwrap();
+    storage.set("with space", b"\x00\xff").unwrap();
+    storage.set("gone", b"soon").unwrap();
+    storage.remove("gone").unwrap();
+
+    let storage = FileStorage::open(&file.0);
+    assert_eq!(storage.get_u32("cycle.idx"), Some(5));
+    assert_eq!(storage.get("with space").as_deref(), Some(&b"\x00\xff"[..]));
+    assert_eq!(storage.keys(), ["cycle.idx", "with space"]);
+    assert_eq!(
+        fs::read_to_string(&file.0).unwrap(),
+        "cycle.idx 05000000\nwith space 00ff\n"
+    );
+}
+
+#[test]
+fn skips_lines_it_cannot_read() {
+    let file = TempFile::new("bad");
+    fs::write(&file.0, "good 0102\nnovalue\nodd 123\nnothex zz\n").unwrap();
+    let storage = FileStorage::open(&file.0);
+    assert_eq!(storage.keys(), ["good"]);
+    assert_eq!(storage.get("good").as_deref(), Some(&[1, 2][..]));
+}
+
+#[test]
+fn clears_the_file() {
+    let file = TempFile::new("clear");
+    let mut storage = FileStorage::open(&file.0);
+    storage.set_u32("cycle.idx", 2).unwrap();
+    storage.clear().unwrap();
+    assert!(FileStorage::open(&file.0).keys().is_empty());
+    assert_eq!(fs::read_to_string(&file.0).unwrap(), "");
+}
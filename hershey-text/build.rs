@@ -1,58 +1,105 @@
-use std::{collections::HashMap, fs, path::PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::PathBuf,
+};
 
-#[derive(Debug, Copy, Clone)]
-struct PackedPoint {
-    pub x: i8,
-    pub y: i8,
-    pub pen: bool,
-}
-const NUM_GLYPHS: usize = 2500;
-type FontFile = [Option<Glyph>; NUM_GLYPHS];
+use stroke_font::{GlyphTableBuilder, JhfGlyph, Subset, optimize_strokes, parse_jhf};
 
-fn generate_rust(font: &[Option<Glyph>], mappings: &HashMap<String, FontMapping>) -> String {
-    let mut out = String::new();
+/// Glyphs by Hershey number.
+type FontFile = BTreeMap<u16, JhfGlyph>;
 
-    // Write the symbol table
-    out.push_str(&format!(
-        "static HERSHEY_FONT: [Option<Glyph>; {}] = [\n",
-        NUM_GLYPHS
-    ));
+/// The glyph set the Japanese font's numbers refer to.
+const JAPANESE_MAPPING: &str = "japan";
 
-    for glyph in font {
-        match glyph {
-            None => out.push_str("    None,\n"),
-            Some(g) => {
-                out.push_str("    Some(Glyph {\n");
-                out.push_str(&format!("        left: {},\n", g.left));
-                out.push_str(&format!("        right: {},\n", g.right));
-                out.push_str("        strokes: &[\n");
-
-                for p in &g.strokes {
-                    out.push_str(&format!(
-                        "            PackedPoint {{ x: {}, y: {}, pen: {} }},\n",
-                        p.x, p.y, p.pen
-                    ));
-                }
+struct Mapping {
+    name: String,
+    latin1: FontMapping,
+    unicode: UnicodeMapping,
+}
 
-                out.push_str("        ],\n    }),\n");
-            }
+/// Glyph numbers that characters in `subsets` map to, in the Japanese glyph
+/// set or the Western one.
+fn used_glyphs(mappings: &[Mapping], subsets: &[Subset], japanese: bool) -> BTreeSet<u16> {
+    let latin1 = (0..=255u8).map(char::from);
+
+    mappings
+        .iter()
+        .filter(|m| (m.name == JAPANESE_MAPPING) == japanese)
+        .flat_map(|m| {
+            let latin1 = latin1.clone().map(|c| (c, m.latin1[c as usize]));
+            latin1.chain(m.unicode.iter().copied())
+        })
+        .filter(|&(c, id)| id != 0 && subsets.contains(&Subset::of(c)))
+        .map(|(_, id)| id)
+        .collect()
+}
+
+/// Pack the glyphs that characters in enabled subsets map to, and the ones
+/// no character maps to if symbols are enabled.
+fn generate_table(
+    font: &FontFile,
+    mappings: &[Mapping],
+    subsets: &[Subset],
+    japanese: bool,
+) -> GlyphTableBuilder {
+    let used = used_glyphs(mappings, subsets, japanese);
+    let mapped = used_glyphs(mappings, &Subset::ALL, japanese);
+    let symbols = subsets.contains(&Subset::Symbols);
+
+    let mut table = GlyphTableBuilder::new();
+    for (&id, g) in font {
+        if used.contains(&id) || (symbols && !mapped.contains(&id)) {
+            let strokes = optimize_strokes(&g.strokes, (g.left, 0));
+            table.push(id, g.left, g.right, &strokes);
         }
     }
 
-    out.push_str("];\n");
+    table
+}
 
-    // Write the font lookup tables
-    out.push_str("pub mod fonts {\n");
+fn generate_rust(
+    font: &FontFile,
+    japanese: Option<&FontFile>,
+    mappings: &[Mapping],
+    subsets: &[Subset],
+) -> (String, Vec<(&'static str, Vec<u8>)>) {
+    let mut out = String::new();
+    let mut data = Vec::new();
+
+    // Write the symbol tables
+    let table = generate_table(font, mappings, subsets, false);
+    out.push_str(&table.to_rust("HERSHEY_FONT", "hershey_font.bin"));
+    data.push(("hershey_font.bin", table.data().to_vec()));
 
-    for (name, data) in mappings {
-        let parts: Vec<_> = name.split(".").collect();
+    if let Some(japanese) = japanese {
+        let table = generate_table(japanese, mappings, subsets, true);
+        out.push_str(&table.to_rust("JAPANESE_FONT", "japanese_font.bin"));
+        data.push(("japanese_font.bin", table.data().to_vec()));
+    }
+
+    // Write the font lookup tables, without code points past Latin-1 that
+    // are in disabled subsets
+    let unicode: Vec<UnicodeMapping> = mappings
+        .iter()
+        .map(|m| {
+            m.unicode
+                .iter()
+                .copied()
+                .filter(|&(c, _)| subsets.contains(&Subset::of(c)))
+                .collect()
+        })
+        .collect();
+
+    out.push_str("pub mod fonts {\n");
 
+    for (mapping, unicode) in mappings.iter().zip(&unicode) {
         out.push_str(&format!(
             "  pub static {}: [u16; 256] = [\n    ",
-            parts[0].to_uppercase()
+            mapping.name.to_uppercase()
         ));
 
-        for (i, v) in data.iter().enumerate() {
+        for (i, v) in mapping.latin1.iter().enumerate() {
             out.push_str(&format!("{},\t", v));
             if i % 16 == 15 {
                 out.push_str("\n    ");
@@ -60,99 +107,61 @@ fn generate_rust(font: &[Option<Glyph>], mappings: &HashMap<String, FontMapping>
         }
 
         out.push_str("\n  ];\n\n");
-    }
-
-    out.push_str("}\n");
-
-    out
-}
-
-#[derive(Debug, Clone)]
-struct Glyph {
-    pub left: i8,
-    pub right: i8,
-    pub strokes: Vec<PackedPoint>,
-}
-
-impl Glyph {
-    fn from_line(line: &str) -> Result<(u16, Self), ()> {
-        let mut chars = line.chars();
-
-        let id: String = chars.by_ref().take(5).collect();
-        let id: u16 = id.trim().parse().map_err(|_| ())?;
-        let _space = chars.next();
-        let _vertex_count: String = chars.by_ref().take(2).collect();
 
-        let coords: Vec<char> = chars.collect();
+        // Kept when its subsets are disabled, so code naming it still builds
+        if !mapping.unicode.is_empty() {
+            out.push_str(&format!(
+                "  pub static {}_UNICODE: [(char, u16); {}] = [\n",
+                mapping.name.to_uppercase(),
+                unicode.len()
+            ));
 
-        let left = coords[0] as i32 - 'R' as i32;
-        let right = coords[1] as i32 - 'R' as i32;
-
-        let mut strokes = Vec::new();
-        let mut pen = false;
-
-        let mut iter = coords[2..].iter();
-
-        while let (Some(&xch), Some(&ych)) = (iter.next(), iter.next()) {
-            if xch == ' ' && ych == 'R' {
-                // lift pen for the next stroke
-                pen = false;
-                continue;
+            for (c, id) in unicode {
+                out.push_str(&format!("    ('\\u{{{:x}}}', {}),\n", *c as u32, id));
             }
 
-            let x = xch as i32 - 'R' as i32;
-            let y = ych as i32 - 'R' as i32;
-            strokes.push(PackedPoint {
-                x: x as i8,
-                y: y as i8,
-                pen,
-            });
-            // drop the pen for the rest of this stroke
-            pen = true;
+            out.push_str("  ];\n\n");
         }
-
-        Ok((
-            id,
-            Self {
-                left: left as i8,
-                right: right as i8,
-                strokes,
-            },
-        ))
     }
-}
 
-fn load_file(file: &str) -> FontFile {
-    let mut result = [const { None }; NUM_GLYPHS];
-    let mut lines = file.lines();
+    out.push_str("}\n");
 
-    loop {
-        let next_line = lines.next();
+    // And the table to look them up by name
+    out.push_str(&format!(
+        "static FONTS_BY_NAME: [(&str, HersheyFont<'static>); {}] = [\n",
+        mappings.len()
+    ));
 
-        match next_line {
-            Some("") => continue,
-            Some(line) => {
-                let mut full = line.to_owned();
-                let mut last_line = line;
+    for (mapping, unicode) in mappings.iter().zip(&unicode) {
+        let upper = mapping.name.to_uppercase();
+        let unicode = if unicode.is_empty() {
+            String::from("&[]")
+        } else {
+            format!("&fonts::{}_UNICODE", upper)
+        };
+        let glyphs = if mapping.name == JAPANESE_MAPPING {
+            "Japanese"
+        } else {
+            "Occidental"
+        };
 
-                while last_line.len() == 72 {
-                    let line = lines.next().unwrap_or("");
-                    full += line;
-                    last_line = line;
-                }
+        out.push_str(&format!(
+            "    (\"{}\", HersheyFont {{ mapping: &fonts::{}, unicode: {}, glyphs: GlyphSet::{} }}),\n",
+            mapping.name, upper, unicode, glyphs
+        ));
+    }
 
-                let glyph = Glyph::from_line(&full);
+    out.push_str("];\n");
 
-                if let Ok((id, glyph)) = glyph {
-                    let id = id as usize;
-                    if id < NUM_GLYPHS {
-                        result[id] = Some(glyph);
-                    }
-                }
-            }
-            None => break result,
-        }
-    }
+    (out, data)
+}
+
+fn load_file(name: &str, file: &str) -> FontFile {
+    parse_jhf(file)
+        .unwrap_or_else(|e| panic!("{}: {}", name, e))
+        .into_iter()
+        .map(|g| (g.id, g))
+        .collect()
 }
 
 pub type FontMapping = [u16; 256];
@@ -191,24 +200,98 @@ pub fn load_mapping(file: &str) -> Result<FontMapping, ()> {
     Ok(result)
 }
 
+/// Code points past Latin-1, sorted for searching.
+pub type UnicodeMapping = Vec<(char, u16)>;
+
+/// Load a `.ump` file. Each line is a hex code point followed by a range of
+/// glyph numbers like in a `.hmp` file, which go to consecutive code points.
+pub fn load_unicode_mapping(file: &str) -> Result<UnicodeMapping, String> {
+    let mut result = BTreeMap::new();
+
+    for line in file.lines() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let parts: Vec<_> = line.split(' ').collect();
+        let [codepoint, first, last] = parts[..] else {
+            return Err(format!("bad line: {}", line));
+        };
+
+        let codepoint = u32::from_str_radix(codepoint, 16).map_err(|e| e.to_string())?;
+        let first: u16 = first.parse().map_err(|_| format!("bad line: {}", line))?;
+        let mut last: u16 = last.parse().map_err(|_| format!("bad line: {}", line))?;
+        if last == 0 {
+            last = first;
+        }
+
+        for (i, idx) in (first..=last).enumerate() {
+            let c = char::from_u32(codepoint + i as u32)
+                .ok_or_else(|| format!("bad code point in: {}", line))?;
+            result.insert(c, idx);
+        }
+    }
+
+    Ok(result.into_iter().collect())
+}
+
 fn main() {
+    let subsets: Vec<_> = Subset::ALL
+        .into_iter()
+        .filter(|s| std::env::var_os(s.feature_env()).is_some())
+        .collect();
+    let japanese = subsets.contains(&Subset::Cjk);
+
     let hershey = fs::read_to_string("data/hershey.jhf").unwrap();
+    let glyphs = load_file("hershey.jhf", &hershey);
 
-    let glyphs = load_file(&hershey);
+    let japanese_glyphs = japanese.then(|| {
+        load_file(
+            "japanese.jhf",
+            &fs::read_to_string("data/japanese.jhf").unwrap(),
+        )
+    });
 
-    let mut mappings: HashMap<String, FontMapping> = HashMap::new();
+    let mut mappings = Vec::new();
 
     for file in fs::read_dir("data/mappings").unwrap() {
-        let file = file.unwrap();
-        let contents = fs::read_to_string(file.path()).unwrap();
-        let result = load_mapping(&contents);
-        mappings.insert(file.file_name().into_string().unwrap(), result.unwrap());
+        let path = file.unwrap().path();
+        if path.extension().is_none_or(|e| e != "hmp") {
+            continue;
+        }
+
+        let name = path.file_stem().unwrap().to_str().unwrap().to_owned();
+        if name == JAPANESE_MAPPING && !japanese {
+            continue;
+        }
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let unicode = match fs::read_to_string(path.with_extension("ump")) {
+            Ok(contents) => {
+                load_unicode_mapping(&contents).unwrap_or_else(|e| panic!("{}.ump: {}", name, e))
+            }
+            Err(_) => Vec::new(),
+        };
+
+        mappings.push(Mapping {
+            name,
+            latin1: load_mapping(&contents).unwrap(),
+            unicode,
+        });
     }
 
+    mappings.sort_by(|a, b| a.name.cmp(&b.name));
+
     let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
     let out_file = out_dir.join("hershey_font.rs");
 
-    fs::write(out_file, generate_rust(&glyphs, &mappings)).unwrap();
+    let (source, data) = generate_rust(&glyphs, japanese_glyphs.as_ref(), &mappings, &subsets);
+    fs::write(out_file, source).unwrap();
+    for (name, data) in data {
+        fs::write(out_dir.join(name), data).unwrap();
+    }
 
     println!("cargo:rerun-if-changed=data/hershey.jhf");
+    println!("cargo:rerun-if-changed=data/japanese.jhf");
+    println!("cargo:rerun-if-changed=data/mappings");
 }
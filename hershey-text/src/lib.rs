@@ -1,67 +1,108 @@
 #![no_std]
 
-extern crate alloc;
+pub use stroke_font::{
+    Bounds, FontMetrics, KernedFont, Point, StrokeFont, TextMetrics, measure_text, render_text,
+    text_width,
+};
+use stroke_font::{Glyph, GlyphTable, HERSHEY_METRICS};
 
-use alloc::vec::Vec;
+pub type FontMapping = [u16; 256];
 
-#[derive(Debug, Copy, Clone)]
-struct PackedPoint {
-    pub x: i8,
-    pub y: i8,
-    pub pen: bool,
-}
+/// Hershey glyph numbers for code points past Latin-1, sorted by code point.
+pub type UnicodeMapping = [(char, u16)];
 
-#[derive(Debug, Copy, Clone)]
-struct Glyph {
-    pub left: i8,
-    pub right: i8,
-    pub strokes: &'static [PackedPoint],
+/// Which numbering a font's glyph numbers refer to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GlyphSet {
+    /// The Western glyphs in `hershey.jhf`.
+    Occidental,
+    /// Kana and kanji from `japanese.jhf`.
+    #[cfg(feature = "cjk")]
+    Japanese,
 }
 
-pub struct Point {
-    pub x: i16,
-    pub y: i16,
-    pub pen: bool,
+include!(concat!(env!("OUT_DIR"), "/hershey_font.rs"));
+
+/// One of the Hershey fonts, selected by a mapping from Latin-1 code points
+/// to Hershey glyph numbers (see [`fonts`]) and optionally one for the rest
+/// of Unicode.
+#[derive(Debug, Copy, Clone)]
+pub struct HersheyFont<'a> {
+    mapping: &'a FontMapping,
+    unicode: &'a UnicodeMapping,
+    glyphs: GlyphSet,
 }
 
-impl Default for Point {
-    fn default() -> Self {
+impl<'a> HersheyFont<'a> {
+    pub const fn new(mapping: &'a FontMapping) -> Self {
         Self {
-            x: 0,
-            y: 0,
-            pen: false,
+            mapping,
+            unicode: &[],
+            glyphs: GlyphSet::Occidental,
         }
     }
-}
 
-pub type FontMapping = [u16; 256];
+    /// Use `unicode`, which must be sorted, for code points past Latin-1.
+    pub const fn with_unicode(mut self, unicode: &'a UnicodeMapping) -> Self {
+        self.unicode = unicode;
+        self
+    }
 
-include!(concat!(env!("OUT_DIR"), "/hershey_font.rs"));
+    pub const fn with_glyphs(mut self, glyphs: GlyphSet) -> Self {
+        self.glyphs = glyphs;
+        self
+    }
 
-pub fn render_text(text: &str, mapping: FontMapping) -> Vec<Point> {
-    let mut result = Vec::new();
-    let mut x_idx = 0;
+    /// Look up a font by its mapping file name, like `"romans"`, ignoring
+    /// case.
+    pub fn by_name(name: &str) -> Option<&'static HersheyFont<'static>> {
+        FONTS_BY_NAME
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, font)| font)
+    }
+
+    /// Names accepted by [`HersheyFont::by_name`], in alphabetical order.
+    pub fn names() -> impl Iterator<Item = &'static str> {
+        FONTS_BY_NAME.iter().map(|(name, _)| *name)
+    }
 
-    for character in text.chars() {
-        if character > 255 as char {
-            continue;
+    /// Look up a glyph by its Hershey number, for glyphs that no code point
+    /// maps to.
+    pub fn hershey_glyph(&self, id: u16) -> Option<Glyph<'static>> {
+        match self.glyphs {
+            GlyphSet::Occidental => HERSHEY_FONT.get(id),
+            #[cfg(feature = "cjk")]
+            GlyphSet::Japanese => JAPANESE_FONT.get(id),
         }
+    }
+}
 
-        let hershey_id = mapping[character as usize] as usize;
+/// The kana and kanji font by Unicode code point only. Unlike `"japan"`, it
+/// leaves ASCII alone rather than drawing kana for it.
+#[cfg(feature = "cjk")]
+pub const JAPANESE: HersheyFont<'static> = HersheyFont::new(&[0; 256])
+    .with_unicode(&fonts::JAPAN_UNICODE)
+    .with_glyphs(GlyphSet::Japanese);
 
-        if hershey_id == 0 || hershey_id >= HERSHEY_FONT.len() {
-            continue;
-        }
+impl StrokeFont for HersheyFont<'_> {
+    fn glyph(&self, c: char) -> Option<Glyph<'_>> {
+        let hershey_id = match self.mapping.get(c as usize) {
+            Some(&id) => id,
+            None => self
+                .unicode
+                .binary_search_by_key(&c, |(c, _)| *c)
+                .map_or(0, |i| self.unicode[i].1),
+        };
 
-        if let Some(glyph) = HERSHEY_FONT[hershey_id] {
-            result.extend(glyph.strokes.iter().map(|point| Point {
-                x: point.x as i16 - glyph.left as i16 + x_idx,
-                y: point.y as i16,
-                pen: point.pen,
-            }));
-            x_idx += glyph.right as i16 - glyph.left as i16;
+        if hershey_id == 0 {
+            return None;
         }
+
+        self.hershey_glyph(hershey_id)
     }
 
-    result
+    fn metrics(&self) -> FontMetrics {
+        HERSHEY_METRICS
+    }
 }
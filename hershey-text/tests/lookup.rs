@@ -0,0 +1,62 @@
+use hershey_text::{HersheyFont, StrokeFont, fonts};
+
+/// A glyph's shape, to compare glyphs found different ways.
+fn shape(font: &HersheyFont, c: char) -> Option<String> {
+    font.glyph(c).map(|g| format!("{:?}", g))
+}
+
+fn hershey_shape(font: &HersheyFont, id: u16) -> Option<String> {
+    font.hershey_glyph(id).map(|g| format!("{:?}", g))
+}
+
+#[test]
+fn finds_fonts_by_name_ignoring_case() {
+    let romans = HersheyFont::by_name("romans").unwrap();
+    for name in ["ROMANS", "RomanS"] {
+        let font = HersheyFont::by_name(name).unwrap();
+        assert_eq!(shape(font, 'A'), shape(romans, 'A'));
+    }
+    assert!(HersheyFont::by_name("romans ").is_none());
+    assert!(HersheyFont::by_name("helvetica").is_none());
+    assert!(HersheyFont::by_name("").is_none());
+}
+
+#[test]
+fn lists_font_names_in_order() {
+    let names: Vec<_> = HersheyFont::names().collect();
+    assert!(names.is_sorted());
+    for name in ["cyrilc", "greeks", "romans", "scripts"] {
+        assert!(names.contains(&name), "{} missing", name);
+    }
+    assert!(names.iter().all(|n| HersheyFont::by_name(n).is_some()));
+}
+
+#[test]
+#[cfg(feature = "greek-cyrillic")]
+fn maps_unicode_to_hershey_glyphs() {
+    let romans = HersheyFont::new(&fonts::ROMANS);
+    assert_eq!(shape(&romans, 'A'), hershey_shape(&romans, 501));
+    // Nothing past Latin-1 without a Unicode mapping
+    assert_eq!(shape(&romans, '\u{391}'), None);
+
+    let greeks = HersheyFont::by_name("greeks").unwrap();
+    assert_eq!(shape(greeks, '\u{391}'), hershey_shape(greeks, 527));
+    assert_eq!(shape(greeks, '\u{394}'), hershey_shape(greeks, 530));
+
+    let cyrilc = HersheyFont::by_name("cyrilc").unwrap();
+    assert_eq!(shape(cyrilc, '\u{410}'), hershey_shape(cyrilc, 2801));
+    // Code points between the mapped ones have no glyph
+    assert_eq!(shape(cyrilc, '\u{4ff}'), None);
+}
+
+#[test]
+#[cfg(not(feature = "greek-cyrillic"))]
+fn leaves_disabled_subsets_unmapped() {
+    // The mappings are still there to name, just empty
+    assert!(fonts::GREEKS_UNICODE.is_empty());
+    assert!(fonts::CYRILC_UNICODE.is_empty());
+
+    let greeks = HersheyFont::by_name("greeks").unwrap();
+    assert_eq!(shape(greeks, '\u{391}'), None);
+    assert!(shape(greeks, 'A').is_some());
+}
@@ -1,48 +1,30 @@
 use std::{collections::HashMap, fs, path::PathBuf};
 
-#[derive(Debug, Copy, Clone)]
-struct PackedPoint {
-    pub x: i8,
-    pub y: i8,
-    pub pen: bool,
-}
+use stroke_font::{GlyphTableBuilder, PackedPoint, Subset, optimize_strokes};
 
 const NUM_GLYPHS: usize = 0x27FF;
 type FontFile = [Option<Glyph>; NUM_GLYPHS];
 
-fn generate_rust(font: &[Option<Glyph>]) -> String {
-    let mut out = String::new();
-
-    // Write the symbol table
-    out.push_str(&format!(
-        "static NEWSTROKE_FONT: [Option<Glyph>; {}] = [\n",
-        font.len()
-    ));
-
-    for glyph in font {
-        match glyph {
-            None => out.push_str("    None,\n"),
-            Some(g) => {
-                out.push_str("    Some(Glyph {\n");
-                out.push_str(&format!("        left: {},\n", g.left));
-                out.push_str(&format!("        right: {},\n", g.right));
-                out.push_str("        strokes: &[\n");
-
-                for p in &g.strokes {
-                    out.push_str(&format!(
-                        "            PackedPoint {{ x: {}, y: {}, pen: {} }},\n",
-                        p.x, p.y, p.pen
-                    ));
-                }
+fn generate_rust(font: &[Option<Glyph>], subsets: &[Subset]) -> (String, Vec<u8>) {
+    // Write the symbol table, leaving out glyphs in disabled subsets
+    let mut table = GlyphTableBuilder::new();
 
-                out.push_str("        ],\n    }),\n");
-            }
+    for (c, glyph) in font.iter().enumerate() {
+        let Some(g) = glyph else {
+            continue;
+        };
+
+        let included = char::from_u32(c as u32).is_some_and(|c| subsets.contains(&Subset::of(c)));
+        if included {
+            let strokes = optimize_strokes(&g.strokes, (g.left, 0));
+            table.push(c as u16, g.left, g.right, &strokes);
         }
     }
 
-    out.push_str("];\n");
-
-    out
+    (
+        table.to_rust("NEWSTROKE_FONT", "newstroke_font.bin"),
+        table.data().to_vec(),
+    )
 }
 
 #[derive(Debug, Clone)]
@@ -335,15 +317,47 @@ fn build_single(raw: &HashMap<String, RawGlyph>, name: &str) -> Option<Glyph> {
     }
 }
 
+/// Where to put an accent on a base glyph: the base's pin, and the accent's
+/// pin to line up with it. A `-` or empty name means the glyph's origin.
+struct Attach<'a> {
+    base_pin: &'a str,
+    accent_pin: &'a str,
+}
+
+impl<'a> Attach<'a> {
+    /// Accents without pins are drawn where they are, like `GRAVE` over
+    /// `A_SMALL` or `CEDILLA` under `C_CAP`.
+    const DEFAULT: Attach<'static> = Attach {
+        base_pin: "-",
+        accent_pin: "-",
+    };
+
+    fn parse(spec: &'a str) -> Option<Self> {
+        let (base_pin, accent_pin) = spec.split_once('=')?;
+        Some(Self {
+            base_pin,
+            accent_pin,
+        })
+    }
+}
+
+fn pin(glyph: &RawGlyph, name: &str) -> (i8, i8) {
+    if name.is_empty() || name == "-" {
+        return (0, 0);
+    }
+
+    glyph.anchors.get(name).copied().unwrap_or((0, 0))
+}
+
 fn anchor_offset(
     base: &RawGlyph,
     accent: &RawGlyph,
-    anchor: &str,
+    attach: &Attach,
     base_tr: &Transform,
     accent_tr: &Transform,
 ) -> (i8, i8) {
-    let (bx, by) = base.anchors.get(anchor).copied().unwrap_or((0, 0));
-    let (ax, ay) = accent.anchors.get(anchor).copied().unwrap_or((0, 0));
+    let (bx, by) = pin(base, attach.base_pin);
+    let (ax, ay) = pin(accent, attach.accent_pin);
 
     let ox = bx * base_tr.sx - ax * accent_tr.sx;
     let oy = by * base_tr.sy + base_tr.oy - ay * accent_tr.sy - accent_tr.oy;
@@ -351,43 +365,47 @@ fn anchor_offset(
     (ox, oy)
 }
 
-fn compose_two(raw: &HashMap<String, RawGlyph>, a: &str, b: &str) -> Option<Glyph> {
-    let (ta, a_name) = split_transform(a);
-    let (tb, b_name) = split_transform(b);
-
-    let base = match raw.get(a_name) {
-        Some(base) => base,
-        None => {
-            eprintln!(
-                "Failed to find glyph for A name: {} (combining with B name: {})",
-                a_name, b_name
-            );
-            return None;
-        }
-    };
-
-    let acc = match raw.get(b_name) {
-        Some(acc) => acc,
-        None => {
-            eprintln!(
-                "Failed to find glyph for B name: {} (combining with A name: {})",
-                b_name, a_name
-            );
-            return None;
-        }
-    };
+fn lookup<'a>(raw: &'a HashMap<String, RawGlyph>, name: &str, line: &str) -> Option<&'a RawGlyph> {
+    let glyph = raw.get(name);
+    if glyph.is_none() {
+        eprintln!("Failed to find glyph for name: {} (in: {})", name, line);
+    }
+    glyph
+}
 
-    let (ox, oy) = anchor_offset(base, acc, "ABOVE", &ta, &tb);
+/// Build a glyph from a base followed by any number of accents, each
+/// optionally followed by how to attach it, like `A_CAP GRAVE ABOVE=X`.
+fn compose(raw: &HashMap<String, RawGlyph>, parts: &[&str], line: &str) -> Option<Glyph> {
+    let (ta, a_name) = split_transform(parts[0]);
+    let base = lookup(raw, a_name, line)?;
 
     let mut strokes = render_glyph(base, &ta, 0, 0);
-    strokes.extend(render_glyph(acc, &tb, ox, oy));
+    let (mut left, mut right) = transform_metrics(base, &ta);
+
+    let mut rest = parts[1..].iter().peekable();
+    while let Some(accent) = rest.next() {
+        let attach = match rest.peek().and_then(|spec| Attach::parse(spec)) {
+            Some(attach) => {
+                rest.next();
+                attach
+            }
+            None => Attach::DEFAULT,
+        };
+
+        let (tb, b_name) = split_transform(accent);
+        let acc = lookup(raw, b_name, line)?;
 
-    let (l1, r1) = transform_metrics(base, &ta);
-    let (l2, r2) = transform_metrics(acc, &tb);
+        let (ox, oy) = anchor_offset(base, acc, &attach, &ta, &tb);
+        strokes.extend(render_glyph(acc, &tb, ox, oy));
+
+        let (l, r) = transform_metrics(acc, &tb);
+        left = left.min(l + ox);
+        right = right.max(r + ox);
+    }
 
     Some(Glyph {
-        left: l1.min(l2 + ox),
-        right: r1.max(r2 + ox),
+        left,
+        right,
         strokes,
     })
 }
@@ -404,6 +422,9 @@ fn parse_charlist(input: &str, font: &HashMap<String, RawGlyph>) -> FontFile {
             continue;
         }
 
+        // Drop trailing comments
+        let line = line.split(" #").next().unwrap_or(line);
+
         let parts: Vec<&str> = line.split_whitespace().collect();
         if parts.is_empty() {
             continue;
@@ -434,12 +455,12 @@ fn parse_charlist(input: &str, font: &HashMap<String, RawGlyph>) -> FontFile {
                 let glyph = match parts.len() {
                     2 => build_single(font, parts[1]),
 
-                    3 => compose_two(font, parts[1], parts[2]),
-
-                    _ => {
+                    1 => {
                         eprintln!("unsupported + form at line {}: {}", lineno + 1, line);
                         None
                     }
+
+                    _ => compose(font, &parts[1..], line),
                 };
 
                 out[codepoint] = glyph;
@@ -492,7 +513,14 @@ fn main() {
     let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
     let out_file = out_dir.join("newstroke_font.rs");
 
-    fs::write(out_file, generate_rust(&glyphs)).unwrap();
+    let subsets: Vec<_> = Subset::ALL
+        .into_iter()
+        .filter(|s| std::env::var_os(s.feature_env()).is_some())
+        .collect();
+
+    let (source, data) = generate_rust(&glyphs, &subsets);
+    fs::write(out_file, source).unwrap();
+    fs::write(out_dir.join("newstroke_font.bin"), data).unwrap();
 
     println!("cargo:rerun-if-changed=data/charlist.txt");
     println!("cargo:rerun-if-changed=data/CJK.lib");
@@ -0,0 +1,39 @@
+// Accented letters are all past ASCII
+#![cfg(feature = "latin")]
+
+use newstroke_text::{Newstroke, StrokeFont};
+
+fn stroke_count(c: char) -> usize {
+    let glyph = Newstroke
+        .glyph(c)
+        .unwrap_or_else(|| panic!("no glyph for {:?}", c));
+    glyph.points().filter(|p| !p.pen).count()
+}
+
+#[test]
+fn composes_accented_letters() {
+    // A stem and a dot, from a base and one accent
+    assert_eq!(stroke_count('i'), stroke_count('\u{131}') + 1);
+    assert!(Newstroke.glyph('j').is_some());
+
+    // Accents sit above the base, which keeps its own strokes
+    let e = Newstroke.glyph('e').unwrap();
+    let e_acute = Newstroke.glyph('\u{e9}').unwrap();
+    assert!(stroke_count('\u{e9}') > stroke_count('e'));
+    assert_eq!(e_acute.advance(), e.advance());
+    assert!(e_acute.bounds().unwrap().min_y < e.bounds().unwrap().min_y);
+}
+
+#[test]
+fn draws_unpinned_accents_in_place() {
+    // A cedilla with no pins hangs below the C rather than moving to its top
+    let c = Newstroke.glyph('C').unwrap().bounds().unwrap();
+    let c_cedilla = Newstroke.glyph('\u{c7}').unwrap().bounds().unwrap();
+    assert_eq!(c_cedilla.min_y, c.min_y);
+    assert!(c_cedilla.max_y > c.max_y);
+
+    // Both strokes of the dollar sign cross the S
+    let s = Newstroke.glyph('S').unwrap().bounds().unwrap();
+    let dollar = Newstroke.glyph('$').unwrap().bounds().unwrap();
+    assert!(dollar.min_y < s.min_y && dollar.max_y > s.max_y);
+}
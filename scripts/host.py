@@ -1,5 +1,6 @@
 import serial
 import json
+import sys
 import time
 
 PORT = "/dev/cu.usbmodem1201"
@@ -14,12 +15,31 @@ def main():
 
     time.sleep(2)  # give the device time to reset
 
-    payload = {
-        "cmd": "SetIndicatorLight",
-        "r": 127,
-        "g": 127,
-        "b": 127,
-    }
+    cmd = sys.argv[1] if len(sys.argv) > 1 else "SetIndicatorLight"
+
+    if cmd == "SetIndicatorLight":
+        payload = {
+            "cmd": "SetIndicatorLight",
+            "r": 127,
+            "g": 127,
+            "b": 127,
+        }
+    elif cmd == "ShowMessage":
+        # e.g. python host.py ShowMessage "{red}HELLO {size=2}world"
+        payload = {
+            "cmd": "ShowMessage",
+            "text": sys.argv[2] if len(sys.argv) > 2 else "{red}HELLO {/}world",
+        }
+    elif cmd == "SetDmxPatch":
+        # e.g. python host.py SetDmxPatch 1 101
+        payload = {
+            "cmd": "SetDmxPatch",
+            "universe": int(sys.argv[2]) if len(sys.argv) > 2 else 1,
+            "address": int(sys.argv[3]) if len(sys.argv) > 3 else 1,
+        }
+    else:
+        # e.g. DumpStorage, ClearStorage, ClearMessage
+        payload = {"cmd": cmd}
 
     # newline-delimited JSON
     msg = json.dumps(payload) + "\n"
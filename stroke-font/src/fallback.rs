@@ -0,0 +1,109 @@
+use crate::{FontMetrics, Glyph, GlyphScale, PackedPoint, StrokeFont, Strokes};
+
+const fn p(x: i8, y: i8, pen: bool) -> PackedPoint {
+    PackedPoint { x, y, pen }
+}
+
+/// The replacement glyph stands on y = 0 and is 6 units tall.
+const REPLACEMENT_METRICS: FontMetrics = FontMetrics {
+    baseline: 0,
+    line_height: 6,
+    ascent: 6,
+    descent: 0,
+    cap_height: 6,
+};
+
+/// A crossed out box, drawn for characters no font has.
+static REPLACEMENT_STROKES: [PackedPoint; 6] = [
+    p(0, 0, false),
+    p(0, -6, true),
+    p(4, -6, true),
+    p(4, 0, true),
+    p(0, 0, true),
+    p(4, -6, true),
+];
+
+/// Tries each of a list of fonts in turn, so text can use characters that no
+/// single font covers. Glyphs from later fonts are scaled to match the cap
+/// height and baseline of the first.
+#[derive(Copy, Clone)]
+pub struct FallbackFont<'a> {
+    fonts: &'a [&'a dyn StrokeFont],
+    replacement: bool,
+}
+
+impl<'a> FallbackFont<'a> {
+    pub const fn new(fonts: &'a [&'a dyn StrokeFont]) -> Self {
+        Self {
+            fonts,
+            replacement: true,
+        }
+    }
+
+    /// Skip characters no font has instead of drawing a replacement box.
+    pub const fn without_replacement(mut self) -> Self {
+        self.replacement = false;
+        self
+    }
+
+    /// Scale from a font with `from` metrics to the first font's.
+    fn rescale(&self, from: FontMetrics) -> GlyphScale {
+        let to = self.metrics();
+        if from.cap_height <= 0 || (from.cap_height, from.baseline) == (to.cap_height, to.baseline)
+        {
+            return GlyphScale::IDENTITY;
+        }
+
+        let scale = GlyphScale {
+            num: to.cap_height,
+            den: from.cap_height,
+            dy: 0,
+        };
+        GlyphScale {
+            dy: to.baseline - scale.x(from.baseline),
+            ..scale
+        }
+    }
+}
+
+impl StrokeFont for FallbackFont<'_> {
+    fn glyph(&self, c: char) -> Option<Glyph<'_>> {
+        if c.is_control() {
+            return None;
+        }
+
+        for font in self.fonts {
+            if let Some(glyph) = font.glyph(c) {
+                return Some(Glyph {
+                    scale: glyph.scale.then(self.rescale(font.metrics())),
+                    ..glyph
+                });
+            }
+        }
+
+        self.replacement.then(|| Glyph {
+            left: -1,
+            right: 5,
+            scale: self.rescale(REPLACEMENT_METRICS),
+            strokes: Strokes::Points(&REPLACEMENT_STROKES),
+        })
+    }
+
+    fn metrics(&self) -> FontMetrics {
+        self.fonts
+            .first()
+            .map_or(REPLACEMENT_METRICS, |font| font.metrics())
+    }
+
+    /// Pairs are kerned only when both characters come from the same font.
+    fn kerning(&self, left: char, right: char) -> i16 {
+        let font_for = |c| self.fonts.iter().position(|font| font.glyph(c).is_some());
+        match (font_for(left), font_for(right)) {
+            (Some(a), Some(b)) if a == b => {
+                let font = self.fonts[a];
+                self.rescale(font.metrics()).x(font.kerning(left, right))
+            }
+            _ => 0,
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Building glyph tables from fonts in other formats, for build scripts.
+//!
+//! A build script can turn any single-line `.svg` or `.jhf` font into a
+//! [`TableFont`] without a crate of its own:
+//!
+//! ```ignore
+//! let font = ImportedFont::from_svg(&fs::read_to_string("fonts/allure.svg")?)?;
+//! let (source, data) = font.to_rust("ALLURE", "allure.bin", &Subset::ALL);
+//! fs::write(out_dir.join("allure.rs"), source)?;
+//! fs::write(out_dir.join("allure.bin"), data)?;
+//! ```
+//!
+//! and then `include!` the source where `GlyphTable`, `TableFont` and
+//! `FontMetrics` are in scope.
+
+use alloc::{collections::btree_map::BTreeMap, format, string::String, vec::Vec};
+use core::fmt;
+
+use crate::{
+    FontMetrics, Glyph, GlyphTable, GlyphTableBuilder, HERSHEY_METRICS, PackedPoint, StrokeFont,
+    Subset, jhf::parse_jhf, optimize_strokes, svg::parse_svg_font,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportError {
+    /// A line of a `.jhf` file, counting from 1, that isn't a glyph.
+    BadJhfLine(usize),
+    /// An SVG tag or comment that never ends.
+    UnclosedTag,
+    /// An SVG attribute without a quoted value, or with a bad number in it.
+    BadAttribute,
+    /// An `&...;` reference that isn't a character.
+    BadEntity,
+    /// The glyph for this character has path data that can't be read.
+    BadPath(char),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::BadJhfLine(line) => write!(f, "line {} is not a glyph", line),
+            ImportError::UnclosedTag => write!(f, "unclosed tag"),
+            ImportError::BadAttribute => write!(f, "bad attribute"),
+            ImportError::BadEntity => write!(f, "bad character reference"),
+            ImportError::BadPath(c) => write!(f, "bad path for glyph {:?}", c),
+        }
+    }
+}
+
+impl core::error::Error for ImportError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedGlyph {
+    pub left: i8,
+    pub right: i8,
+    pub strokes: Vec<PackedPoint>,
+}
+
+/// A font read from another format, ready to be packed into a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedFont {
+    pub glyphs: BTreeMap<char, ImportedGlyph>,
+    pub metrics: FontMetrics,
+}
+
+fn to_i8(v: f32) -> i8 {
+    libm::roundf(v).clamp(i8::MIN as f32, i8::MAX as f32) as i8
+}
+
+impl ImportedFont {
+    /// Read a `.jhf` file whose glyphs are the characters from space
+    /// onwards, in order, like most Hershey fonts found outside the
+    /// original distribution.
+    pub fn from_jhf(file: &str) -> Result<Self, ImportError> {
+        let glyphs = parse_jhf(file)?
+            .into_iter()
+            .zip(' '..=char::MAX)
+            .map(|(g, c)| {
+                let glyph = ImportedGlyph {
+                    left: g.left,
+                    right: g.right,
+                    strokes: g.strokes,
+                };
+                (c, glyph)
+            })
+            .collect();
+
+        Ok(Self {
+            glyphs,
+            metrics: HERSHEY_METRICS,
+        })
+    }
+
+    /// Read an SVG font, scaled so its capitals are as tall as a Hershey
+    /// font's. Glyphs start at x = 0 and end at their advance.
+    pub fn from_svg(file: &str) -> Result<Self, ImportError> {
+        let cap_height = HERSHEY_METRICS.cap_height as f32;
+        let font = parse_svg_font(file, cap_height)?;
+        let scale = cap_height / font.face.cap_height;
+        let baseline = HERSHEY_METRICS.baseline as f32;
+
+        let mut glyphs = BTreeMap::new();
+        for glyph in font.glyphs {
+            let mut strokes: Vec<PackedPoint> = Vec::new();
+            for stroke in &glyph.strokes {
+                let mut pen = false;
+                for &(x, y) in stroke {
+                    let (x, y) = (to_i8(x * scale), to_i8(baseline - y * scale));
+                    if pen && strokes.last().is_some_and(|p| (p.x, p.y) == (x, y)) {
+                        continue;
+                    }
+                    strokes.push(PackedPoint { x, y, pen });
+                    pen = true;
+                }
+            }
+
+            let imported = ImportedGlyph {
+                left: 0,
+                right: to_i8(glyph.advance * scale),
+                strokes,
+            };
+            glyphs.insert(glyph.c, imported);
+        }
+
+        let ascent = to_i8(font.face.ascent * scale) as i16;
+        let descent = to_i8(-font.face.descent * scale) as i16;
+        Ok(Self {
+            glyphs,
+            metrics: FontMetrics {
+                // SVG fonts have no line gap
+                line_height: ascent + descent,
+                ascent,
+                descent,
+                ..HERSHEY_METRICS
+            },
+        })
+    }
+
+    /// Pack the glyphs in `subsets` with their strokes reordered for drawing,
+    /// leaving out characters past the Basic Multilingual Plane.
+    pub fn to_table(&self, subsets: &[Subset]) -> GlyphTableBuilder {
+        let mut table = GlyphTableBuilder::new();
+        for (&c, g) in &self.glyphs {
+            let Ok(key) = u16::try_from(c as u32) else {
+                continue;
+            };
+            if subsets.contains(&Subset::of(c)) {
+                table.push(
+                    key,
+                    g.left,
+                    g.right,
+                    &optimize_strokes(&g.strokes, (g.left, 0)),
+                );
+            }
+        }
+        table
+    }
+
+    /// Rust source for a `static` [`TableFont`] called `name`, and the data
+    /// for it to include from `data_file` in `OUT_DIR`.
+    pub fn to_rust(&self, name: &str, data_file: &str, subsets: &[Subset]) -> (String, Vec<u8>) {
+        let table = self.to_table(subsets);
+        let m = &self.metrics;
+
+        let mut out = table.to_rust(&format!("{}_TABLE", name), data_file);
+        out.push_str(&format!(
+            "pub static {}: TableFont<'static> = TableFont::new(\n    &{}_TABLE,\n    \
+             FontMetrics {{ baseline: {}, line_height: {}, ascent: {}, descent: {}, cap_height: {} }},\n);\n",
+            name, name, m.baseline, m.line_height, m.ascent, m.descent, m.cap_height
+        ));
+
+        (out, table.data().to_vec())
+    }
+}
+
+/// A font whose glyphs are in a [`GlyphTable`] keyed by code point, like
+/// the ones [`ImportedFont::to_rust`] generates.
+#[derive(Debug, Copy, Clone)]
+pub struct TableFont<'a> {
+    table: &'a GlyphTable<'a>,
+    metrics: FontMetrics,
+}
+
+impl<'a> TableFont<'a> {
+    pub const fn new(table: &'a GlyphTable<'a>, metrics: FontMetrics) -> Self {
+        Self { table, metrics }
+    }
+}
+
+impl StrokeFont for TableFont<'_> {
+    fn glyph(&self, c: char) -> Option<Glyph<'_>> {
+        self.table.get(u16::try_from(c as u32).ok()?)
+    }
+
+    fn metrics(&self) -> FontMetrics {
+        self.metrics
+    }
+}
@@ -0,0 +1,79 @@
+//! Reading Hershey fonts in the `.jhf` format.
+//!
+//! Each glyph is a 5 digit number, a 3 digit count of coordinate pairs, and
+//! the pairs themselves as characters offset from `R`. The first pair is the
+//! left and right edges, and ` R` lifts the pen. Long glyphs carry on over
+//! several lines.
+
+use alloc::vec::Vec;
+
+use crate::{PackedPoint, import::ImportError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JhfGlyph {
+    /// The glyph's number, which means nothing in many fonts.
+    pub id: u16,
+    pub left: i8,
+    pub right: i8,
+    pub strokes: Vec<PackedPoint>,
+}
+
+fn coord(c: u8) -> i8 {
+    (c as i16 - b'R' as i16) as i8
+}
+
+/// Read every glyph in a `.jhf` file, in the order they appear.
+pub fn parse_jhf(file: &str) -> Result<Vec<JhfGlyph>, ImportError> {
+    let mut glyphs = Vec::new();
+    let mut lines = file.lines().enumerate();
+
+    while let Some((n, line)) = lines.next() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let bad = || ImportError::BadJhfLine(n + 1);
+        let header = line.get(..8).filter(|h| h.is_ascii()).ok_or_else(bad)?;
+        let id: u16 = header[..5].trim().parse().map_err(|_| bad())?;
+        let pairs: usize = header[5..].trim().parse().map_err(|_| bad())?;
+
+        // Join continuation lines until all the pairs are there
+        let mut coords = Vec::from(&line.as_bytes()[8..]);
+        while coords.len() < pairs * 2 {
+            let (_, more) = lines.next().ok_or_else(bad)?;
+            coords.extend_from_slice(more.as_bytes());
+        }
+
+        let [l, r, points @ ..] = &coords[..pairs * 2] else {
+            return Err(bad());
+        };
+        if !coords.is_ascii() {
+            return Err(bad());
+        }
+
+        let mut strokes = Vec::new();
+        let mut pen = false;
+        for pair in points.chunks_exact(2) {
+            if pair == b" R" {
+                pen = false;
+                continue;
+            }
+
+            strokes.push(PackedPoint {
+                x: coord(pair[0]),
+                y: coord(pair[1]),
+                pen,
+            });
+            pen = true;
+        }
+
+        glyphs.push(JhfGlyph {
+            id,
+            left: coord(*l),
+            right: coord(*r),
+            strokes,
+        });
+    }
+
+    Ok(glyphs)
+}
@@ -0,0 +1,39 @@
+use crate::{FontMetrics, Glyph, StrokeFont};
+
+/// Pairs of characters and the adjustment to the space between them, as
+/// returned by [`StrokeFont::kerning`]. Sorted by pair, for searching.
+pub type KerningTable = [(char, char, i8)];
+
+/// Adds a pair kerning table to another font.
+#[derive(Copy, Clone)]
+pub struct KernedFont<'a> {
+    font: &'a dyn StrokeFont,
+    pairs: &'a KerningTable,
+}
+
+impl<'a> KernedFont<'a> {
+    /// `pairs` must be sorted.
+    pub const fn new(font: &'a dyn StrokeFont, pairs: &'a KerningTable) -> Self {
+        Self { font, pairs }
+    }
+}
+
+impl StrokeFont for KernedFont<'_> {
+    fn glyph(&self, c: char) -> Option<Glyph<'_>> {
+        self.font.glyph(c)
+    }
+
+    fn metrics(&self) -> FontMetrics {
+        self.font.metrics()
+    }
+
+    fn kerning(&self, left: char, right: char) -> i16 {
+        match self
+            .pairs
+            .binary_search_by_key(&(left, right), |&(l, r, _)| (l, r))
+        {
+            Ok(i) => self.pairs[i].2 as i16,
+            Err(_) => self.font.kerning(left, right),
+        }
+    }
+}
@@ -0,0 +1,336 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::str::Chars;
+
+mod fallback;
+mod import;
+mod jhf;
+mod kerning;
+mod optimize;
+mod packed;
+mod svg;
+
+pub use fallback::FallbackFont;
+pub use import::{ImportError, ImportedFont, ImportedGlyph, TableFont};
+pub use jhf::{JhfGlyph, parse_jhf};
+pub use kerning::{KernedFont, KerningTable};
+pub use optimize::{blanked_travel, optimize_strokes};
+pub use packed::{GlyphTable, GlyphTableBuilder, StrokeIter, Strokes, Subset};
+pub use svg::{SvgFont, SvgFontFace, SvgGlyph, SvgPoint, parse_path, parse_svg_font};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PackedPoint {
+    pub x: i8,
+    pub y: i8,
+    pub pen: bool,
+}
+
+/// Converts a glyph's own units into those of the font that returned it,
+/// for glyphs borrowed from a font of a different size: `x * num / den` and
+/// `y * num / den + dy`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GlyphScale {
+    pub num: i16,
+    pub den: i16,
+    pub dy: i16,
+}
+
+impl GlyphScale {
+    pub const IDENTITY: GlyphScale = GlyphScale {
+        num: 1,
+        den: 1,
+        dy: 0,
+    };
+
+    /// Apply `self`, then `next`.
+    pub fn then(self, next: GlyphScale) -> GlyphScale {
+        GlyphScale {
+            num: self.num * next.num,
+            den: self.den * next.den,
+            dy: next.y(self.dy),
+        }
+    }
+
+    pub fn x(&self, x: i16) -> i16 {
+        if *self == Self::IDENTITY {
+            return x;
+        }
+
+        // Round to nearest
+        let (x, num, den) = (x as i32, self.num as i32, self.den as i32);
+        (2 * x * num + den).div_euclid(2 * den) as i16
+    }
+
+    pub fn y(&self, y: i16) -> i16 {
+        self.x(y) + self.dy
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Glyph<'a> {
+    pub left: i8,
+    pub right: i8,
+    pub scale: GlyphScale,
+    pub strokes: Strokes<'a>,
+}
+
+impl<'a> Glyph<'a> {
+    /// Horizontal distance from this glyph's origin to the next one's.
+    pub fn advance(&self) -> i16 {
+        self.scale.x(self.right as i16 - self.left as i16)
+    }
+
+    /// A stroke point relative to the glyph's origin, in font units.
+    pub fn point(&self, p: &PackedPoint) -> Point {
+        Point {
+            x: self.scale.x(p.x as i16 - self.left as i16),
+            y: self.scale.y(p.y as i16),
+            pen: p.pen,
+        }
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Point> + 'a {
+        let glyph = *self;
+        self.strokes.iter().map(move |p| glyph.point(&p))
+    }
+
+    /// The box around this glyph's strokes, relative to its origin in font
+    /// units, or `None` if it has no strokes.
+    pub fn bounds(&self) -> Option<Bounds> {
+        Bounds::of(self.points())
+    }
+}
+
+/// A box in font units, with y growing downwards.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Bounds {
+    pub min_x: i16,
+    pub min_y: i16,
+    pub max_x: i16,
+    pub max_y: i16,
+}
+
+impl Bounds {
+    /// The box around all of `points`, or `None` if there aren't any.
+    pub fn of(points: impl IntoIterator<Item = Point>) -> Option<Bounds> {
+        points
+            .into_iter()
+            .map(|p| Bounds {
+                min_x: p.x,
+                min_y: p.y,
+                max_x: p.x,
+                max_y: p.y,
+            })
+            .reduce(Bounds::union)
+    }
+
+    pub fn union(self, other: Bounds) -> Bounds {
+        Bounds {
+            min_x: self.min_x.min(other.min_x),
+            min_y: self.min_y.min(other.min_y),
+            max_x: self.max_x.max(other.max_x),
+            max_y: self.max_y.max(other.max_y),
+        }
+    }
+
+    pub fn width(&self) -> i16 {
+        self.max_x - self.min_x
+    }
+
+    pub fn height(&self) -> i16 {
+        self.max_y - self.min_y
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Point {
+    pub x: i16,
+    pub y: i16,
+    pub pen: bool,
+}
+
+/// Vertical metrics shared by every glyph of a font, in glyph units with y
+/// growing downwards.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FontMetrics {
+    /// Y coordinate of the baseline.
+    pub baseline: i16,
+    /// Distance between the baselines of consecutive lines.
+    pub line_height: i16,
+    /// Space a line of text takes above the baseline.
+    pub ascent: i16,
+    /// Space a line of text takes below the baseline, for descenders.
+    pub descent: i16,
+    /// Height of capital letters above the baseline.
+    pub cap_height: i16,
+}
+
+/// Metrics of the Hershey fonts, which Newstroke shares, `.jhf` files use
+/// and imported SVG fonts are scaled to match.
+pub const HERSHEY_METRICS: FontMetrics = FontMetrics {
+    baseline: 9,
+    line_height: 32,
+    ascent: 21,
+    descent: 7,
+    cap_height: 21,
+};
+
+pub trait StrokeFont {
+    /// Look up the glyph for `c`, if the font has one.
+    fn glyph(&self, c: char) -> Option<Glyph<'_>>;
+
+    fn metrics(&self) -> FontMetrics;
+
+    /// Adjustment to the space between `left` and `right` when `right`
+    /// follows `left`, in font units. Negative values move them closer.
+    fn kerning(&self, _left: char, _right: char) -> i16 {
+        0
+    }
+}
+
+/// A glyph of a string laid out along a baseline, as returned by [`glyphs`].
+#[derive(Debug, Copy, Clone)]
+pub struct PositionedGlyph<'a> {
+    pub c: char,
+    /// Where the glyph's origin goes along the baseline, in font units.
+    pub x: i16,
+    pub glyph: Glyph<'a>,
+}
+
+/// The glyphs of a string and where each one's origin goes along the
+/// baseline, skipping characters the font has no glyph for.
+pub struct GlyphPositions<'a> {
+    font: &'a dyn StrokeFont,
+    chars: Chars<'a>,
+    /// The last character that had a glyph.
+    prev: Option<char>,
+    letter_spacing: i16,
+    x: i16,
+}
+
+impl GlyphPositions<'_> {
+    /// Add `spacing` font units between each pair of characters.
+    pub fn with_letter_spacing(mut self, spacing: i16) -> Self {
+        self.letter_spacing = spacing;
+        self
+    }
+}
+
+impl<'a> Iterator for GlyphPositions<'a> {
+    type Item = PositionedGlyph<'a>;
+
+    fn next(&mut self) -> Option<PositionedGlyph<'a>> {
+        loop {
+            let c = self.chars.next()?;
+            let Some(glyph) = self.font.glyph(c) else {
+                continue;
+            };
+
+            if let Some(prev) = self.prev {
+                let gap = self
+                    .font
+                    .kerning(prev, c)
+                    .saturating_add(self.letter_spacing);
+                self.x = self.x.saturating_add(gap);
+            }
+
+            let x = self.x;
+            self.x = self.x.saturating_add(glyph.advance());
+            self.prev = Some(c);
+            return Some(PositionedGlyph { c, x, glyph });
+        }
+    }
+}
+
+pub fn glyphs<'a>(font: &'a dyn StrokeFont, text: &'a str) -> GlyphPositions<'a> {
+    GlyphPositions {
+        font,
+        chars: text.chars(),
+        prev: None,
+        letter_spacing: 0,
+        x: 0,
+    }
+}
+
+/// How much room a line of text takes, as returned by [`measure_text`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TextMetrics {
+    /// Distance from the start of the text to where the next character
+    /// would go.
+    pub width: i16,
+    /// The box around the strokes, or `None` if nothing is drawn.
+    pub bounds: Option<Bounds>,
+}
+
+/// Iterator over the pen positions of a string laid out along a horizontal
+/// baseline, as returned by [`strokes`].
+pub struct TextStrokes<'a> {
+    positions: GlyphPositions<'a>,
+    glyph: Option<(i16, Glyph<'a>, StrokeIter<'a>)>,
+}
+
+impl TextStrokes<'_> {
+    /// Add `spacing` font units between each pair of characters.
+    pub fn with_letter_spacing(mut self, spacing: i16) -> Self {
+        self.positions.letter_spacing = spacing;
+        self
+    }
+
+    /// Measure the text instead of drawing it.
+    pub fn metrics(mut self) -> TextMetrics {
+        let bounds = Bounds::of(self.by_ref());
+        TextMetrics {
+            width: self.positions.x,
+            bounds,
+        }
+    }
+}
+
+impl Iterator for TextStrokes<'_> {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Point> {
+        loop {
+            if let Some((x, glyph, points)) = &mut self.glyph
+                && let Some(point) = points.next()
+            {
+                let point = glyph.point(&point);
+                return Some(Point {
+                    x: point.x.saturating_add(*x),
+                    ..point
+                });
+            }
+
+            let PositionedGlyph { x, glyph, .. } = self.positions.next()?;
+            self.glyph = Some((x, glyph, glyph.strokes.iter()));
+        }
+    }
+}
+
+pub fn strokes<'a>(font: &'a dyn StrokeFont, text: &'a str) -> TextStrokes<'a> {
+    TextStrokes {
+        positions: glyphs(font, text),
+        glyph: None,
+    }
+}
+
+pub fn render_text(font: &dyn StrokeFont, text: &str) -> Vec<Point> {
+    strokes(font, text).collect()
+}
+
+/// Measure a line of text without drawing it. Use
+/// [`TextStrokes::metrics`] to measure it with letter spacing.
+pub fn measure_text(font: &dyn StrokeFont, text: &str) -> TextMetrics {
+    strokes(font, text).metrics()
+}
+
+/// Just the width of a line of text, which is quicker to find than its
+/// bounds.
+pub fn text_width(font: &dyn StrokeFont, text: &str) -> i16 {
+    let mut positions = glyphs(font, text);
+    positions.by_ref().for_each(drop);
+    positions.x
+}
@@ -0,0 +1,128 @@
+//! Reordering glyph strokes to cut down on blanked travel, for build scripts.
+
+use alloc::vec::Vec;
+
+use crate::PackedPoint;
+
+/// A run of pen-down points, with the pen starting at the first.
+type Stroke = Vec<(i8, i8)>;
+
+fn distance(a: (i8, i8), b: (i8, i8)) -> f32 {
+    let (dx, dy) = (a.0 as f32 - b.0 as f32, a.1 as f32 - b.1 as f32);
+    libm::sqrtf(dx * dx + dy * dy)
+}
+
+/// Total distance moved with the pen up when drawing `points`, starting
+/// from `start`.
+pub fn blanked_travel(points: &[PackedPoint], start: (i8, i8)) -> f32 {
+    let mut at = start;
+    let mut total = 0.0;
+    for p in points {
+        if !p.pen {
+            total += distance(at, (p.x, p.y));
+        }
+        at = (p.x, p.y);
+    }
+    total
+}
+
+fn split_strokes(points: &[PackedPoint]) -> Vec<Stroke> {
+    let mut strokes: Vec<Stroke> = Vec::new();
+    for p in points {
+        match strokes.last_mut() {
+            Some(stroke) if p.pen => stroke.push((p.x, p.y)),
+            _ => strokes.push(Vec::from([(p.x, p.y)])),
+        }
+    }
+
+    // A move with nothing drawn after it doesn't show up
+    strokes.retain(|s| s.len() > 1);
+    strokes
+}
+
+/// Drop points in the middle of straight lines.
+fn merge_segments(stroke: &mut Stroke) {
+    let mut i = 1;
+    while i + 1 < stroke.len() {
+        let (a, b, c) = (stroke[i - 1], stroke[i], stroke[i + 1]);
+        let (ab, bc) = (
+            (b.0 as i32 - a.0 as i32, b.1 as i32 - a.1 as i32),
+            (c.0 as i32 - b.0 as i32, c.1 as i32 - b.1 as i32),
+        );
+
+        let collinear = ab.0 * bc.1 == ab.1 * bc.0;
+        let same_way = ab.0 * bc.0 + ab.1 * bc.1 > 0;
+        if b == a || b == c || (collinear && same_way) {
+            stroke.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+}
+
+/// Draw `strokes` in order, carrying on without a move where one starts at
+/// the end of the last.
+fn join(strokes: &[Stroke]) -> Vec<PackedPoint> {
+    let mut out = Vec::new();
+    let mut at = None;
+    for stroke in strokes {
+        for (n, &(x, y)) in stroke.iter().enumerate() {
+            let pen = n > 0;
+            if !pen && at == Some((x, y)) {
+                continue;
+            }
+            out.push(PackedPoint { x, y, pen });
+        }
+        at = stroke.last().copied();
+    }
+    out
+}
+
+/// Order strokes by always drawing whichever stroke end is nearest next.
+fn nearest_first(mut strokes: Vec<Stroke>, start: (i8, i8)) -> Vec<Stroke> {
+    let mut ordered = Vec::with_capacity(strokes.len());
+    let mut at = start;
+    while !strokes.is_empty() {
+        let end = |s: &Stroke, reverse: bool| if reverse { s[s.len() - 1] } else { s[0] };
+        let (i, reverse) = (0..strokes.len())
+            .flat_map(|i| [(i, false), (i, true)])
+            .min_by(|&(i, ri), &(j, rj)| {
+                distance(at, end(&strokes[i], ri)).total_cmp(&distance(at, end(&strokes[j], rj)))
+            })
+            .unwrap();
+
+        let mut stroke = strokes.swap_remove(i);
+        if reverse {
+            stroke.reverse();
+        }
+        at = stroke[stroke.len() - 1];
+        ordered.push(stroke);
+    }
+    ordered
+}
+
+/// Reorder and reverse the strokes of a glyph so the pen travels as little
+/// as possible with the laser off, starting from `start`, and join strokes
+/// that meet end to end. The lines drawn stay the same.
+pub fn optimize_strokes(points: &[PackedPoint], start: (i8, i8)) -> Vec<PackedPoint> {
+    // A glyph that starts with the pen down draws from wherever the last
+    // one ended, so leave it alone
+    if points.first().is_some_and(|p| p.pen) {
+        return points.to_vec();
+    }
+
+    let mut strokes = split_strokes(points);
+    strokes.iter_mut().for_each(merge_segments);
+
+    // Nearest first is usually better than the original order, but not
+    // always
+    let original = join(&strokes);
+    let nearest = join(&nearest_first(strokes, start));
+
+    let cost = |points: &[PackedPoint]| (blanked_travel(points, start), points.len());
+    if cost(&nearest) < cost(&original) {
+        nearest
+    } else {
+        original
+    }
+}
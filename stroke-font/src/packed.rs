@@ -0,0 +1,261 @@
+//! Glyph tables packed into one byte blob by a build script, with the stroke
+//! points of each glyph delta-encoded and only decoded as they're drawn.
+//!
+//! Each glyph in the blob is its left and right bearings followed by its
+//! points. A point is the difference from the one before it, starting from
+//! the glyph's origin, in one of two forms:
+//!
+//! - `1pxxxyyy`: pen `p`, with x and y deltas between -4 and 3.
+//! - `0p000000 xxxxxxxx yyyyyyyy`: pen `p`, with wrapping i8 x and y deltas.
+
+use alloc::{format, string::String, vec::Vec};
+
+use crate::{Glyph, GlyphScale, PackedPoint};
+
+const SHORT: u8 = 0x80;
+const PEN: u8 = 0x40;
+
+/// The stroke points of a glyph.
+#[derive(Debug, Copy, Clone)]
+pub enum Strokes<'a> {
+    Points(&'a [PackedPoint]),
+    /// Points delta-encoded as described in the [module docs](self).
+    Encoded(&'a [u8]),
+}
+
+impl<'a> Strokes<'a> {
+    pub fn iter(&self) -> StrokeIter<'a> {
+        StrokeIter {
+            strokes: *self,
+            pos: 0,
+            x: 0,
+            y: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StrokeIter<'a> {
+    strokes: Strokes<'a>,
+    pos: usize,
+    x: i8,
+    y: i8,
+}
+
+impl Iterator for StrokeIter<'_> {
+    type Item = PackedPoint;
+
+    fn next(&mut self) -> Option<PackedPoint> {
+        let data = match self.strokes {
+            Strokes::Points(points) => {
+                let point = points.get(self.pos)?;
+                self.pos += 1;
+                return Some(*point);
+            }
+            Strokes::Encoded(data) => data,
+        };
+
+        let head = *data.get(self.pos)?;
+        let (dx, dy) = if head & SHORT != 0 {
+            self.pos += 1;
+            // Sign-extend each 3-bit delta
+            (((head << 2) as i8) >> 5, ((head << 5) as i8) >> 5)
+        } else {
+            let delta = data.get(self.pos + 1..self.pos + 3)?;
+            self.pos += 3;
+            (delta[0] as i8, delta[1] as i8)
+        };
+
+        self.x = self.x.wrapping_add(dx);
+        self.y = self.y.wrapping_add(dy);
+
+        Some(PackedPoint {
+            x: self.x,
+            y: self.y,
+            pen: head & PEN != 0,
+        })
+    }
+}
+
+/// Glyphs looked up by a `u16` key, like a code point or glyph number.
+#[derive(Debug, Copy, Clone)]
+pub struct GlyphTable<'a> {
+    /// Sorted keys of the glyphs in the table.
+    keys: &'a [u16],
+    /// Where each glyph starts in `data`, and where the last one ends.
+    offsets: &'a [u32],
+    data: &'a [u8],
+}
+
+impl<'a> GlyphTable<'a> {
+    pub const fn new(keys: &'a [u16], offsets: &'a [u32], data: &'a [u8]) -> Self {
+        Self {
+            keys,
+            offsets,
+            data,
+        }
+    }
+
+    pub fn get(&self, key: u16) -> Option<Glyph<'a>> {
+        let i = self.keys.binary_search(&key).ok()?;
+        let glyph = self
+            .data
+            .get(*self.offsets.get(i)? as usize..*self.offsets.get(i + 1)? as usize)?;
+        let [left, right, strokes @ ..] = glyph else {
+            return None;
+        };
+
+        Some(Glyph {
+            left: *left as i8,
+            right: *right as i8,
+            scale: GlyphScale::IDENTITY,
+            strokes: Strokes::Encoded(strokes),
+        })
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = u16> + 'a {
+        self.keys.iter().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+/// Packs glyphs into a [`GlyphTable`], for use from build scripts.
+#[derive(Debug, Clone, Default)]
+pub struct GlyphTableBuilder {
+    keys: Vec<u16>,
+    offsets: Vec<u32>,
+    data: Vec<u8>,
+}
+
+impl GlyphTableBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a glyph. Keys must be added in increasing order.
+    pub fn push(&mut self, key: u16, left: i8, right: i8, strokes: &[PackedPoint]) {
+        assert!(
+            self.keys.last().is_none_or(|&last| last < key),
+            "glyph {} added out of order",
+            key
+        );
+
+        self.keys.push(key);
+        self.offsets.push(self.data.len() as u32);
+        self.data.extend([left as u8, right as u8]);
+
+        let (mut x, mut y) = (0i8, 0i8);
+        for p in strokes {
+            let (dx, dy) = (p.x.wrapping_sub(x), p.y.wrapping_sub(y));
+            let pen = if p.pen { PEN } else { 0 };
+
+            if (-4..4).contains(&dx) && (-4..4).contains(&dy) {
+                self.data
+                    .push(SHORT | pen | ((dx as u8 & 0b111) << 3) | (dy as u8 & 0b111));
+            } else {
+                self.data.extend([pen, dx as u8, dy as u8]);
+            }
+
+            (x, y) = (p.x, p.y);
+        }
+    }
+
+    /// The packed glyph data, to be written to a file in `OUT_DIR`.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Rust source for a `static` called `name` holding the table, with the
+    /// data included from `data_file` in `OUT_DIR`.
+    pub fn to_rust(&self, name: &str, data_file: &str) -> String {
+        let mut out = format!(
+            "static {}: GlyphTable<'static> = GlyphTable::new(\n    &[",
+            name
+        );
+
+        for (i, key) in self.keys.iter().enumerate() {
+            if i % 16 == 0 {
+                out.push_str("\n        ");
+            }
+            out.push_str(&format!("{}, ", key));
+        }
+
+        out.push_str("\n    ],\n    &[");
+
+        let end = self.data.len() as u32;
+        for (i, offset) in self.offsets.iter().chain([&end]).enumerate() {
+            if i % 16 == 0 {
+                out.push_str("\n        ");
+            }
+            out.push_str(&format!("{}, ", offset));
+        }
+
+        out.push_str(&format!(
+            "\n    ],\n    include_bytes!(concat!(env!(\"OUT_DIR\"), \"/{}\")),\n);\n",
+            data_file
+        ));
+
+        out
+    }
+}
+
+/// Groups of characters that fonts can leave out to save flash, each behind
+/// a cargo feature of the same name in the font crates.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Subset {
+    Ascii,
+    /// Accented Latin letters, IPA and combining marks.
+    Latin,
+    GreekCyrillic,
+    /// Kana, kanji and CJK punctuation.
+    Cjk,
+    /// Everything else: punctuation, currency, arrows, maths, box drawing...
+    Symbols,
+}
+
+impl Subset {
+    pub const ALL: [Subset; 5] = [
+        Subset::Ascii,
+        Subset::Latin,
+        Subset::GreekCyrillic,
+        Subset::Cjk,
+        Subset::Symbols,
+    ];
+
+    pub fn of(c: char) -> Subset {
+        match c as u32 {
+            0x0000..=0x007F => Subset::Ascii,
+            0x0080..=0x036F | 0x1E00..=0x1EFF => Subset::Latin,
+            0x0370..=0x052F | 0x1F00..=0x1FFF => Subset::GreekCyrillic,
+            0x2E80..=0x9FFF | 0xF900..=0xFAFF | 0xFF00..=0xFFEF => Subset::Cjk,
+            _ => Subset::Symbols,
+        }
+    }
+
+    /// The cargo feature enabling this subset.
+    pub fn feature(&self) -> &'static str {
+        match self {
+            Subset::Ascii => "ascii",
+            Subset::Latin => "latin",
+            Subset::GreekCyrillic => "greek-cyrillic",
+            Subset::Cjk => "cjk",
+            Subset::Symbols => "symbols",
+        }
+    }
+
+    /// The `CARGO_FEATURE_*` variable set for build scripts when the feature
+    /// is enabled.
+    pub fn feature_env(&self) -> String {
+        format!(
+            "CARGO_FEATURE_{}",
+            self.feature().to_uppercase().replace('-', "_")
+        )
+    }
+}
@@ -0,0 +1,537 @@
+//! Reading single-line SVG fonts, like the EMS "Hershey Text" fonts that
+//! come with Inkscape.
+//!
+//! Only the parts of SVG those fonts use are understood: `<font>`,
+//! `<font-face>` and `<glyph>` elements, and the path data in their `d`
+//! attributes. Curves and arcs are flattened into straight strokes.
+
+use alloc::{string::String, vec::Vec};
+use core::f32::consts::TAU;
+
+use crate::import::ImportError;
+
+/// How long the straight pieces of flattened curves are, in the units the
+/// caller scales paths to.
+const CURVE_STEP: f32 = 2.0;
+
+const MAX_CURVE_STEPS: f32 = 32.0;
+
+/// A point in the font's own units, with y pointing up.
+pub type SvgPoint = (f32, f32);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgGlyph {
+    pub c: char,
+    pub advance: f32,
+    /// Lines through consecutive points.
+    pub strokes: Vec<Vec<SvgPoint>>,
+}
+
+/// The font-wide values from `<font>` and `<font-face>`, in font units.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SvgFontFace {
+    pub units_per_em: f32,
+    pub ascent: f32,
+    /// Usually negative, as it's below the baseline.
+    pub descent: f32,
+    pub cap_height: f32,
+    /// Advance of glyphs that don't give their own.
+    pub advance: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgFont {
+    pub face: SvgFontFace,
+    pub glyphs: Vec<SvgGlyph>,
+}
+
+/// A start tag's name and the text of its attributes.
+struct Tag<'a> {
+    name: &'a str,
+    attributes: &'a str,
+}
+
+/// The start tags in `xml`, skipping comments, declarations and end tags.
+fn tags(xml: &str) -> Result<Vec<Tag<'_>>, ImportError> {
+    let mut tags = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+
+        let close = if rest.starts_with("!--") {
+            "-->"
+        } else if rest.starts_with('?') {
+            "?>"
+        } else if rest.starts_with('!') || rest.starts_with('/') {
+            ">"
+        } else {
+            ""
+        };
+        if !close.is_empty() {
+            let end = rest.find(close).ok_or(ImportError::UnclosedTag)?;
+            rest = &rest[end + close.len()..];
+            continue;
+        }
+
+        // Find the end of the tag, ignoring any `>` in quoted values
+        let mut quote = None;
+        let end = rest
+            .char_indices()
+            .find(|&(_, c)| match quote {
+                Some(q) => {
+                    if c == q {
+                        quote = None;
+                    }
+                    false
+                }
+                None if c == '"' || c == '\'' => {
+                    quote = Some(c);
+                    false
+                }
+                None => c == '>',
+            })
+            .map(|(i, _)| i)
+            .ok_or(ImportError::UnclosedTag)?;
+
+        let tag = rest[..end].trim_end_matches('/');
+        let name_end = tag.find(|c: char| c.is_whitespace()).unwrap_or(tag.len());
+        tags.push(Tag {
+            name: &tag[..name_end],
+            attributes: &tag[name_end..],
+        });
+        rest = &rest[end + 1..];
+    }
+
+    Ok(tags)
+}
+
+/// Replace character and entity references in an attribute value.
+fn unescape(value: &str) -> Result<String, ImportError> {
+    let mut out = String::new();
+    let mut rest = value;
+
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        let end = rest[amp..].find(';').ok_or(ImportError::BadEntity)? + amp;
+        let entity = &rest[amp + 1..end];
+
+        let c = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => {
+                let code = match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => entity.strip_prefix('#').and_then(|d| d.parse().ok()),
+                };
+                code.and_then(char::from_u32)
+            }
+        };
+        out.push(c.ok_or(ImportError::BadEntity)?);
+        rest = &rest[end + 1..];
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Look up the attribute `name` in the text of a tag's attributes.
+fn attribute(attributes: &str, name: &str) -> Result<Option<String>, ImportError> {
+    let mut rest = attributes;
+    loop {
+        rest = rest.trim_start();
+        let Some(eq) = rest.find('=') else {
+            return Ok(None);
+        };
+        let key = rest[..eq].trim();
+
+        rest = rest[eq + 1..].trim_start();
+        let quote = rest.chars().next().filter(|&q| q == '"' || q == '\'');
+        let quote = quote.ok_or(ImportError::BadAttribute)?;
+        let end = rest[1..].find(quote).ok_or(ImportError::BadAttribute)? + 1;
+
+        if key == name {
+            return unescape(&rest[1..end]).map(Some);
+        }
+        rest = &rest[end + 1..];
+    }
+}
+
+fn number_attribute(attributes: &str, name: &str) -> Result<Option<f32>, ImportError> {
+    match attribute(attributes, name)? {
+        Some(value) => match value.trim().parse::<f32>() {
+            Ok(v) if v.is_finite() => Ok(Some(v)),
+            _ => Err(ImportError::BadAttribute),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Reads numbers, flags and commands from path data.
+struct PathData<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl PathData<'_> {
+    fn skip_separators(&mut self) {
+        while self
+            .data
+            .get(self.pos)
+            .is_some_and(|c| c.is_ascii_whitespace() || *c == b',')
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_separators();
+        self.pos >= self.data.len()
+    }
+
+    /// The next command letter, if that's what comes next.
+    fn command(&mut self) -> Option<u8> {
+        self.skip_separators();
+        let c = *self.data.get(self.pos)?;
+        // `e` and `E` only appear inside numbers
+        if c.is_ascii_alphabetic() && c != b'e' && c != b'E' {
+            self.pos += 1;
+            Some(c)
+        } else {
+            None
+        }
+    }
+
+    fn number(&mut self) -> Option<f32> {
+        self.skip_separators();
+        let start = self.pos;
+        let digits = |this: &mut Self| {
+            let from = this.pos;
+            while this.data.get(this.pos).is_some_and(u8::is_ascii_digit) {
+                this.pos += 1;
+            }
+            this.pos > from
+        };
+
+        if matches!(self.data.get(self.pos), Some(b'+' | b'-')) {
+            self.pos += 1;
+        }
+        let mut any = digits(self);
+        if self.data.get(self.pos) == Some(&b'.') {
+            self.pos += 1;
+            any |= digits(self);
+        }
+        if !any {
+            self.pos = start;
+            return None;
+        }
+
+        // An exponent, but only if there are digits for it
+        if matches!(self.data.get(self.pos), Some(b'e' | b'E')) {
+            let mantissa_end = self.pos;
+            self.pos += 1;
+            if matches!(self.data.get(self.pos), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if !digits(self) {
+                self.pos = mantissa_end;
+            }
+        }
+
+        core::str::from_utf8(&self.data[start..self.pos])
+            .ok()?
+            .parse()
+            .ok()
+    }
+
+    fn point(&mut self) -> Option<SvgPoint> {
+        Some((self.number()?, self.number()?))
+    }
+
+    /// An arc flag, which can be written without a separator after it.
+    fn flag(&mut self) -> Option<bool> {
+        self.skip_separators();
+        let flag = match self.data.get(self.pos)? {
+            b'0' => false,
+            b'1' => true,
+            _ => return None,
+        };
+        self.pos += 1;
+        Some(flag)
+    }
+}
+
+fn lerp(a: SvgPoint, b: SvgPoint, t: f32) -> SvgPoint {
+    (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+}
+
+fn distance(a: SvgPoint, b: SvgPoint) -> f32 {
+    libm::hypotf(b.0 - a.0, b.1 - a.1)
+}
+
+/// How many straight pieces to split a curve about `length` font units
+/// long into.
+fn steps(length: f32, scale: f32) -> usize {
+    libm::ceilf(length * scale / CURVE_STEP).clamp(1.0, MAX_CURVE_STEPS) as usize
+}
+
+/// Angle from `u` to `v`.
+fn angle(u: SvgPoint, v: SvgPoint) -> f32 {
+    libm::atan2f(u.0 * v.1 - u.1 * v.0, u.0 * v.0 + u.1 * v.1)
+}
+
+struct ArcTo {
+    radii: SvgPoint,
+    rotation: f32,
+    large: bool,
+    sweep: bool,
+    to: SvgPoint,
+}
+
+/// Points along an elliptical arc from `from`, following the endpoint to
+/// centre conversion in the SVG spec.
+fn arc_points(from: SvgPoint, arc: &ArcTo, scale: f32, out: &mut Vec<SvgPoint>) {
+    let (mut rx, mut ry) = (libm::fabsf(arc.radii.0), libm::fabsf(arc.radii.1));
+    if rx == 0.0 || ry == 0.0 || from == arc.to {
+        out.push(arc.to);
+        return;
+    }
+
+    let (sin, cos) = libm::sincosf(arc.rotation.to_radians());
+    let (hx, hy) = ((from.0 - arc.to.0) / 2.0, (from.1 - arc.to.1) / 2.0);
+    let (x1, y1) = (cos * hx + sin * hy, -sin * hx + cos * hy);
+
+    // Grow radii too small to reach the end point
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1.0 {
+        let k = libm::sqrtf(lambda);
+        rx *= k;
+        ry *= k;
+    }
+
+    let num = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let den = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let sign = if arc.large == arc.sweep { -1.0 } else { 1.0 };
+    let k = sign * libm::sqrtf((num / den).max(0.0));
+    let (cx1, cy1) = (k * rx * y1 / ry, -k * ry * x1 / rx);
+    let center = (
+        cos * cx1 - sin * cy1 + (from.0 + arc.to.0) / 2.0,
+        sin * cx1 + cos * cy1 + (from.1 + arc.to.1) / 2.0,
+    );
+
+    let start = angle((1.0, 0.0), ((x1 - cx1) / rx, (y1 - cy1) / ry));
+    let mut sweep = angle(
+        ((x1 - cx1) / rx, (y1 - cy1) / ry),
+        ((-x1 - cx1) / rx, (-y1 - cy1) / ry),
+    );
+    if !arc.sweep && sweep > 0.0 {
+        sweep -= TAU;
+    } else if arc.sweep && sweep < 0.0 {
+        sweep += TAU;
+    }
+
+    let n = steps(libm::fabsf(sweep) * rx.max(ry), scale);
+    for i in 1..n {
+        let (s, c) = libm::sincosf(start + sweep * i as f32 / n as f32);
+        out.push((
+            cos * rx * c - sin * ry * s + center.0,
+            sin * rx * c + cos * ry * s + center.1,
+        ));
+    }
+    out.push(arc.to);
+}
+
+/// Flatten SVG path data into lines, with curves split into pieces about
+/// [`CURVE_STEP`] long once scaled by `scale`.
+pub fn parse_path(d: &str, scale: f32) -> Option<Vec<Vec<SvgPoint>>> {
+    let mut data = PathData {
+        data: d.as_bytes(),
+        pos: 0,
+    };
+    let mut strokes: Vec<Vec<SvgPoint>> = Vec::new();
+    let mut stroke: Vec<SvgPoint> = Vec::new();
+    let mut at = (0.0, 0.0);
+    let mut start = (0.0, 0.0);
+    // The last control point, for smooth curves to reflect
+    let mut control: Option<(u8, SvgPoint)> = None;
+    let mut command = None;
+
+    while !data.at_end() {
+        let c = match data.command() {
+            Some(c) => c,
+            // Repeated arguments repeat the command, with moves turning
+            // into lines
+            None => match command? {
+                b'M' => b'L',
+                b'm' => b'l',
+                b'Z' | b'z' => return None,
+                c => c,
+            },
+        };
+        command = Some(c);
+
+        let relative = c.is_ascii_lowercase();
+        let offset = move |p: SvgPoint| {
+            if relative {
+                (at.0 + p.0, at.1 + p.1)
+            } else {
+                p
+            }
+        };
+
+        // Drawing carries on from the last point, or after a close from the
+        // start of the closed stroke
+        if stroke.is_empty() && !matches!(c, b'M' | b'm') {
+            stroke.push(at);
+        }
+
+        let mut next_control = None;
+        match c.to_ascii_uppercase() {
+            b'M' => {
+                if stroke.len() > 1 {
+                    strokes.push(core::mem::take(&mut stroke));
+                }
+                stroke.clear();
+                at = offset(data.point()?);
+                start = at;
+                stroke.push(at);
+            }
+            b'L' => {
+                at = offset(data.point()?);
+                stroke.push(at);
+            }
+            b'H' => {
+                let x = data.number()?;
+                at = (if relative { at.0 + x } else { x }, at.1);
+                stroke.push(at);
+            }
+            b'V' => {
+                let y = data.number()?;
+                at = (at.0, if relative { at.1 + y } else { y });
+                stroke.push(at);
+            }
+            b'C' | b'S' => {
+                let c1 = if c.eq_ignore_ascii_case(&b'C') {
+                    offset(data.point()?)
+                } else {
+                    match control {
+                        Some((b'C', p)) => (2.0 * at.0 - p.0, 2.0 * at.1 - p.1),
+                        _ => at,
+                    }
+                };
+                let c2 = offset(data.point()?);
+                let to = offset(data.point()?);
+
+                let n = steps(
+                    distance(at, c1) + distance(c1, c2) + distance(c2, to),
+                    scale,
+                );
+                for i in 1..=n {
+                    let t = i as f32 / n as f32;
+                    let (a, b, c) = (lerp(at, c1, t), lerp(c1, c2, t), lerp(c2, to, t));
+                    stroke.push(lerp(lerp(a, b, t), lerp(b, c, t), t));
+                }
+                at = to;
+                next_control = Some((b'C', c2));
+            }
+            b'Q' | b'T' => {
+                let c1 = if c.eq_ignore_ascii_case(&b'Q') {
+                    offset(data.point()?)
+                } else {
+                    match control {
+                        Some((b'Q', p)) => (2.0 * at.0 - p.0, 2.0 * at.1 - p.1),
+                        _ => at,
+                    }
+                };
+                let to = offset(data.point()?);
+
+                let n = steps(distance(at, c1) + distance(c1, to), scale);
+                for i in 1..=n {
+                    let t = i as f32 / n as f32;
+                    stroke.push(lerp(lerp(at, c1, t), lerp(c1, to, t), t));
+                }
+                at = to;
+                next_control = Some((b'Q', c1));
+            }
+            b'A' => {
+                let arc = ArcTo {
+                    radii: (data.number()?, data.number()?),
+                    rotation: data.number()?,
+                    large: data.flag()?,
+                    sweep: data.flag()?,
+                    to: offset(data.point()?),
+                };
+                arc_points(at, &arc, scale, &mut stroke);
+                at = arc.to;
+            }
+            b'Z' => {
+                stroke.push(start);
+                at = start;
+                if stroke.len() > 1 {
+                    strokes.push(core::mem::take(&mut stroke));
+                }
+                stroke.clear();
+            }
+            _ => return None,
+        }
+        control = next_control;
+    }
+
+    if stroke.len() > 1 {
+        strokes.push(stroke);
+    }
+    Some(strokes)
+}
+
+/// Read the glyphs of an SVG font that stand for a single character, with
+/// paths flattened for drawing with capitals `cap_height` units tall.
+pub fn parse_svg_font(file: &str, cap_height: f32) -> Result<SvgFont, ImportError> {
+    let tags = tags(file)?;
+
+    let font = tags.iter().find(|t| t.name == "font");
+    let face = tags.iter().find(|t| t.name == "font-face");
+    let (font, face) = (
+        font.map_or("", |t| t.attributes),
+        face.map_or("", |t| t.attributes),
+    );
+
+    let units_per_em = number_attribute(face, "units-per-em")?.unwrap_or(1000.0);
+    let ascent = number_attribute(face, "ascent")?.unwrap_or(units_per_em * 0.8);
+    let face = SvgFontFace {
+        units_per_em,
+        ascent,
+        descent: number_attribute(face, "descent")?.unwrap_or(-units_per_em * 0.2),
+        cap_height: number_attribute(face, "cap-height")?.unwrap_or(ascent),
+        advance: number_attribute(font, "horiz-adv-x")?.unwrap_or(units_per_em / 2.0),
+    };
+    if face.cap_height <= 0.0 {
+        return Err(ImportError::BadAttribute);
+    }
+    let scale = cap_height / face.cap_height;
+
+    let mut glyphs = Vec::new();
+    for tag in tags.iter().filter(|t| t.name == "glyph") {
+        let Some(unicode) = attribute(tag.attributes, "unicode")? else {
+            continue;
+        };
+        let mut chars = unicode.chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            // Ligatures have no single character to go under
+            continue;
+        };
+
+        let d = attribute(tag.attributes, "d")?.unwrap_or_default();
+        let strokes = parse_path(&d, scale).ok_or(ImportError::BadPath(c))?;
+
+        glyphs.push(SvgGlyph {
+            c,
+            advance: number_attribute(tag.attributes, "horiz-adv-x")?.unwrap_or(face.advance),
+            strokes,
+        });
+    }
+
+    Ok(SvgFont { face, glyphs })
+}
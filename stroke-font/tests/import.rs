@@ -0,0 +1,212 @@
+use stroke_font::{
+    FontMetrics, GlyphTable, GlyphTableBuilder, ImportError, ImportedFont, PackedPoint, StrokeFont,
+    Subset, TableFont, parse_jhf, parse_path, parse_svg_font,
+};
+
+const SVG: &str = include_str!("data/sample.svg");
+const JHF: &str = include_str!("data/sample.jhf");
+
+fn p(x: i8, y: i8, pen: bool) -> PackedPoint {
+    PackedPoint { x, y, pen }
+}
+
+#[test]
+fn reads_path_lines() {
+    // Implicit line-to after a move, relative and closing commands
+    let strokes = parse_path("M0 0 10 0 h5 v5 Z m1 1 l2 2", 1.0).unwrap();
+    assert_eq!(
+        strokes,
+        [
+            vec![
+                (0.0, 0.0),
+                (10.0, 0.0),
+                (15.0, 0.0),
+                (15.0, 5.0),
+                (0.0, 0.0)
+            ],
+            vec![(1.0, 1.0), (3.0, 3.0)],
+        ]
+    );
+
+    // Numbers can run together when the sign or a second point splits them
+    let strokes = parse_path("M1.5-2L.5.5", 1.0).unwrap();
+    assert_eq!(strokes, [vec![(1.5, -2.0), (0.5, 0.5)]]);
+
+    // A lone move draws nothing
+    assert!(parse_path("M5 5", 1.0).unwrap().is_empty());
+
+    assert_eq!(parse_path("M0 0 L", 1.0), None);
+    assert_eq!(parse_path("M0 0 X1 1", 1.0), None);
+    assert_eq!(
+        parse_path("L1 1", 1.0),
+        Some(vec![vec![(0.0, 0.0), (1.0, 1.0)]])
+    );
+}
+
+#[test]
+fn flattens_curves() {
+    let near = |a: f32, b: f32| (a - b).abs() < 0.01;
+
+    // A half circle of radius 10 around (10, 0)
+    let strokes = parse_path("M0 0 A10 10 0 0 1 20 0", 1.0).unwrap();
+    let arc = &strokes[0];
+    assert!(arc.len() > 8);
+    assert_eq!(arc.last(), Some(&(20.0, 0.0)));
+    for &(x, y) in arc {
+        assert!(near((x - 10.0).hypot(y), 10.0));
+        assert!(y <= 0.01);
+    }
+
+    // More pieces when drawn bigger, within a limit
+    let small = parse_path("M0 0 Q50 100 100 0", 0.1).unwrap();
+    let large = parse_path("M0 0 Q50 100 100 0", 10.0).unwrap();
+    assert!(small[0].len() < large[0].len());
+    assert!(large[0].len() <= 33);
+    for &(x, y) in &large[0] {
+        // On the parabola through the ends with its peak at (50, 50)
+        assert!(near(y, x * (100.0 - x) / 50.0));
+    }
+
+    // Smooth curves mirror the previous control point
+    let strokes = parse_path("M0 0 Q10 10 20 0 T40 0", 1.0).unwrap();
+    let lowest = strokes[0].iter().map(|p| p.1).fold(0.0, f32::min);
+    assert!((lowest + 5.0).abs() < 0.2);
+}
+
+#[test]
+fn reads_svg_fonts() {
+    let font = parse_svg_font(SVG, 21.0).unwrap();
+    assert_eq!(font.face.units_per_em, 1000.0);
+    assert_eq!(font.face.cap_height, 700.0);
+    assert_eq!(font.face.advance, 500.0);
+
+    let chars: String = font.glyphs.iter().map(|g| g.c).collect();
+    assert_eq!(chars, " ILOS&\u{2013}");
+
+    let l = &font.glyphs[2];
+    assert_eq!(l.advance, 500.0);
+    assert_eq!(
+        l.strokes,
+        [vec![(100.0, 700.0), (100.0, 0.0), (400.0, 0.0)]]
+    );
+}
+
+#[test]
+fn imports_svg_fonts() {
+    let font = ImportedFont::from_svg(SVG).unwrap();
+    assert_eq!(
+        font.metrics,
+        FontMetrics {
+            baseline: 9,
+            line_height: 30,
+            ascent: 24,
+            descent: 6,
+            cap_height: 21,
+        }
+    );
+
+    // Scaled to capitals 21 tall standing on the baseline, with y down
+    let i = &font.glyphs[&'I'];
+    assert_eq!((i.left, i.right), (0, 6));
+    assert_eq!(i.strokes, [p(3, 9, false), p(3, -12, true)]);
+
+    assert!(font.glyphs[&' '].strokes.is_empty());
+    assert!(font.glyphs.contains_key(&'&'));
+}
+
+#[test]
+fn reads_jhf_files() {
+    let glyphs = parse_jhf(JHF).unwrap();
+    assert_eq!(glyphs.len(), 2);
+
+    // Joined from two lines
+    let a = &glyphs[0];
+    assert_eq!((a.id, a.left, a.right), (501, -5, 5));
+    assert_eq!(
+        a.strokes,
+        [
+            p(0, -5, false),
+            p(-4, 4, true),
+            p(0, -5, false),
+            p(4, 4, true),
+            p(-2, 1, false),
+            p(2, 1, true),
+        ]
+    );
+    assert_eq!(glyphs[1].id, 502);
+
+    let font = ImportedFont::from_jhf(JHF).unwrap();
+    let chars: Vec<_> = font.glyphs.keys().copied().collect();
+    assert_eq!(chars, [' ', '!']);
+}
+
+#[test]
+fn reports_bad_files() {
+    assert_eq!(parse_jhf("  501  9MW"), Err(ImportError::BadJhfLine(1)));
+    assert_eq!(parse_jhf("\n  50x  1MW"), Err(ImportError::BadJhfLine(2)));
+    assert_eq!(parse_jhf("  501  0"), Err(ImportError::BadJhfLine(1)));
+    assert_eq!(
+        parse_jhf("  501  1M\u{e9}"),
+        Err(ImportError::BadJhfLine(1))
+    );
+
+    assert_eq!(
+        parse_svg_font("<font><glyph unicode='a'", 21.0).err(),
+        Some(ImportError::UnclosedTag)
+    );
+    assert_eq!(
+        parse_svg_font("<!-- <glyph/>", 21.0).err(),
+        Some(ImportError::UnclosedTag)
+    );
+    assert_eq!(
+        parse_svg_font("<glyph unicode=a/>", 21.0).err(),
+        Some(ImportError::BadAttribute)
+    );
+    assert_eq!(
+        parse_svg_font("<glyph unicode='&bogus;'/>", 21.0).err(),
+        Some(ImportError::BadEntity)
+    );
+    assert_eq!(
+        parse_svg_font("<glyph unicode='a' d='M0 0 L'/>", 21.0).err(),
+        Some(ImportError::BadPath('a'))
+    );
+}
+
+#[test]
+fn survives_truncated_files() {
+    // Every prefix of a good file either reads or fails, without panicking
+    for end in (0..=SVG.len()).filter(|&i| SVG.is_char_boundary(i)) {
+        let _ = ImportedFont::from_svg(&SVG[..end]);
+    }
+    for end in 0..=JHF.len() {
+        let _ = ImportedFont::from_jhf(&JHF[..end]);
+    }
+}
+
+#[test]
+fn builds_table_fonts() {
+    let font = ImportedFont::from_svg(SVG).unwrap();
+    let table = font.to_table(&[Subset::Ascii]);
+
+    let (source, data) = font.to_rust("SAMPLE", "sample.bin", &[Subset::Ascii]);
+    assert_eq!(data, table.data());
+    assert!(source.contains("static SAMPLE_TABLE: GlyphTable<'static>"));
+    assert!(source.contains("pub static SAMPLE: TableFont<'static> = TableFont::new("));
+    // Left out by the subset
+    assert!(!source.contains("8211"));
+
+    let mut single = GlyphTableBuilder::new();
+    let l = &font.glyphs[&'L'];
+    single.push('L' as u16, l.left, l.right, &l.strokes);
+    let data = single.data();
+    let offsets = [0, data.len() as u32];
+    let glyphs = GlyphTable::new(&['L' as u16], &offsets, data);
+    let font = TableFont::new(&glyphs, font.metrics);
+
+    let l = font.glyph('L').unwrap();
+    assert_eq!(l.advance(), 15);
+    let points: Vec<_> = l.points().map(|p| (p.x, p.y, p.pen)).collect();
+    assert_eq!(points, [(3, -12, false), (3, 9, true), (12, 9, true)]);
+    assert!(font.glyph('I').is_none());
+    assert_eq!(font.metrics().cap_height, 21);
+}
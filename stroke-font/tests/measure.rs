@@ -0,0 +1,95 @@
+use stroke_font::{
+    Bounds, FallbackFont, FontMetrics, Glyph, GlyphScale, KernedFont, PackedPoint, StrokeFont,
+    Strokes, TextMetrics, measure_text, render_text, strokes, text_width,
+};
+
+/// A bar from the baseline up to the cap height.
+static BAR: [PackedPoint; 2] = [
+    PackedPoint {
+        x: 1,
+        y: 0,
+        pen: false,
+    },
+    PackedPoint {
+        x: 1,
+        y: -4,
+        pen: true,
+    },
+];
+
+/// Draws a bar 4 units wide for 'A', 'V' and 'W', nothing for space.
+struct Bars;
+
+impl StrokeFont for Bars {
+    fn glyph(&self, c: char) -> Option<Glyph<'_>> {
+        let strokes: &[PackedPoint] = match c {
+            'A' | 'V' | 'W' => &BAR,
+            ' ' => &[],
+            _ => return None,
+        };
+        Some(Glyph {
+            left: 0,
+            right: 4,
+            scale: GlyphScale::IDENTITY,
+            strokes: Strokes::Points(strokes),
+        })
+    }
+
+    fn metrics(&self) -> FontMetrics {
+        FontMetrics {
+            baseline: 0,
+            line_height: 6,
+            ascent: 4,
+            descent: 1,
+            cap_height: 4,
+        }
+    }
+}
+
+const KERNED: KernedFont<'static> = KernedFont::new(&Bars, &[('A', 'V', -2), ('A', 'W', -1)]);
+
+#[test]
+fn measures_width_and_bounds() {
+    assert_eq!(
+        measure_text(&Bars, "AV A"),
+        TextMetrics {
+            width: 16,
+            bounds: Some(Bounds {
+                min_x: 1,
+                min_y: -4,
+                max_x: 13,
+                max_y: 0,
+            }),
+        }
+    );
+    assert_eq!(text_width(&Bars, "AV A"), 16);
+    assert_eq!(measure_text(&Bars, " ").bounds, None);
+    assert_eq!(Bars.glyph('A').unwrap().bounds().unwrap().height(), 4);
+}
+
+#[test]
+fn kerns_pairs() {
+    assert_eq!(text_width(&KERNED, "AVAW"), 13);
+
+    let xs: Vec<_> = render_text(&KERNED, "AV").iter().map(|p| p.x).collect();
+    assert_eq!(xs, [1, 1, 3, 3]);
+
+    // Characters without glyphs don't break up pairs
+    assert_eq!(text_width(&KERNED, "A\u{1}V"), 6);
+}
+
+#[test]
+fn fallback_kerns_pairs_from_the_same_font() {
+    let fonts: [&dyn StrokeFont; 1] = [&KERNED];
+    let fallback = FallbackFont::new(&fonts);
+    assert_eq!(fallback.kerning('A', 'V'), -2);
+    // The replacement box isn't kerned
+    assert_eq!(fallback.kerning('A', 'x'), 0);
+}
+
+#[test]
+fn spaces_letters() {
+    let metrics = strokes(&KERNED, "AVA").with_letter_spacing(3).metrics();
+    assert_eq!(metrics.width, 4 - 2 + 3 + 4 + 3 + 4);
+    assert_eq!(metrics.bounds.unwrap().max_x, 1 + 12);
+}
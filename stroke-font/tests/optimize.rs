@@ -0,0 +1,70 @@
+use stroke_font::{PackedPoint, blanked_travel, optimize_strokes};
+
+fn p(x: i8, y: i8, pen: bool) -> PackedPoint {
+    PackedPoint { x, y, pen }
+}
+
+#[test]
+fn reorders_and_reverses_strokes() {
+    // Three vertical bars drawn right, left, middle, each from the top
+    let points = [
+        p(8, 0, false),
+        p(8, 10, true),
+        p(0, 0, false),
+        p(0, 10, true),
+        p(4, 0, false),
+        p(4, 10, true),
+    ];
+
+    let optimized = optimize_strokes(&points, (0, 0));
+    assert_eq!(
+        optimized,
+        [
+            p(0, 0, false),
+            p(0, 10, true),
+            p(4, 10, false),
+            p(4, 0, true),
+            p(8, 0, false),
+            p(8, 10, true),
+        ]
+    );
+    assert_eq!(blanked_travel(&optimized, (0, 0)), 8.0);
+}
+
+#[test]
+fn joins_strokes_that_meet() {
+    let points = [p(0, 0, false), p(5, 0, true), p(5, 5, false), p(5, 0, true)];
+
+    assert_eq!(
+        optimize_strokes(&points, (0, 0)),
+        [p(0, 0, false), p(5, 0, true), p(5, 5, true)]
+    );
+}
+
+#[test]
+fn merges_straight_runs() {
+    let points = [
+        p(0, 0, false),
+        p(1, 1, true),
+        p(2, 2, true),
+        p(2, 2, true),
+        p(4, 4, true),
+        // Doubling back isn't a straight run
+        p(3, 3, true),
+    ];
+
+    assert_eq!(
+        optimize_strokes(&points, (0, 0)),
+        [p(0, 0, false), p(4, 4, true), p(3, 3, true)]
+    );
+}
+
+#[test]
+fn keeps_dots_and_drops_lone_moves() {
+    let points = [p(1, 1, false), p(1, 1, true), p(9, 9, false)];
+
+    assert_eq!(
+        optimize_strokes(&points, (0, 0)),
+        [p(1, 1, false), p(1, 1, true)]
+    );
+}
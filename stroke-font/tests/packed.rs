@@ -0,0 +1,75 @@
+use stroke_font::{GlyphTable, GlyphTableBuilder, PackedPoint, Subset};
+
+fn p(x: i8, y: i8, pen: bool) -> PackedPoint {
+    PackedPoint { x, y, pen }
+}
+
+#[test]
+fn round_trips_glyphs() {
+    let glyphs = [
+        (32, -2, 2, vec![]),
+        (
+            65,
+            -5,
+            5,
+            vec![p(-4, 3, false), p(0, -6, true), p(3, 3, true)],
+        ),
+        // Deltas too big for the short form, and ones that wrap around
+        (
+            300,
+            -128,
+            127,
+            vec![p(127, -128, false), p(-128, 127, true), p(-124, 124, true)],
+        ),
+    ];
+
+    let mut builder = GlyphTableBuilder::new();
+    for (key, left, right, strokes) in &glyphs {
+        builder.push(*key, *left, *right, strokes);
+    }
+
+    let keys: Vec<_> = glyphs.iter().map(|g| g.0).collect();
+    let mut offsets = vec![0];
+    let mut data = Vec::new();
+    for (key, left, right, strokes) in &glyphs {
+        let mut single = GlyphTableBuilder::new();
+        single.push(*key, *left, *right, strokes);
+        data.extend_from_slice(single.data());
+        offsets.push(data.len() as u32);
+    }
+    assert_eq!(builder.data(), &data[..]);
+
+    let table = GlyphTable::new(&keys, &offsets, &data);
+    assert_eq!(table.len(), 3);
+    assert!(table.get(66).is_none());
+
+    for (key, left, right, strokes) in &glyphs {
+        let glyph = table.get(*key).unwrap();
+        assert_eq!((glyph.left, glyph.right), (*left, *right));
+        assert_eq!(glyph.strokes.iter().collect::<Vec<_>>(), *strokes);
+    }
+}
+
+#[test]
+fn small_steps_take_one_byte() {
+    let strokes: Vec<_> = (0..10).map(|i| p(20 + i * 3, -20 - i * 4, i > 0)).collect();
+
+    let mut builder = GlyphTableBuilder::new();
+    builder.push(0, 0, 0, &strokes);
+
+    // Bearings, then the first point's long form and one byte per step
+    assert_eq!(builder.data().len(), 2 + 3 + 9);
+}
+
+#[test]
+fn classifies_subsets() {
+    assert_eq!(Subset::of('A'), Subset::Ascii);
+    assert_eq!(Subset::of('é'), Subset::Latin);
+    assert_eq!(Subset::of('Ж'), Subset::GreekCyrillic);
+    assert_eq!(Subset::of('か'), Subset::Cjk);
+    assert_eq!(Subset::of('€'), Subset::Symbols);
+    assert_eq!(
+        Subset::GreekCyrillic.feature_env(),
+        "CARGO_FEATURE_GREEK_CYRILLIC"
+    );
+}
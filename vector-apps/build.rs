@@ -0,0 +1,64 @@
+use std::{fs, path::PathBuf};
+
+use stroke_font::{ImportedFont, Subset};
+
+/// Rust identifier for the font in `path`, like `SVG_SEGMENT` for
+/// `segment.svg`.
+fn ident(kind: &str, stem: &str) -> String {
+    let stem: String = stem
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{}_{}", kind, stem).to_uppercase()
+}
+
+fn main() {
+    let subsets: Vec<_> = Subset::ALL
+        .into_iter()
+        .filter(|s| std::env::var_os(s.feature_env()).is_some())
+        .collect();
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+
+    // Every .svg and .jhf file in fonts/, by name
+    let mut files: Vec<_> = fs::read_dir("fonts")
+        .map(|dir| dir.map(|f| f.unwrap().path()).collect())
+        .unwrap_or_default();
+    files.sort();
+
+    let mut source = String::new();
+    let mut names = Vec::new();
+    for path in files {
+        let Some(kind) = path.extension().and_then(|e| e.to_str()) else {
+            continue;
+        };
+        let stem = path.file_stem().unwrap().to_str().unwrap().to_lowercase();
+        let contents = fs::read_to_string(&path).unwrap();
+
+        let font = match kind {
+            "svg" => ImportedFont::from_svg(&contents),
+            "jhf" => ImportedFont::from_jhf(&contents),
+            _ => continue,
+        }
+        .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+
+        let ident = ident(kind, &stem);
+        let data_file = format!("{}.bin", ident.to_lowercase());
+        let (rust, data) = font.to_rust(&ident, &data_file, &subsets);
+        source.push_str(&rust);
+        fs::write(out_dir.join(data_file), data).unwrap();
+        names.push((format!("{}:{}", kind, stem), ident));
+    }
+
+    source.push_str(&format!(
+        "pub static IMPORTED_FONTS: [(&str, &TableFont<'static>); {}] = [\n",
+        names.len()
+    ));
+    for (name, ident) in names {
+        source.push_str(&format!("    (\"{}\", &{}),\n", name, ident));
+    }
+    source.push_str("];\n");
+
+    fs::write(out_dir.join("imported_fonts.rs"), source).unwrap();
+
+    println!("cargo:rerun-if-changed=fonts");
+}
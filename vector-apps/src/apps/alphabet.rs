@@ -1,44 +1,56 @@
 use alloc::{string::String, vec::Vec};
-// use hershey_text::fonts;
-use itertools::Itertools;
 
 use crate::{
     apps::VectorApp,
-    point::{Path, Point},
-    utils::{colors::hsl_to_rgb, text::text_to_path_gradient},
+    point::Path,
+    utils::{
+        layout::{HAlign, Rect, TextLayout, VAlign},
+        text::{DEFAULT_FONT, Line},
+        text_animation::{TextAnimation, TextEffect},
+    },
 };
 
 pub struct AlphabetDemo {
-    points: Vec<Point>,
+    lines: Vec<Line>,
+    animation: TextAnimation,
+    points: Path,
 }
 
 impl AlphabetDemo {
     pub fn new(text: String) -> Self {
-        let points = text
-            .chars()
-            .chunks(8)
-            .into_iter()
-            .take(5)
-            .enumerate()
-            .flat_map(|(i, mut line)| {
-                text_to_path_gradient(
-                    &line.join(""),
-                    0,
-                    32 + 32 * i as u8,
-                    1.0,
-                    1.0,
-                    |x| hsl_to_rgb(x * 0.001, 1.0, 0.5),
-                    // fonts::ROMANS,
-                )
+        let lines = TextLayout::new(DEFAULT_FONT, Rect::new(16.0, 16.0, 224.0, 224.0))
+            .align(HAlign::Center, VAlign::Middle)
+            .scale(8.0, 8.0)
+            .line_spacing(1.25)
+            .wrap(true)
+            .fit(true)
+            .lines(&text);
+
+        let animation = TextAnimation::new()
+            .with(TextEffect::Typewriter { rate: 0.25 })
+            .with(TextEffect::Wave {
+                amplitude: 0.75,
+                wavelength: 8.0,
+                speed: 0.02,
             })
-            .collect();
+            .with(TextEffect::Rainbow {
+                spread: 0.06,
+                speed: 0.004,
+            });
 
-        Self { points }
+        Self {
+            lines,
+            animation,
+            points: Vec::new(),
+        }
     }
 }
 
 impl VectorApp for AlphabetDemo {
-    fn get_path(&mut self, _frame: u64) -> &Path {
+    fn get_path(&mut self, frame: u64) -> &Path {
+        self.points =
+            self.animation
+                .lines_to_path(DEFAULT_FONT, &self.lines, frame as f32, (255, 255, 255));
         &self.points
     }
 }
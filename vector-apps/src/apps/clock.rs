@@ -5,12 +5,14 @@ use jiff::Timestamp;
 use jiff::tz::TimeZone;
 
 use alloc::vec::Vec;
-// use hershey_text::fonts;
 
 use crate::{
     apps::VectorApp,
     point::{Path, Point},
-    utils::text::text_to_path,
+    utils::{
+        layout::{HAlign, Rect, TextLayout, VAlign, measure},
+        text::DEFAULT_FONT,
+    },
 };
 
 pub trait TimeSource {
@@ -56,57 +58,57 @@ impl<T: TimeSource> VectorApp for Clock<T> {
         let second = dt.second() as u8;
 
         // Time (HH:MM:SS)
-        self.path.append(&mut text_to_path(
-            &format!("{:02} {:02} {:02}", hour, minute, second),
-            6,
-            96,
-            1.5,
-            1.5,
-            color,
-            // fonts::ROMANS,
-        ));
+        let time = format!("{:02} {:02} {:02}", hour, minute, second);
+        let layout = TextLayout::new(DEFAULT_FONT, Rect::new(8.0, 0.0, 240.0, 256.0))
+            .align(HAlign::Center, VAlign::Middle)
+            .scale(5.0, 5.0)
+            .fit(true);
+        self.path.append(&mut layout.to_path(&time, color));
 
         // Date (YYYY-MM-DD)
         // self.path.append(&mut text_to_path(
+        //     DEFAULT_FONT,
         //     &format!("{:04}-{:02}-{:02}", year, month, day),
         //     0,
         //     144,
         //     1.0,
         //     1.0,
         //     color,
-        //     fonts::ROMANS,
         // ));
 
-        // draw the lil dots?
-        self.path.push(Point {
-            x: 78,
-            y: 96,
-            color: (0, 0, 0),
-            delay: 400,
-        });
-        self.path.push(Point {
-            x: 78,
-            y: 96,
-            color,
-            delay: 400,
-        });
-        self.path.push(Point {
-            x: 162,
-            y: 96,
-            color: (0, 0, 0),
-            delay: 400,
-        });
-        self.path.push(Point {
-            x: 162,
-            y: 96,
-            color,
-            delay: 400,
-        });
+        let Some(line) = layout.lines(&time).pop() else {
+            return &self.path;
+        };
+        let baseline = DEFAULT_FONT.metrics().baseline as f32;
+        let space = measure(DEFAULT_FONT, " ") as f32;
+
+        // draw the lil dots in the middle of the gaps
+        for digits in ["00", "00 00"] {
+            let x = measure(DEFAULT_FONT, digits) as f32 + space / 2.0;
+            let (x, y) = line.transform.apply(x, baseline);
+            let (x, y) = (x as u8, y as u8);
+
+            self.path.push(Point {
+                x,
+                y,
+                color: (0, 0, 0),
+                delay: 400,
+            });
+            self.path.push(Point {
+                x,
+                y,
+                color,
+                delay: 400,
+            });
+        }
 
         // laser off at end
+        let (x, y) = line
+            .transform
+            .apply(measure(DEFAULT_FONT, &time) as f32, baseline);
         self.path.push(Point {
-            x: 192,
-            y: 96,
+            x: x as u8,
+            y: y as u8,
             color: (0, 0, 0),
             delay: 0,
         });
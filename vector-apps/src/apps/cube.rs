@@ -1,9 +1,8 @@
 use crate::{
     point::{Path, Point},
-    utils::text::text_to_path,
+    utils::text::{DEFAULT_FONT, text_to_path},
 };
 use alloc::vec::Vec;
-// use hershey_text::fonts;
 
 use crate::apps::VectorApp;
 
@@ -49,22 +48,22 @@ impl CubeDemo {
         let mut static_points = Vec::new();
 
         static_points.append(&mut text_to_path(
+            DEFAULT_FONT,
             "Hello",
             0,
             16,
             1.0,
             1.0,
             (255, 0, 0),
-            // fonts::ROMANS,
         ));
         static_points.append(&mut text_to_path(
+            DEFAULT_FONT,
             "World",
             176,
             240,
             1.0,
             1.0,
             (255, 0, 0),
-            // fonts::ROMANS,
         ));
 
         Self {
@@ -3,6 +3,7 @@ use alloc::{boxed::Box, vec::Vec};
 use crate::{
     apps::{Controls, VectorApp},
     point::Path,
+    storage::{Storage, StorageError},
 };
 
 pub struct Cycle {
@@ -14,6 +15,18 @@ impl Cycle {
     pub fn new(apps: Vec<Box<dyn VectorApp>>) -> Self {
         Self { apps, idx: 0 }
     }
+
+    pub fn app_count(&self) -> usize {
+        self.apps.len()
+    }
+
+    /// Switch to app `idx`, wrapping past the last one. Does nothing when
+    /// there are no apps.
+    pub fn select(&mut self, idx: usize) {
+        if let Some(idx) = idx.checked_rem(self.apps.len()) {
+            self.idx = idx;
+        }
+    }
 }
 
 impl VectorApp for Cycle {
@@ -30,4 +43,24 @@ impl VectorApp for Cycle {
             self.apps[self.idx].handle_controls(controls);
         }
     }
+
+    fn load_state(&mut self, storage: &dyn Storage) {
+        if let Some(idx) = storage.get_u32("cycle.idx") {
+            self.select(idx as usize);
+        }
+
+        for app in &mut self.apps {
+            app.load_state(storage);
+        }
+    }
+
+    fn save_state(&self, storage: &mut dyn Storage) -> Result<(), StorageError> {
+        storage.set_u32("cycle.idx", self.idx as u32)?;
+
+        for app in &self.apps {
+            app.save_state(storage)?;
+        }
+
+        Ok(())
+    }
 }
@@ -1,28 +1,163 @@
-use alloc::vec::Vec;
-
 use crate::{
-    apps::VectorApp,
-    point::{Path, Point},
-    utils::ilda::read_ilda,
+    apps::{Controls, VectorApp},
+    point::Path,
+    utils::{
+        ilda::{IldaAnimation, read_ilda_animation},
+        scanner::ScannerProfile,
+    },
 };
 
-pub struct Ilda {
-    points: Vec<Point>,
+/// Frames per second of an animation, which ILDA files don't say.
+const DEFAULT_FPS: f32 = 30.0;
+
+const MIN_SPEED: f32 = 0.125;
+const MAX_SPEED: f32 = 8.0;
+
+const EMPTY: &Path = &Path::new();
+
+/// `v` wrapped into `0.0..n`.
+fn wrap(v: f32, n: f32) -> f32 {
+    let r = libm::fmodf(v, n);
+    if r < 0.0 { r + n } else { r }
 }
 
-const ILDA_KPPS: u8 = 12;
+/// What happens after the last frame of an animation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PlaybackMode {
+    /// Start again from the first frame.
+    Loop,
+    /// Play backwards to the first frame, then forwards again.
+    PingPong,
+    /// Stay on the last frame.
+    Once,
+}
+
+/// Plays an ILDA animation. Up and down on the stick change the speed, left
+/// and right step through frames one at a time, and `a` pauses.
+pub struct Ilda {
+    animation: IldaAnimation,
+    fps: f32,
+    mode: PlaybackMode,
+    speed: f32,
+    paused: bool,
+    /// How far playback has got, in frames. Ping-pong counts the frames on
+    /// the way back too.
+    position: f32,
+    /// Microseconds the last path took to draw, which is the time since it
+    /// was asked for.
+    drawn_us: u32,
+    last_controls: Controls,
+}
 
 impl Ilda {
     pub fn new() -> Self {
-        let paths = read_ilda(include_bytes!("ildatest.ild"), ILDA_KPPS);
-        let points = paths.get("ILDA Tes").unwrap().to_vec();
+        let animation =
+            read_ilda_animation(include_bytes!("ildatest.ild"), &ScannerProfile::new()).unwrap();
+        Self::from_animation(animation)
+    }
+
+    pub fn from_animation(animation: IldaAnimation) -> Self {
+        Self {
+            animation,
+            fps: DEFAULT_FPS,
+            mode: PlaybackMode::Loop,
+            speed: 1.0,
+            paused: false,
+            position: 0.0,
+            drawn_us: 0,
+            last_controls: Controls::default(),
+        }
+    }
+
+    pub fn with_fps(mut self, fps: f32) -> Self {
+        self.fps = fps;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: PlaybackMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Multiple of the frame rate to play at.
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+        self
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
 
-        Self { points }
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Index of the frame being shown.
+    pub fn frame(&self) -> usize {
+        let len = self.animation.len();
+        if len == 0 {
+            return 0;
+        }
+        let position = self.position as usize;
+        let index = match self.mode {
+            PlaybackMode::PingPong if position >= len => 2 * (len - 1) - position,
+            _ => position,
+        };
+        // Rounding can leave a looping position right at the end
+        index.min(len - 1)
+    }
+
+    /// Move `frames` along the order frames play in, which can be negative.
+    pub fn step(&mut self, frames: f32) {
+        let len = self.animation.len() as f32;
+        if len == 0.0 {
+            return;
+        }
+
+        self.position += frames;
+        self.position = match self.mode {
+            PlaybackMode::Loop => wrap(self.position, len),
+            PlaybackMode::PingPong if len > 1.0 => wrap(self.position, 2.0 * (len - 1.0)),
+            PlaybackMode::PingPong | PlaybackMode::Once => self.position.clamp(0.0, len - 1.0),
+        };
     }
 }
 
 impl VectorApp for Ilda {
     fn get_path(&mut self, _frame: u64) -> &Path {
-        &self.points
+        if !self.paused {
+            self.step(self.drawn_us as f32 / 1_000_000.0 * self.fps * self.speed);
+        }
+
+        let Some(frame) = self.animation.frames.get(self.frame()) else {
+            return EMPTY;
+        };
+
+        self.drawn_us = frame.path.iter().map(|p| p.delay as u32).sum();
+        &frame.path
+    }
+
+    fn handle_controls(&mut self, controls: Controls) {
+        let last = self.last_controls;
+        self.last_controls = controls;
+
+        if controls.a && !last.a {
+            self.paused = !self.paused;
+        }
+
+        if controls.y != 0 && last.y == 0 {
+            let factor = if controls.y > 0 { 2.0 } else { 0.5 };
+            self.speed = (self.speed * factor).clamp(MIN_SPEED, MAX_SPEED);
+        }
+
+        if controls.x != 0 && last.x == 0 {
+            self.paused = true;
+            self.step(controls.x.signum() as f32);
+        }
     }
 }
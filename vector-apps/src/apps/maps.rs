@@ -3,6 +3,7 @@ use alloc::vec::Vec;
 use crate::{
     apps::{Controls, VectorApp},
     point::{Path, Point},
+    storage::{Storage, StorageError},
     utils::math::Vec2,
 };
 
@@ -377,4 +378,17 @@ impl VectorApp for Maps {
 
         self.generate_path();
     }
+
+    fn load_state(&mut self, storage: &dyn Storage) {
+        if let (Some(lat), Some(lon)) = (storage.get_f32("maps.lat"), storage.get_f32("maps.lon")) {
+            self.lat = lat;
+            self.lon = lon;
+            self.generate_path();
+        }
+    }
+
+    fn save_state(&self, storage: &mut dyn Storage) -> Result<(), StorageError> {
+        storage.set_f32("maps.lat", self.lat)?;
+        storage.set_f32("maps.lon", self.lon)
+    }
 }
@@ -0,0 +1,245 @@
+use alloc::{string::String, vec::Vec};
+use stroke_font::StrokeFont;
+
+use crate::{
+    apps::{Controls, VectorApp},
+    point::Path,
+    utils::{
+        layout::{Rect, measure},
+        math::Affine,
+        text::{UNICODE_FONT, strokes_to_path},
+    },
+};
+
+/// Blank space between the end of the message and its next repeat.
+const GAP: &str = "    ";
+
+const MAX_SPEED: f32 = 8.0;
+
+/// Scrolls a message of any length right to left through a window.
+pub struct Marquee {
+    font: &'static dyn StrokeFont,
+    window: Rect,
+    speed: f32,
+    color: (u8, u8, u8),
+    message: String,
+    strokes: Vec<Pen>,
+    /// Width of the message plus the gap, in glyph units.
+    period: i32,
+    /// How far the message has scrolled, in DAC units.
+    offset: f32,
+    path: Path,
+}
+
+impl Marquee {
+    pub fn new(message: &str) -> Self {
+        let mut marquee = Self {
+            font: UNICODE_FONT,
+            window: Rect::new(0.0, 96.0, 256.0, 64.0),
+            speed: 1.0,
+            color: (255, 0, 0),
+            message: String::new(),
+            strokes: Vec::new(),
+            period: 0,
+            offset: 0.0,
+            path: Vec::new(),
+        };
+        marquee.set_message(message);
+        marquee
+    }
+
+    pub fn with_font(mut self, font: &'static dyn StrokeFont) -> Self {
+        self.font = font;
+        let message = core::mem::take(&mut self.message);
+        self.set_message(&message);
+        self
+    }
+
+    /// The text is scaled to the height of the window and clipped to its
+    /// sides.
+    pub fn with_window(mut self, window: Rect) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Scroll speed in DAC units per frame.
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn with_color(mut self, color: (u8, u8, u8)) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Replace the scrolling text. The scroll position carries on from where
+    /// it was, so the change shows up as the new text scrolls in.
+    pub fn set_message(&mut self, message: &str) {
+        self.message = message.replace('\n', " ");
+        self.strokes.clear();
+        let width = layout(self.font, &self.message, &mut self.strokes);
+        self.period = width + measure(self.font, GAP) as i32;
+    }
+
+    fn scale(&self) -> f32 {
+        let metrics = self.font.metrics();
+        self.window.height / (metrics.ascent + metrics.descent) as f32
+    }
+}
+
+/// A pen position in a message, which can run further than an `i16` reaches.
+#[derive(Debug, Copy, Clone)]
+struct Pen {
+    x: i32,
+    y: i16,
+    pen: bool,
+}
+
+/// Append the strokes of `text` laid out along the baseline to `strokes`,
+/// and return its width in glyph units.
+fn layout(font: &dyn StrokeFont, text: &str, strokes: &mut Vec<Pen>) -> i32 {
+    let mut x = 0;
+    let mut prev = None;
+
+    for c in text.chars() {
+        let Some(glyph) = font.glyph(c) else {
+            continue;
+        };
+
+        if let Some(prev) = prev {
+            x += font.kerning(prev, c) as i32;
+        }
+        strokes.extend(glyph.points().map(|p| Pen {
+            x: x + p.x as i32,
+            y: p.y,
+            pen: p.pen,
+        }));
+        x += glyph.advance() as i32;
+        prev = Some(c);
+    }
+
+    x
+}
+
+/// Cut `strokes` down to the parts between `left` and `right`, shifting them
+/// `dx` glyph units to the right first.
+fn clip_strokes(
+    strokes: &[Pen],
+    dx: i32,
+    left: f32,
+    right: f32,
+    out: &mut Vec<stroke_font::Point>,
+) {
+    let mut pen_at = None;
+
+    for (i, to) in strokes.iter().enumerate() {
+        let from = strokes[i.saturating_sub(1)];
+        if !to.pen {
+            continue;
+        }
+
+        let (x0, x1) = ((from.x + dx) as f32, (to.x + dx) as f32);
+        let (y0, y1) = (from.y as f32, to.y as f32);
+
+        // Parametric range of the segment that lies inside the window
+        let (mut t0, mut t1) = (0.0f32, 1.0f32);
+        if x0 == x1 {
+            if x0 < left || x0 > right {
+                continue;
+            }
+        } else {
+            let ta = (left - x0) / (x1 - x0);
+            let tb = (right - x0) / (x1 - x0);
+            t0 = t0.max(ta.min(tb));
+            t1 = t1.min(ta.max(tb));
+            if t0 > t1 {
+                continue;
+            }
+        }
+
+        // Round cut points inwards so they never land outside the window
+        let at = |t: f32| {
+            let x = x0 + (x1 - x0) * t;
+            let x = if t == 0.0 || t == 1.0 {
+                x
+            } else if x < (left + right) / 2.0 {
+                libm::ceilf(x)
+            } else {
+                libm::floorf(x)
+            };
+            (x as i16, libm::roundf(y0 + (y1 - y0) * t) as i16)
+        };
+        let (start, end) = (at(t0), at(t1));
+
+        if pen_at != Some(start) {
+            out.push(stroke_font::Point {
+                x: start.0,
+                y: start.1,
+                pen: false,
+            });
+        }
+        out.push(stroke_font::Point {
+            x: end.0,
+            y: end.1,
+            pen: true,
+        });
+        pen_at = Some(end);
+    }
+}
+
+impl VectorApp for Marquee {
+    fn get_path(&mut self, _frame: u64) -> &Path {
+        self.path.clear();
+        if self.period <= 0 {
+            return &self.path;
+        }
+
+        let metrics = self.font.metrics();
+        let scale = self.scale();
+        let period = self.period as f32 * scale;
+
+        self.offset = (self.offset + self.speed) % period;
+        if self.offset < 0.0 {
+            self.offset += period;
+        }
+
+        // Work in glyph units with the window's left edge at `offset`, so only
+        // whole copies of the message need shifting
+        let left = self.offset / scale;
+        let right = left + self.window.width / scale;
+
+        // Clipped strokes are drawn relative to `origin`, which keeps them in
+        // reach of an `i16` however far into the message the window is
+        let origin = libm::floorf(left) as i32;
+        let mut strokes = Vec::new();
+        let first = libm::floorf(left / self.period as f32) as i32;
+        let last = libm::floorf(right / self.period as f32) as i32;
+        for copy in first..=last {
+            clip_strokes(
+                &self.strokes,
+                copy * self.period - origin,
+                left - origin as f32,
+                right - origin as f32,
+                &mut strokes,
+            );
+        }
+
+        let baseline = self.window.y + metrics.ascent as f32 * scale;
+        let transform = Affine::scale(scale, scale).then(Affine::translate(
+            self.window.x - self.offset + origin as f32 * scale,
+            baseline - metrics.baseline as f32 * scale,
+        ));
+
+        self.path = strokes_to_path(&strokes, &transform, |_| self.color);
+        &self.path
+    }
+
+    fn handle_controls(&mut self, controls: Controls) {
+        self.speed = (self.speed + controls.x as f32 * 0.25).clamp(-MAX_SPEED, MAX_SPEED);
+    }
+}
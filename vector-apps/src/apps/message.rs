@@ -0,0 +1,68 @@
+use alloc::vec::Vec;
+
+use crate::{
+    apps::VectorApp,
+    point::Path,
+    utils::{
+        layout::{HAlign, Rect, VAlign},
+        markup::{MarkupError, TextRun, TextStyle, parse_markup, runs_to_path},
+        text::UNICODE_FONT,
+    },
+};
+
+/// Capital height of unstyled text, in DAC units. Longer messages shrink to
+/// fit.
+const CAP_HEIGHT: f32 = 24.0;
+
+/// Shows a message written in [markup](crate::utils::markup), such as one
+/// sent over the serial port.
+pub struct Message {
+    runs: Vec<TextRun>,
+    window: Rect,
+    path: Path,
+}
+
+impl Message {
+    /// The style unstyled text starts in.
+    pub const BASE_STYLE: TextStyle = TextStyle::new(UNICODE_FONT, (255, 255, 255));
+
+    pub fn new(markup: &str) -> Result<Self, MarkupError> {
+        let mut message = Self {
+            runs: Vec::new(),
+            window: Rect::new(16.0, 16.0, 224.0, 224.0),
+            path: Vec::new(),
+        };
+        message.set_markup(markup)?;
+        Ok(message)
+    }
+
+    pub fn with_window(mut self, window: Rect) -> Self {
+        self.window = window;
+        self.layout();
+        self
+    }
+
+    /// Replace the message, leaving the old one showing if `markup` has a
+    /// mistake in it.
+    pub fn set_markup(&mut self, markup: &str) -> Result<(), MarkupError> {
+        self.runs = parse_markup(markup, Self::BASE_STYLE)?;
+        self.layout();
+        Ok(())
+    }
+
+    fn layout(&mut self) {
+        self.path = runs_to_path(
+            &self.runs,
+            self.window,
+            HAlign::Center,
+            VAlign::Middle,
+            CAP_HEIGHT,
+        );
+    }
+}
+
+impl VectorApp for Message {
+    fn get_path(&mut self, _frame: u64) -> &Path {
+        &self.path
+    }
+}
@@ -1,4 +1,9 @@
-use crate::point::Path;
+use alloc::boxed::Box;
+
+use crate::{
+    point::Path,
+    storage::{Storage, StorageError},
+};
 
 pub mod align;
 pub mod alphabet;
@@ -8,7 +13,9 @@ pub mod cube;
 pub mod cycle;
 pub mod ilda;
 pub mod maps;
+pub mod marquee;
 pub mod mbta;
+pub mod message;
 
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Controls {
@@ -29,4 +36,30 @@ pub trait VectorApp {
     fn get_path(&mut self, frame: u64) -> &Path;
 
     fn handle_controls(&mut self, _controls: Controls) {}
+
+    /// Restore any state saved by a previous run.
+    fn load_state(&mut self, _storage: &dyn Storage) {}
+
+    /// Persist state that should survive a reboot.
+    fn save_state(&self, _storage: &mut dyn Storage) -> Result<(), StorageError> {
+        Ok(())
+    }
+}
+
+impl<T: VectorApp + ?Sized> VectorApp for Box<T> {
+    fn get_path(&mut self, frame: u64) -> &Path {
+        (**self).get_path(frame)
+    }
+
+    fn handle_controls(&mut self, controls: Controls) {
+        (**self).handle_controls(controls)
+    }
+
+    fn load_state(&mut self, storage: &dyn Storage) {
+        (**self).load_state(storage)
+    }
+
+    fn save_state(&self, storage: &mut dyn Storage) -> Result<(), StorageError> {
+        (**self).save_state(storage)
+    }
 }
@@ -6,4 +6,6 @@ pub mod apps;
 
 pub mod point;
 
+pub mod storage;
+
 pub mod utils;
@@ -0,0 +1,97 @@
+//! Persistent key-value storage for app state.
+//!
+//! The firmware backs this with a region of flash and the simulator with a
+//! file, so apps can keep things like map position and settings across reboots.
+
+use alloc::{collections::btree_map::BTreeMap, string::String, vec::Vec};
+
+pub mod flash;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageError {
+    /// There is no room left in the backing store for the value.
+    Full,
+    /// The key or value is larger than the backing store supports.
+    TooLarge,
+    /// The underlying flash or file reported an error.
+    Io,
+    /// The backing store is too small to use, like flash with fewer than
+    /// two sectors to wear-level over.
+    TooSmall,
+}
+
+pub trait Storage {
+    /// Look up the raw bytes stored under `key`.
+    fn get(&self, key: &str) -> Option<Vec<u8>>;
+
+    /// Store `value` under `key`, replacing any previous value.
+    fn set(&mut self, key: &str, value: &[u8]) -> Result<(), StorageError>;
+
+    /// Delete `key`, if present.
+    fn remove(&mut self, key: &str) -> Result<(), StorageError>;
+
+    /// All keys currently stored, in sorted order.
+    fn keys(&self) -> Vec<String>;
+
+    /// Delete every key.
+    fn clear(&mut self) -> Result<(), StorageError>;
+
+    fn get_u32(&self, key: &str) -> Option<u32> {
+        let bytes = self.get(key)?;
+        Some(u32::from_le_bytes(bytes.as_slice().try_into().ok()?))
+    }
+
+    fn set_u32(&mut self, key: &str, value: u32) -> Result<(), StorageError> {
+        self.set(key, &value.to_le_bytes())
+    }
+
+    fn get_f32(&self, key: &str) -> Option<f32> {
+        self.get_u32(key).map(f32::from_bits)
+    }
+
+    fn set_f32(&mut self, key: &str, value: f32) -> Result<(), StorageError> {
+        self.set_u32(key, value.to_bits())
+    }
+}
+
+/// Storage that lives only in RAM. Useful for tests and as a cache in front
+/// of slower backends.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStorage {
+    entries: BTreeMap<String, Vec<u8>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Vec<u8>)> {
+        self.entries.iter()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        self.entries.get(key).cloned()
+    }
+
+    fn set(&mut self, key: &str, value: &[u8]) -> Result<(), StorageError> {
+        self.entries.insert(String::from(key), value.to_vec());
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), StorageError> {
+        self.entries.remove(key);
+        Ok(())
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.entries.keys().cloned().collect()
+    }
+
+    fn clear(&mut self) -> Result<(), StorageError> {
+        self.entries.clear();
+        Ok(())
+    }
+}
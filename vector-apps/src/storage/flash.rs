@@ -0,0 +1,357 @@
+//! Wear-leveled key-value log over a flash region.
+//!
+//! The region is split into erase sectors which are used as a ring. Only one
+//! sector is active at a time: it starts with a header holding a sequence
+//! number, followed by an append-only list of records. Updating a key appends
+//! a new record; when the active sector fills up, every live entry is copied
+//! into the next sector and writing continues there. The new sector's header
+//! (with a higher sequence number) is only written once the copy is done, and
+//! on boot the sector with the highest sequence number wins, so a power cut
+//! part-way through a compaction leaves the previous sector in charge.
+
+use alloc::{collections::btree_map::BTreeMap, string::String, vec, vec::Vec};
+use embedded_storage::nor_flash::NorFlash;
+
+use crate::storage::{Storage, StorageError};
+
+// Record layout (all fields little-endian, padded to a 4-byte boundary):
+//
+//   key_len: u8   (0xFF = erased, end of log)
+//   val_len: u16  (TOMBSTONE bit set = key was deleted)
+//   crc: u8       (over key_len, val_len, key and value)
+//   key: [u8; key_len]
+//   value: [u8; val_len]
+
+const SECTOR_MAGIC: u32 = u32::from_le_bytes(*b"GVKV");
+const SECTOR_HEADER_LEN: usize = 8;
+const RECORD_HEADER_LEN: usize = 4;
+const ALIGN: usize = 4;
+
+const ERASED: u8 = 0xFF;
+const TOMBSTONE: u16 = 0x8000;
+const MAX_KEY_LEN: usize = 0xFE;
+const MAX_VALUE_LEN: usize = TOMBSTONE as usize - 1;
+
+fn align_up(n: usize) -> usize {
+    n.div_ceil(ALIGN) * ALIGN
+}
+
+fn crc8(data: &[u8]) -> u8 {
+    let mut crc: u8 = 0;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+fn encode_record(key: &str, value: Option<&[u8]>) -> Vec<u8> {
+    let body = value.unwrap_or(&[]);
+    let val_len = match value {
+        Some(v) => v.len() as u16,
+        None => TOMBSTONE,
+    };
+
+    let mut record = vec![ERASED; align_up(RECORD_HEADER_LEN + key.len() + body.len())];
+    record[0] = key.len() as u8;
+    record[1..3].copy_from_slice(&val_len.to_le_bytes());
+    record[RECORD_HEADER_LEN..RECORD_HEADER_LEN + key.len()].copy_from_slice(key.as_bytes());
+    record[RECORD_HEADER_LEN + key.len()..RECORD_HEADER_LEN + key.len() + body.len()]
+        .copy_from_slice(body);
+
+    let mut crc_input = Vec::with_capacity(3 + key.len() + body.len());
+    crc_input.extend_from_slice(&record[0..3]);
+    crc_input.extend_from_slice(key.as_bytes());
+    crc_input.extend_from_slice(body);
+    record[3] = crc8(&crc_input);
+
+    record
+}
+
+pub struct FlashStore<F: NorFlash> {
+    flash: F,
+    sector_size: usize,
+    sector_count: usize,
+    active: usize,
+    seq: u32,
+    cursor: usize,
+    entries: BTreeMap<String, Vec<u8>>,
+}
+
+impl<F: NorFlash> FlashStore<F> {
+    /// Mount the store, replaying the most recent sector. If no valid sector
+    /// is found (e.g. on first boot) the region is formatted. Compaction
+    /// needs somewhere to copy to, so the region must hold at least two
+    /// sectors.
+    ///
+    /// If the flash can't be tidied up while mounting, what was read is
+    /// still available, but nothing is written until a later write manages
+    /// to compact into an erased sector.
+    pub fn new(flash: F) -> Result<Self, StorageError> {
+        let sector_size = F::ERASE_SIZE;
+        let sector_count = flash.capacity() / sector_size;
+        if sector_count < 2 {
+            return Err(StorageError::TooSmall);
+        }
+
+        let mut store = Self {
+            flash,
+            sector_size,
+            sector_count,
+            active: 0,
+            seq: 0,
+            cursor: SECTOR_HEADER_LEN,
+            entries: BTreeMap::new(),
+        };
+
+        let newest = (0..sector_count)
+            .filter_map(|sector| store.read_sector_seq(sector).map(|seq| (seq, sector)))
+            .max();
+
+        match newest {
+            Some((seq, sector)) => {
+                store.active = sector;
+                store.seq = seq;
+                // The log ends in a torn write. Move everything we did read
+                // into a fresh sector so new records land after valid data
+                // only.
+                if !store.replay() && store.compact().is_err() {
+                    store.seal();
+                }
+            }
+            None => {
+                if store.format().is_err() {
+                    store.seal();
+                }
+            }
+        }
+
+        Ok(store)
+    }
+
+    fn sector_offset(&self, sector: usize) -> u32 {
+        (sector * self.sector_size) as u32
+    }
+
+    fn read_sector_seq(&mut self, sector: usize) -> Option<u32> {
+        let mut header = [0; SECTOR_HEADER_LEN];
+        self.flash
+            .read(self.sector_offset(sector), &mut header)
+            .ok()?;
+
+        let magic = u32::from_le_bytes(header[0..4].try_into().unwrap());
+        let seq = u32::from_le_bytes(header[4..8].try_into().unwrap());
+
+        (magic == SECTOR_MAGIC).then_some(seq)
+    }
+
+    /// Load all records of the active sector into the cache. Returns `false`
+    /// if a corrupt record was found before the end of the log.
+    fn replay(&mut self) -> bool {
+        let base = self.sector_offset(self.active);
+        self.cursor = SECTOR_HEADER_LEN;
+        self.entries.clear();
+
+        while self.cursor + RECORD_HEADER_LEN <= self.sector_size {
+            let mut header = [0; RECORD_HEADER_LEN];
+            if self
+                .flash
+                .read(base + self.cursor as u32, &mut header)
+                .is_err()
+            {
+                return false;
+            }
+
+            if header[0] == ERASED {
+                return true;
+            }
+
+            let key_len = header[0] as usize;
+            let val_len = u16::from_le_bytes([header[1], header[2]]);
+            let body_len = if val_len & TOMBSTONE != 0 {
+                0
+            } else {
+                val_len as usize
+            };
+
+            let len = align_up(RECORD_HEADER_LEN + key_len + body_len);
+            if self.cursor + len > self.sector_size {
+                return false;
+            }
+
+            let mut body = vec![0; len - RECORD_HEADER_LEN];
+            if self
+                .flash
+                .read(base + (self.cursor + RECORD_HEADER_LEN) as u32, &mut body)
+                .is_err()
+            {
+                return false;
+            }
+
+            let mut crc_input = Vec::with_capacity(3 + key_len + body_len);
+            crc_input.extend_from_slice(&header[0..3]);
+            crc_input.extend_from_slice(&body[..key_len + body_len]);
+            if crc8(&crc_input) != header[3] {
+                return false;
+            }
+
+            let Ok(key) = core::str::from_utf8(&body[..key_len]) else {
+                return false;
+            };
+
+            if val_len & TOMBSTONE != 0 {
+                self.entries.remove(key);
+            } else {
+                self.entries.insert(
+                    String::from(key),
+                    body[key_len..key_len + body_len].to_vec(),
+                );
+            }
+
+            self.cursor += len;
+        }
+
+        true
+    }
+
+    fn erase_sector(&mut self, sector: usize) -> Result<(), StorageError> {
+        let from = self.sector_offset(sector);
+        self.flash
+            .erase(from, from + self.sector_size as u32)
+            .map_err(|_| StorageError::Io)
+    }
+
+    /// Mark `sector` as holding the log as of `seq`, making it the one
+    /// mounted at boot.
+    fn write_header(&mut self, sector: usize, seq: u32) -> Result<(), StorageError> {
+        // The sequence number goes down before the magic, so a torn header
+        // never looks valid
+        let from = self.sector_offset(sector);
+        self.flash
+            .write(from + 4, &seq.to_le_bytes())
+            .map_err(|_| StorageError::Io)?;
+        self.flash
+            .write(from, &SECTOR_MAGIC.to_le_bytes())
+            .map_err(|_| StorageError::Io)
+    }
+
+    fn append(&mut self, record: &[u8]) -> Result<(), StorageError> {
+        if self.cursor + record.len() > self.sector_size {
+            return Err(StorageError::Full);
+        }
+
+        let offset = self.sector_offset(self.active) + self.cursor as u32;
+        self.flash
+            .write(offset, record)
+            .map_err(|_| StorageError::Io)?;
+        self.cursor += record.len();
+        Ok(())
+    }
+
+    /// Stop anything being appended to the active sector, which may not be
+    /// erased past the last good record, so the next write compacts first.
+    fn seal(&mut self) {
+        self.cursor = self.sector_size;
+    }
+
+    /// Copy all live entries into the next sector of the ring.
+    fn compact(&mut self) -> Result<(), StorageError> {
+        let next = (self.active + 1) % self.sector_count;
+        self.erase_sector(next)?;
+
+        let base = self.sector_offset(next);
+        let mut cursor = SECTOR_HEADER_LEN;
+        for (key, value) in &self.entries {
+            let record = encode_record(key, Some(value));
+            if cursor + record.len() > self.sector_size {
+                return Err(StorageError::Full);
+            }
+
+            self.flash
+                .write(base + cursor as u32, &record)
+                .map_err(|_| StorageError::Io)?;
+            cursor += record.len();
+        }
+
+        // Only now does the copy take over from the old sector
+        let seq = self.seq.wrapping_add(1);
+        self.write_header(next, seq)?;
+        self.active = next;
+        self.seq = seq;
+        self.cursor = cursor;
+        Ok(())
+    }
+
+    fn format(&mut self) -> Result<(), StorageError> {
+        for sector in 0..self.sector_count {
+            self.erase_sector(sector)?;
+        }
+
+        self.entries.clear();
+        let seq = self.seq.wrapping_add(1);
+        self.write_header(0, seq)?;
+        self.active = 0;
+        self.seq = seq;
+        self.cursor = SECTOR_HEADER_LEN;
+        Ok(())
+    }
+
+    fn write_record(&mut self, record: &[u8]) -> Result<(), StorageError> {
+        match self.append(record) {
+            Err(StorageError::Full) => {
+                self.compact()?;
+                self.append(record)
+            }
+            result => result,
+        }
+    }
+
+    pub fn entries(&self) -> &BTreeMap<String, Vec<u8>> {
+        &self.entries
+    }
+}
+
+impl<F: NorFlash> Storage for FlashStore<F> {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        self.entries.get(key).cloned()
+    }
+
+    fn set(&mut self, key: &str, value: &[u8]) -> Result<(), StorageError> {
+        if key.len() > MAX_KEY_LEN || value.len() > MAX_VALUE_LEN {
+            return Err(StorageError::TooLarge);
+        }
+
+        // Apps save periodically; don't wear the flash with identical data.
+        if self.entries.get(key).is_some_and(|v| v == value) {
+            return Ok(());
+        }
+
+        self.write_record(&encode_record(key, Some(value)))?;
+        self.entries.insert(String::from(key), value.to_vec());
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), StorageError> {
+        if !self.entries.contains_key(key) {
+            return Ok(());
+        }
+
+        self.write_record(&encode_record(key, None))?;
+        self.entries.remove(key);
+        Ok(())
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.entries.keys().cloned().collect()
+    }
+
+    fn clear(&mut self) -> Result<(), StorageError> {
+        self.format()
+    }
+}
@@ -0,0 +1,272 @@
+//! Bold, italic, outlined, rotated and vertical text, for fonts that only
+//! come in one thin upright style.
+
+use alloc::vec::Vec;
+use stroke_font::{FontMetrics, StrokeFont, glyphs, render_text};
+
+use crate::{
+    point::{Path, Point},
+    utils::{math::Affine, text::strokes_to_path},
+};
+
+/// How to draw text on top of its font and scale.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FontStyle {
+    /// Extra copies of each stroke drawn alongside it, to thicken it.
+    pub bold: u8,
+    /// Distance between the copies of a bold stroke, in DAC units.
+    pub bold_spacing: f32,
+    /// Width in DAC units to draw strokes as outlines of, or 0 to draw the
+    /// strokes themselves.
+    pub outline: f32,
+    /// How far the tops of characters lean to the right, as a fraction of
+    /// their height above the baseline.
+    pub italic: f32,
+    /// Radians clockwise around where the text is placed.
+    pub rotation: f32,
+    /// Stack characters top to bottom instead of left to right.
+    pub vertical: bool,
+}
+
+impl Default for FontStyle {
+    fn default() -> Self {
+        Self::PLAIN
+    }
+}
+
+impl FontStyle {
+    pub const PLAIN: FontStyle = FontStyle {
+        bold: 0,
+        bold_spacing: 1.0,
+        outline: 0.0,
+        italic: 0.0,
+        rotation: 0.0,
+        vertical: false,
+    };
+
+    pub fn new() -> Self {
+        Self::PLAIN
+    }
+
+    /// Draw `strokes` extra copies of each stroke, `spacing` DAC units apart.
+    pub fn with_bold(mut self, strokes: u8, spacing: f32) -> Self {
+        self.bold = strokes;
+        self.bold_spacing = spacing;
+        self
+    }
+
+    /// Draw each stroke as the outline of a stroke `width` DAC units wide.
+    pub fn with_outline(mut self, width: f32) -> Self {
+        self.outline = width;
+        self
+    }
+
+    /// Lean characters over, with 0.2 or so looking like a usual italic.
+    pub fn with_italic(mut self, slant: f32) -> Self {
+        self.italic = slant;
+        self
+    }
+
+    pub fn with_rotation(mut self, angle: f32) -> Self {
+        self.rotation = angle;
+        self
+    }
+
+    pub fn with_vertical(mut self, vertical: bool) -> Self {
+        self.vertical = vertical;
+        self
+    }
+
+    /// The italic slant, in glyph units of a font with `metrics`. It leaves
+    /// the baseline where it is.
+    pub fn slant(&self, metrics: &FontMetrics) -> Affine {
+        Affine::shear(-self.italic, 0.0).then(Affine::translate(
+            self.italic * metrics.baseline as f32,
+            0.0,
+        ))
+    }
+
+    /// Pen strokes for `text` in glyph units. Vertical text is centred on
+    /// x = 0, with the top of the first line at y = 0 like horizontal text.
+    pub fn strokes(&self, font: &dyn StrokeFont, text: &str) -> Vec<stroke_font::Point> {
+        if !self.vertical {
+            return render_text(font, text);
+        }
+
+        let line_height = font.metrics().line_height;
+        let mut strokes = Vec::new();
+        for (i, placed) in glyphs(font, text).enumerate() {
+            let (dx, dy) = (-placed.glyph.advance() / 2, i as i16 * line_height);
+            strokes.extend(placed.glyph.points().map(|p| stroke_font::Point {
+                x: p.x.saturating_add(dx),
+                y: p.y.saturating_add(dy),
+                ..p
+            }));
+        }
+        strokes
+    }
+
+    /// Where to draw glyph units of a font with `metrics`, for text that
+    /// `transform` would otherwise place upright. Rotation turns the text
+    /// around `pivot`, in DAC units.
+    pub fn placement(
+        &self,
+        metrics: &FontMetrics,
+        transform: &Affine,
+        pivot: (f32, f32),
+    ) -> Affine {
+        let mut placement = self.slant(metrics).then(*transform);
+        if self.rotation != 0.0 {
+            placement = placement
+                .then(Affine::translate(-pivot.0, -pivot.1))
+                .then(Affine::rotate(self.rotation))
+                .then(Affine::translate(pivot.0, pivot.1));
+        }
+        placement
+    }
+
+    /// Draw `text` placed with `transform` like
+    /// [`strokes_to_path`](crate::utils::text::strokes_to_path), turning it
+    /// around where `transform` puts the glyph origin.
+    pub fn to_path(
+        &self,
+        font: &dyn StrokeFont,
+        text: &str,
+        transform: &Affine,
+        color: (u8, u8, u8),
+    ) -> Path {
+        let placement = self.placement(&font.metrics(), transform, transform.apply(0.0, 0.0));
+        let path = strokes_to_path(&self.strokes(font, text), &placement, |_| color);
+        self.decorate(path)
+    }
+
+    /// Outline `path` and then thicken it, as far as the style says to.
+    pub fn decorate(&self, mut path: Path) -> Path {
+        if self.outline > 0.0 {
+            path = outline(&path, self.outline);
+        }
+        if self.bold > 0 {
+            path = embolden(&path, self.bold, self.bold_spacing);
+        }
+        path
+    }
+}
+
+fn lit(p: &Point) -> bool {
+    p.color != (0, 0, 0)
+}
+
+/// `run[j]` moved `distance` DAC units to the left of the direction the run
+/// is heading there.
+fn offset_point(run: &[Point], j: usize, distance: f32) -> (u8, u8) {
+    let at = |p: &Point| (p.x as f32, p.y as f32);
+    let here = at(&run[j]);
+
+    // Corner dwells repeat points, so look past them for the direction
+    let prev = run[..j].iter().rev().map(at).find(|&p| p != here);
+    let next = run[j + 1..].iter().map(at).find(|&p| p != here);
+    let (from, to) = (prev.unwrap_or(here), next.unwrap_or(here));
+
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length = libm::sqrtf(dx * dx + dy * dy);
+    if length == 0.0 {
+        return (run[j].x, run[j].y);
+    }
+
+    let (nx, ny) = (dy / length, -dx / length);
+    let place = |v: f32| libm::roundf(v).clamp(0.0, 255.0) as u8;
+    (place(here.0 + nx * distance), place(here.1 + ny * distance))
+}
+
+/// The lit runs of `path`, each starting at the point before its first lit
+/// segment, and the blanked points between them on their own.
+fn runs(path: &[Point]) -> impl Iterator<Item = &[Point]> {
+    let mut start = 0;
+    core::iter::from_fn(move || {
+        if start >= path.len() {
+            return None;
+        }
+        let mut end = start + 1;
+        while end < path.len() && lit(&path[end]) {
+            end += 1;
+        }
+        let run = &path[start..end];
+        start = end;
+        Some(run)
+    })
+}
+
+/// Replace the lit parts of `path` with their outlines `width` DAC units
+/// across: along one side, back along the other and closed where it began.
+pub fn outline(path: &[Point], width: f32) -> Path {
+    let mut out = Vec::with_capacity(path.len() * 2 + 1);
+
+    for run in runs(path) {
+        if run.len() < 2 {
+            out.extend_from_slice(run);
+            continue;
+        }
+
+        let half = width / 2.0;
+        let last = run.len() - 1;
+        let at = |j: usize, distance: f32, color, delay| {
+            let (x, y) = offset_point(run, j, distance);
+            Point { x, y, color, delay }
+        };
+
+        out.push(at(0, half, (0, 0, 0), run[0].delay));
+        for (j, p) in run.iter().enumerate().skip(1) {
+            out.push(at(j, half, p.color, p.delay));
+        }
+        // Across the end, then back with each segment's color, then across
+        // the start
+        for j in (0..=last).rev() {
+            let segment = (j + 1).min(last);
+            out.push(at(j, -half, run[segment].color, run[segment].delay));
+        }
+        out.push(at(0, half, run[1].color, run[1].delay));
+    }
+
+    out
+}
+
+/// Thicken the lit parts of `path` by following each one with `strokes`
+/// copies of it, offset `spacing` DAC units at a time to alternate sides.
+/// Every other copy runs backwards, so each starts where the last ended.
+pub fn embolden(path: &[Point], strokes: u8, spacing: f32) -> Path {
+    let mut out = Vec::with_capacity(path.len() * (strokes as usize + 1));
+
+    for run in runs(path) {
+        out.extend_from_slice(run);
+
+        for k in 1..=strokes {
+            if run.len() < 2 {
+                break;
+            }
+
+            let side = if k % 2 == 1 { 1.0 } else { -1.0 };
+            let distance = side * spacing * k.div_ceil(2) as f32;
+            let reverse = k % 2 == 1;
+
+            for n in 0..run.len() {
+                let j = if reverse { run.len() - 1 - n } else { n };
+                // A point's color and delay belong to the segment ending at
+                // it, which is the one after it when going backwards
+                let segment = if reverse {
+                    (j + 1).min(run.len() - 1)
+                } else {
+                    j
+                };
+                let (x, y) = offset_point(run, j, distance);
+                let (color, delay) = if n == 0 {
+                    ((0, 0, 0), run[0].delay)
+                } else {
+                    (run[segment].color, run[segment].delay)
+                };
+                out.push(Point { x, y, color, delay });
+            }
+        }
+    }
+
+    out
+}
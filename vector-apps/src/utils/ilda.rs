@@ -1,4 +1,4 @@
-use core::str;
+use core::fmt;
 
 use alloc::{
     collections::btree_map::BTreeMap,
@@ -6,89 +6,162 @@ use alloc::{
     vec::Vec,
 };
 
-use crate::point::{Path, Point};
+use crate::{
+    apps::VectorApp,
+    point::{Path, Point},
+    utils::scanner::ScannerProfile,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IldaError {
+    /// The file ends partway through the header or record starting at this
+    /// byte offset.
+    Truncated { offset: usize },
+    /// The header at this byte offset doesn't start with `ILDA`.
+    BadMagic { offset: usize },
+    /// The header at this byte offset has a format code other than 0 to 5,
+    /// or the unused 3.
+    BadFormat { offset: usize, format: u8 },
+    /// The record at this byte offset is lit with a color the palette doesn't
+    /// have.
+    BadPaletteIndex { offset: usize, index: u8 },
+    /// A palette to write has no colors, or more than the 256 indices can
+    /// reach.
+    BadPalette { colors: usize },
+    /// A frame to write has more points than a section can hold, or there
+    /// are more frames than a file can number.
+    TooLarge,
+}
+
+impl fmt::Display for IldaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IldaError::Truncated { offset } => {
+                write!(f, "file ends in the section at byte {}", offset)
+            }
+            IldaError::BadMagic { offset } => write!(f, "no ILDA header at byte {}", offset),
+            IldaError::BadFormat { offset, format } => {
+                write!(f, "unknown format {} at byte {}", format, offset)
+            }
+            IldaError::BadPaletteIndex { offset, index } => {
+                write!(
+                    f,
+                    "color {} at byte {} is not in the palette",
+                    index, offset
+                )
+            }
+            IldaError::BadPalette { colors } => {
+                write!(f, "a palette can't have {} colors", colors)
+            }
+            IldaError::TooLarge => write!(f, "too many points or frames for an ILDA file"),
+        }
+    }
+}
+
+impl core::error::Error for IldaError {}
 
 struct Cursor<'a> {
     buf: &'a [u8],
     pos: usize,
+    /// Where the header or record being read started, for errors.
+    start: usize,
 }
 
 impl<'a> Cursor<'a> {
     fn new(buf: &'a [u8]) -> Self {
-        Self { buf, pos: 0 }
-    }
-
-    fn read(&mut self, out: &mut [u8]) {
-        let end = self.pos + out.len();
-        if end > self.buf.len() {
-            panic!();
+        Self {
+            buf,
+            pos: 0,
+            start: 0,
         }
-        out.copy_from_slice(&self.buf[self.pos..end]);
-        self.pos = end;
     }
 
-    fn read_u8(&mut self) -> u8 {
-        if self.pos >= self.buf.len() {
-            panic!();
-        }
-        let v = self.buf[self.pos];
-        self.pos += 1;
-        v
+    /// Note that a new header or record starts here.
+    fn begin(&mut self) {
+        self.start = self.pos;
     }
 
-    fn read_u16_be(&mut self) -> u16 {
-        let end = self.pos + 2;
-        if end > self.buf.len() {
-            panic!();
-        }
-        let v = u16::from_be_bytes(self.buf[self.pos..end].try_into().unwrap());
-        self.pos = end;
-        v
+    fn at_end(&self) -> bool {
+        self.pos >= self.buf.len()
     }
 
-    fn read_i16_be(&mut self) -> i16 {
-        let end = self.pos + 2;
-        if end > self.buf.len() {
-            panic!();
-        }
-        let v = i16::from_be_bytes(self.buf[self.pos..end].try_into().unwrap());
-        self.pos = end;
-        v
+    fn take(&mut self, n: usize) -> Result<&'a [u8], IldaError> {
+        let bytes = self
+            .buf
+            .get(self.pos..self.pos + n)
+            .ok_or(IldaError::Truncated { offset: self.start })?;
+        self.pos += n;
+        Ok(bytes)
     }
 
-    fn read_string(&mut self, chars: usize) -> String {
-        let end = self.pos + chars;
-        if end > self.buf.len() {
-            panic!();
-        }
-        let v = str::from_utf8(&self.buf[self.pos..end])
-            .unwrap()
-            .to_string();
-        self.pos = end;
-        v
+    fn read<const N: usize>(&mut self) -> Result<[u8; N], IldaError> {
+        Ok(self.take(N)?.try_into().unwrap())
     }
+}
 
-    fn skip(&mut self, n: usize) {
-        let end = self.pos + n;
-        if end > self.buf.len() {
-            panic!();
+/// Bytes in a section header after `ILDA`.
+pub(crate) const HEADER_REST_LEN: usize = 28;
+
+pub(crate) struct IldaHeader {
+    pub(crate) format: u8,
+    pub(crate) name: String,
+    pub(crate) author: String,
+    pub(crate) num_records: u16,
+    pub(crate) frame_number: u16,
+    pub(crate) total_frames: u16,
+    pub(crate) projector_number: u8,
+}
+
+/// A name padded with zeros. Any bytes that aren't UTF-8 come out as
+/// U+FFFD, since plenty of files use some other encoding.
+fn decode_name(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+impl IldaHeader {
+    /// The header from the bytes after `ILDA`.
+    pub(crate) fn decode(rest: &[u8; HEADER_REST_LEN]) -> Self {
+        let u16_at = |i: usize| u16::from_be_bytes([rest[i], rest[i + 1]]);
+        Self {
+            format: rest[3],
+            name: decode_name(&rest[4..12]),
+            author: decode_name(&rest[12..20]),
+            num_records: u16_at(20),
+            frame_number: u16_at(22),
+            total_frames: u16_at(24),
+            projector_number: rest[26],
         }
-        self.pos = end;
     }
-}
 
-struct IldaHeader {
-    format: u8,
-    name: String,
-    author: String,
-    num_records: u16,
-    frame_number: u16,
-    total_frames: u16,
-    projector_number: u8,
+    fn write(&self, out: &mut Vec<u8>) {
+        // Cut down to 8 bytes without splitting a character
+        let field = |text: &str| {
+            let mut end = text.len().min(8);
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            let mut bytes = [0; 8];
+            bytes[..end].copy_from_slice(&text.as_bytes()[..end]);
+            bytes
+        };
+
+        out.extend_from_slice(b"ILDA\0\0\0");
+        out.push(self.format);
+        out.extend(field(&self.name));
+        out.extend(field(&self.author));
+        out.extend(self.num_records.to_be_bytes());
+        out.extend(self.frame_number.to_be_bytes());
+        out.extend(self.total_frames.to_be_bytes());
+        out.extend([self.projector_number, 0]);
+    }
 }
 
-const LASTPOINT_BIT: u8 = 0b10000000;
-const BLANKING_BIT: u8 = 0b01000000;
+/// Status bit of the last point of a frame.
+pub const LASTPOINT_BIT: u8 = 0b10000000;
+/// Status bit of a point drawn with the laser off.
+pub const BLANKING_BIT: u8 = 0b01000000;
 
 pub const ILDA_DEFAULT_PALETTE: [(u8, u8, u8); 64] = [
     (255, 0, 0),     //  0 Red
@@ -160,179 +233,656 @@ pub const ILDA_DEFAULT_PALETTE: [(u8, u8, u8); 64] = [
 struct Parser<'a> {
     cur: Cursor<'a>,
     palette: Vec<(u8, u8, u8)>,
-    delay: u16,
 }
 
 impl<'a> Parser<'a> {
-    fn new(data: &'a [u8], kpps: u8) -> Self {
-        let cur = Cursor::new(data);
-
-        let delay = (1000.0 / kpps as f32) as u16;
-
+    fn new(data: &'a [u8]) -> Self {
         Parser {
-            cur,
+            cur: Cursor::new(data),
             palette: ILDA_DEFAULT_PALETTE.to_vec(),
-            delay,
         }
     }
 
-    fn parse_header(&mut self) -> IldaHeader {
-        let mut magic = [0; 4];
-        self.cur.read(&mut magic);
-        if magic != *"ILDA".as_bytes() {
-            panic!();
+    fn parse_header(&mut self) -> Result<IldaHeader, IldaError> {
+        self.cur.begin();
+        let offset = self.cur.pos;
+        if self.cur.read::<4>()? != *b"ILDA" {
+            return Err(IldaError::BadMagic { offset });
+        }
+
+        Ok(IldaHeader::decode(&self.cur.read()?))
+    }
+
+    fn parse_record(&mut self, format: IldaFormat) -> Result<IldaPoint, IldaError> {
+        self.cur.begin();
+        let bytes = self.cur.take(format.record_len())?;
+        decode_record(bytes, format, &self.palette).map_err(|index| IldaError::BadPaletteIndex {
+            offset: self.cur.start,
+            index,
+        })
+    }
+
+    fn parse_record_fmt2(&mut self) -> Result<(u8, u8, u8), IldaError> {
+        self.cur.begin();
+        let [r, g, b] = self.cur.read()?;
+        Ok((r, g, b))
+    }
+
+    fn parse_file(&mut self) -> Result<Vec<IldaFrame>, IldaError> {
+        let mut frames = Vec::new();
+
+        // Plenty of files leave off the empty header that should end them
+        while !self.cur.at_end() {
+            let offset = self.cur.pos;
+            let header = self.parse_header()?;
+
+            if header.num_records == 0 {
+                break;
+            }
+
+            let records = 0..header.num_records;
+            if header.format == 2 {
+                self.palette = records
+                    .map(|_| self.parse_record_fmt2())
+                    .collect::<Result<_, _>>()?;
+                continue;
+            }
+
+            let format = IldaFormat::from_code(header.format).ok_or(IldaError::BadFormat {
+                offset,
+                format: header.format,
+            })?;
+            let points = records
+                .map(|_| self.parse_record(format))
+                .collect::<Result<_, _>>()?;
+
+            frames.push(IldaFrame {
+                format,
+                name: header.name,
+                author: header.author,
+                number: header.frame_number,
+                total: header.total_frames,
+                projector: header.projector_number,
+                points,
+            });
         }
 
-        self.cur.skip(3);
+        Ok(frames)
+    }
+}
 
-        let result = IldaHeader {
-            format: self.cur.read_u8(),
-            name: self.cur.read_string(8),
-            author: self.cur.read_string(8),
-            num_records: self.cur.read_u16_be(),
-            frame_number: self.cur.read_u16_be(),
-            total_frames: self.cur.read_u16_be(),
-            projector_number: self.cur.read_u8(),
+/// Decode a point record of `format`, with colors from `palette`. A lit
+/// point with a color `palette` doesn't have is an error, with its index.
+/// Blanked points can have any index, and come out black if it's missing.
+pub(crate) fn decode_record(
+    bytes: &[u8],
+    format: IldaFormat,
+    palette: &[(u8, u8, u8)],
+) -> Result<IldaPoint, u8> {
+    let i16_at = |i: usize| i16::from_be_bytes([bytes[i], bytes[i + 1]]);
+    let (z, rest) = if format.is_3d() {
+        (i16_at(4), &bytes[6..])
+    } else {
+        (0, &bytes[4..])
+    };
+    let status = rest[0];
+
+    let (index, color) = if format.is_indexed() {
+        let index = rest[1];
+        let color = match palette.get(index as usize) {
+            Some(&color) => color,
+            None if status & BLANKING_BIT != 0 => (0, 0, 0),
+            None => return Err(index),
         };
+        (Some(index), color)
+    } else {
+        // True color records store blue first
+        (None, (rest[3], rest[2], rest[1]))
+    };
+
+    Ok(IldaPoint {
+        x: i16_at(0),
+        y: i16_at(2),
+        z,
+        status,
+        index,
+        color,
+    })
+}
 
-        self.cur.skip(1);
-        result
+/// A point as an ILDA file stores it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct IldaPoint {
+    /// Left to right, from -32768 to 32767.
+    pub x: i16,
+    /// Bottom to top.
+    pub y: i16,
+    /// Towards the viewer, or 0 in a 2D format.
+    pub z: i16,
+    /// [`LASTPOINT_BIT`], [`BLANKING_BIT`] and any other bits as they were.
+    pub status: u8,
+    /// The palette index of a point in an indexed format.
+    pub index: Option<u8>,
+    /// The color the point would be if it weren't blanked.
+    pub color: (u8, u8, u8),
+}
+
+impl IldaPoint {
+    /// A blanked point in the middle, which stands in for an empty frame
+    /// since a header without records marks the end of the file.
+    pub const BLANK: IldaPoint = IldaPoint {
+        x: 0,
+        y: 0,
+        z: 0,
+        status: BLANKING_BIT | LASTPOINT_BIT,
+        index: None,
+        color: (0, 0, 0),
+    };
+
+    pub fn is_blanked(&self) -> bool {
+        self.status & BLANKING_BIT != 0
     }
 
-    fn parse_record_fmt0(&mut self) -> Point {
-        let x = self.cur.read_i16_be();
-        let y = self.cur.read_i16_be();
-        let _z = self.cur.read_i16_be();
-        let status = self.cur.read_u8();
-        let color_idx = self.cur.read_u8() as usize;
+    pub fn is_last(&self) -> bool {
+        self.status & LASTPOINT_BIT != 0
+    }
 
-        Point {
-            x: ((x as i32 + 32768) >> 8) as u8,
-            y: 255 - ((y as i32 + 32768) >> 8) as u8,
-            color: if status & BLANKING_BIT == 0 {
-                *self.palette.get(color_idx).unwrap_or(&(0, 0, 0))
-            } else {
-                (0, 0, 0)
-            },
-            delay: self.delay,
+    /// The color to draw the point, which is black if it's blanked.
+    pub fn lit_color(&self) -> (u8, u8, u8) {
+        if self.is_blanked() {
+            (0, 0, 0)
+        } else {
+            self.color
         }
     }
+}
 
-    fn parse_record_fmt1(&mut self) -> Point {
-        let x = self.cur.read_i16_be();
-        let y = self.cur.read_i16_be();
-        let status = self.cur.read_u8();
-        let color_idx = self.cur.read_u8() as usize;
+/// A frame of an ILDA file at the file's own precision, with what its
+/// header says about it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IldaFrame {
+    /// How the frame was stored.
+    pub format: IldaFormat,
+    pub name: String,
+    pub author: String,
+    /// Position in the animation, counting from 0.
+    pub number: u16,
+    /// Frames in the whole animation.
+    pub total: u16,
+    /// Which projector the frame is for, since a file can hold a show for
+    /// several of them.
+    pub projector: u8,
+    pub points: Vec<IldaPoint>,
+}
 
-        Point {
-            x: ((x as i32 + 32768) >> 8) as u8,
-            y: 255 - ((y as i32 + 32768) >> 8) as u8,
-            color: if status & BLANKING_BIT == 0 {
-                *self.palette.get(color_idx).unwrap_or(&(0, 0, 0))
-            } else {
-                (0, 0, 0)
-            },
-            delay: self.delay,
+/// A DAC coordinate in the middle of the range of ILDA coordinates that
+/// come back as it.
+fn to_ilda(v: u8) -> i16 {
+    (v as i16 - 128) * 256 + 128
+}
+
+fn to_dac(v: i16) -> u8 {
+    ((v as i32 + 32768) >> 8) as u8
+}
+
+impl IldaFrame {
+    /// A 2D true color frame of `path`, with points marked blanked where
+    /// they're black. An empty path is a single [`IldaPoint::BLANK`].
+    pub fn from_path(path: &[Point]) -> Self {
+        let mut points: Vec<IldaPoint> = path
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let mut status = 0;
+                if i == path.len() - 1 {
+                    status |= LASTPOINT_BIT;
+                }
+                if p.color == (0, 0, 0) {
+                    status |= BLANKING_BIT;
+                }
+
+                IldaPoint {
+                    x: to_ilda(p.x),
+                    y: to_ilda(255 - p.y),
+                    z: 0,
+                    status,
+                    index: None,
+                    color: p.color,
+                }
+            })
+            .collect();
+        if points.is_empty() {
+            points.push(IldaPoint::BLANK);
+        }
+
+        Self {
+            format: IldaFormat::TrueColor2d,
+            name: String::new(),
+            author: String::new(),
+            number: 0,
+            total: 1,
+            projector: 0,
+            points,
         }
     }
 
-    fn parse_record_fmt2(&mut self) -> (u8, u8, u8) {
-        (self.cur.read_u8(), self.cur.read_u8(), self.cur.read_u8())
+    /// The frame as DAC coordinates, one point per record, with each drawn
+    /// for `delay` microseconds.
+    pub fn to_path(&self, projection: Projection, delay: u16) -> Path {
+        self.points
+            .iter()
+            .map(|p| {
+                let (x, y) = projection.project(p);
+                Point {
+                    x: to_dac(x),
+                    y: 255 - to_dac(y),
+                    color: p.lit_color(),
+                    delay,
+                }
+            })
+            .collect()
     }
 
-    fn parse_record_fmt4(&mut self) -> Point {
-        let x = self.cur.read_i16_be();
-        let y = self.cur.read_i16_be();
-        let _z = self.cur.read_i16_be();
-        let status = self.cur.read_u8();
-        let red = self.cur.read_u8();
-        let green = self.cur.read_u8();
-        let blue = self.cur.read_u8();
+    /// The frame as DAC coordinates, resampled to draw on the galvos
+    /// `profile` describes rather than the scanner the file was made for.
+    pub fn resample(&self, projection: Projection, profile: &ScannerProfile) -> Path {
+        let targets: Vec<_> = self
+            .points
+            .iter()
+            .map(|p| {
+                let (x, y) = projection.project(p);
+                (to_dac(x) as f32, (255 - to_dac(y)) as f32, p.lit_color())
+            })
+            .collect();
+        profile.resample(&targets)
+    }
+}
 
-        Point {
-            x: ((x as i32 + 32768) >> 8) as u8,
-            y: 255 - ((y as i32 + 32768) >> 8) as u8,
-            color: if status & BLANKING_BIT == 0 {
-                (red, green, blue)
-            } else {
-                (0, 0, 0)
-            },
-            delay: self.delay,
+/// How to flatten the points of 3D frames.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Projection {
+    /// Leave z out, the way a 2D format would.
+    Flat,
+    /// Shrink points towards the middle the further back they are, as seen
+    /// from `distance` ILDA units in front of z = 0.
+    Perspective { distance: f32 },
+}
+
+impl Projection {
+    /// Where `point` lands, in ILDA x and y units.
+    pub fn project(&self, point: &IldaPoint) -> (i16, i16) {
+        match *self {
+            Projection::Flat => (point.x, point.y),
+            Projection::Perspective { distance } => {
+                // Points level with or behind the viewer would turn inside out
+                let scale = distance / (distance - point.z as f32).max(1.0);
+                let place = |v: i16| {
+                    let v = libm::roundf(v as f32 * scale);
+                    v.clamp(i16::MIN as f32, i16::MAX as f32) as i16
+                };
+                (place(point.x), place(point.y))
+            }
+        }
+    }
+}
+
+/// A frame of an ILDA animation, ready to draw.
+#[derive(Clone)]
+pub struct AnimationFrame {
+    pub name: String,
+    pub author: String,
+    /// Position in the animation, counting from 0.
+    pub number: u16,
+    /// Frames in the whole animation.
+    pub total: u16,
+    pub projector: u8,
+    pub path: Path,
+}
+
+/// Every frame of an ILDA file, in the order they're stored.
+#[derive(Clone, Default)]
+pub struct IldaAnimation {
+    pub frames: Vec<AnimationFrame>,
+}
+
+impl IldaAnimation {
+    /// Draw `frames` flattened with `projection`, resampled to `profile`.
+    pub fn from_frames(
+        frames: &[IldaFrame],
+        projection: Projection,
+        profile: &ScannerProfile,
+    ) -> Self {
+        let frames = frames
+            .iter()
+            .map(|f| AnimationFrame {
+                name: f.name.clone(),
+                author: f.author.clone(),
+                number: f.number,
+                total: f.total,
+                projector: f.projector,
+                path: f.resample(projection, profile),
+            })
+            .collect();
+
+        Self { frames }
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// The projectors that frames are for, lowest first.
+    pub fn projectors(&self) -> Vec<u8> {
+        let mut projectors: Vec<u8> = self.frames.iter().map(|f| f.projector).collect();
+        projectors.sort_unstable();
+        projectors.dedup();
+        projectors
+    }
+
+    /// Only the frames for `projector`.
+    pub fn for_projector(&self, projector: u8) -> IldaAnimation {
+        IldaAnimation {
+            frames: self
+                .frames
+                .iter()
+                .filter(|f| f.projector == projector)
+                .cloned()
+                .collect(),
         }
     }
+}
 
-    fn parse_record_fmt5(&mut self) -> Point {
-        let x = self.cur.read_i16_be();
-        let y = self.cur.read_i16_be();
-        let status = self.cur.read_u8();
-        let red = self.cur.read_u8();
-        let green = self.cur.read_u8();
-        let blue = self.cur.read_u8();
+/// Read every frame of an ILDA file, in order, as the file stores them.
+pub fn read_ilda_frames(source: &[u8]) -> Result<Vec<IldaFrame>, IldaError> {
+    Parser::new(source).parse_file()
+}
 
-        Point {
-            x: ((x as i32 + 32768) >> 8) as u8,
-            y: 255 - ((y as i32 + 32768) >> 8) as u8,
-            color: if status & BLANKING_BIT == 0 {
-                (red, green, blue)
-            } else {
-                (0, 0, 0)
-            },
-            delay: self.delay,
+/// Read every frame of an ILDA file, in order, with z left out and
+/// resampled to `profile`.
+pub fn read_ilda_animation(
+    source: &[u8],
+    profile: &ScannerProfile,
+) -> Result<IldaAnimation, IldaError> {
+    let frames = read_ilda_frames(source)?;
+    Ok(IldaAnimation::from_frames(
+        &frames,
+        Projection::Flat,
+        profile,
+    ))
+}
+
+/// Read every frame of an ILDA file, by name. Frames with the same name
+/// as a later one are lost, so use [`read_ilda_animation`] for animations.
+pub fn read_ilda(
+    source: &[u8],
+    profile: &ScannerProfile,
+) -> Result<BTreeMap<String, Path>, IldaError> {
+    let animation = read_ilda_animation(source, profile)?;
+    Ok(animation
+        .frames
+        .into_iter()
+        .map(|f| (f.name, f.path))
+        .collect())
+}
+
+/// How points are stored in an ILDA file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum IldaFormat {
+    /// Format 0: 3D points with palette colors.
+    Indexed3d,
+    /// Format 1: 2D points with palette colors.
+    Indexed2d,
+    /// Format 4: 3D points with 24-bit colors.
+    TrueColor3d,
+    /// Format 5: 2D points with 24-bit colors.
+    TrueColor2d,
+}
+
+impl IldaFormat {
+    /// The format with this code, leaving out palettes.
+    pub fn from_code(code: u8) -> Option<Self> {
+        Some(match code {
+            0 => IldaFormat::Indexed3d,
+            1 => IldaFormat::Indexed2d,
+            4 => IldaFormat::TrueColor3d,
+            5 => IldaFormat::TrueColor2d,
+            _ => return None,
+        })
+    }
+
+    pub fn code(self) -> u8 {
+        match self {
+            IldaFormat::Indexed3d => 0,
+            IldaFormat::Indexed2d => 1,
+            IldaFormat::TrueColor3d => 4,
+            IldaFormat::TrueColor2d => 5,
         }
     }
 
-    fn parse_file(&mut self) -> BTreeMap<String, Path> {
-        let mut paths = BTreeMap::new();
+    /// Bytes in each point record.
+    pub fn record_len(self) -> usize {
+        let coords = if self.is_3d() { 6 } else { 4 };
+        let color = if self.is_indexed() { 1 } else { 3 };
+        coords + 1 + color
+    }
 
-        loop {
-            let header = self.parse_header();
+    pub fn is_indexed(self) -> bool {
+        matches!(self, IldaFormat::Indexed3d | IldaFormat::Indexed2d)
+    }
 
-            if header.num_records == 0 {
-                return paths;
-            }
+    pub fn is_3d(self) -> bool {
+        matches!(self, IldaFormat::Indexed3d | IldaFormat::TrueColor3d)
+    }
+}
 
-            match header.format {
-                0 => {
-                    let path: Path = (0..header.num_records)
-                        .map(|_| self.parse_record_fmt0())
-                        .collect();
+/// The colors points of an indexed format are matched to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IldaPalette {
+    /// [`ILDA_DEFAULT_PALETTE`], which readers already know.
+    Default,
+    /// These colors, written to the file ahead of the frames.
+    Custom(Vec<(u8, u8, u8)>),
+    /// The colors the frames use most, up to 256 of them, written to the file
+    /// ahead of the frames.
+    Generated,
+}
 
-                    paths.insert(header.name, path);
-                }
-                1 => {
-                    let path: Path = (0..header.num_records)
-                        .map(|_| self.parse_record_fmt1())
-                        .collect();
+/// Index of the color in `palette` closest to `color`.
+pub fn nearest_color(palette: &[(u8, u8, u8)], color: (u8, u8, u8)) -> u8 {
+    let distance = |c: &(u8, u8, u8)| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(c.0, color.0) + d(c.1, color.1) + d(c.2, color.2)
+    };
+
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, c)| distance(c))
+        .map_or(0, |(i, _)| i as u8)
+}
 
-                    paths.insert(header.name, path);
-                }
-                2 => {
-                    self.palette = (0..header.num_records)
-                        .map(|_| self.parse_record_fmt2())
-                        .collect();
-                }
-                3 => panic!("invalid format code"),
-                4 => {
-                    let path: Path = (0..header.num_records)
-                        .map(|_| self.parse_record_fmt4())
-                        .collect();
+/// Up to 256 of the lit colors used most in `frames`, most used first.
+fn most_used_colors(frames: &[IldaFrame]) -> Vec<(u8, u8, u8)> {
+    let mut counts: BTreeMap<(u8, u8, u8), usize> = BTreeMap::new();
+    let points = frames.iter().flat_map(|f| &f.points);
+    for p in points.filter(|p| p.lit_color() != (0, 0, 0)) {
+        *counts.entry(p.color).or_default() += 1;
+    }
+
+    let mut colors: Vec<_> = counts.into_iter().collect();
+    colors.sort_by_key(|&(_, count)| core::cmp::Reverse(count));
 
-                    paths.insert(header.name, path);
+    let mut palette: Vec<_> = colors.into_iter().take(256).map(|(c, _)| c).collect();
+    if palette.is_empty() {
+        // Nothing is lit, but an empty palette section would end the file
+        palette.push((255, 255, 255));
+    }
+    palette
+}
+
+/// Builds an ILDA file out of frames, to use in other laser software.
+pub struct IldaWriter {
+    format: IldaFormat,
+    palette: IldaPalette,
+    name: String,
+    author: String,
+    projector: u8,
+    frames: Vec<IldaFrame>,
+}
+
+impl IldaWriter {
+    pub fn new(format: IldaFormat) -> Self {
+        Self {
+            format,
+            palette: IldaPalette::Default,
+            name: String::new(),
+            author: String::new(),
+            projector: 0,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Colors to use for an indexed format.
+    pub fn with_palette(mut self, palette: IldaPalette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Name of the frames added from paths, cut down to 8 bytes.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// The company name field of frames added from paths, cut down to 8
+    /// bytes.
+    pub fn with_author(mut self, author: &str) -> Self {
+        self.author = author.to_string();
+        self
+    }
+
+    pub fn with_projector(mut self, projector: u8) -> Self {
+        self.projector = projector;
+        self
+    }
+
+    pub fn push_frame(&mut self, path: &[Point]) {
+        self.push_ilda_frame(IldaFrame {
+            name: self.name.clone(),
+            author: self.author.clone(),
+            projector: self.projector,
+            number: self.frames.len() as u16,
+            ..IldaFrame::from_path(path)
+        });
+    }
+
+    /// Add a frame as it is, so frames read from a file are written back
+    /// without losing anything the format can hold.
+    pub fn push_ilda_frame(&mut self, frame: IldaFrame) {
+        self.frames.push(frame);
+    }
+
+    /// Add `frames` frames of `app`, asking for them the way a projector
+    /// would from frame 1 on.
+    pub fn record(&mut self, app: &mut dyn VectorApp, frames: u64) {
+        for frame in 1..=frames {
+            let path = app.get_path(frame).clone();
+            self.push_frame(&path);
+        }
+    }
+
+    /// The whole file, ending with an empty header.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, IldaError> {
+        let total = u16::try_from(self.frames.len()).map_err(|_| IldaError::TooLarge)?;
+        let mut out = Vec::new();
+
+        // True color formats have no use for a palette
+        let palette = match &self.palette {
+            IldaPalette::Custom(colors) if self.format.is_indexed() => Some(colors.clone()),
+            IldaPalette::Generated if self.format.is_indexed() => {
+                Some(most_used_colors(&self.frames))
+            }
+            _ => None,
+        };
+        if let Some(colors) = &palette {
+            if colors.is_empty() || colors.len() > 256 {
+                return Err(IldaError::BadPalette {
+                    colors: colors.len(),
+                });
+            }
+
+            let header = IldaHeader {
+                format: 2,
+                name: self.name.clone(),
+                author: self.author.clone(),
+                num_records: colors.len() as u16,
+                frame_number: 0,
+                total_frames: 0,
+                projector_number: self.projector,
+            };
+            header.write(&mut out);
+            for &(r, g, b) in colors {
+                out.extend([r, g, b]);
+            }
+        }
+        let palette = palette.as_deref().unwrap_or(&ILDA_DEFAULT_PALETTE);
+
+        for frame in &self.frames {
+            let points = if frame.points.is_empty() {
+                &[IldaPoint::BLANK][..]
+            } else {
+                &frame.points[..]
+            };
+            let header = IldaHeader {
+                format: self.format.code(),
+                name: frame.name.clone(),
+                author: frame.author.clone(),
+                num_records: u16::try_from(points.len()).map_err(|_| IldaError::TooLarge)?,
+                frame_number: frame.number,
+                total_frames: total,
+                projector_number: frame.projector,
+            };
+            header.write(&mut out);
+
+            for (i, p) in points.iter().enumerate() {
+                out.extend(p.x.to_be_bytes());
+                out.extend(p.y.to_be_bytes());
+                if self.format.is_3d() {
+                    out.extend(p.z.to_be_bytes());
                 }
-                5 => {
-                    let path: Path = (0..header.num_records)
-                        .map(|_| self.parse_record_fmt5())
-                        .collect();
 
-                    paths.insert(header.name, path);
+                let last = i == points.len() - 1;
+                let status = p.status & !LASTPOINT_BIT;
+                out.push(if last { status | LASTPOINT_BIT } else { status });
+
+                if self.format.is_indexed() {
+                    // Keep the original index if it's still the same color
+                    let index = match p.index {
+                        Some(i) if palette.get(i as usize) == Some(&p.color) => i,
+                        _ => nearest_color(palette, p.color),
+                    };
+                    out.push(index);
+                } else {
+                    let (r, g, b) = p.color;
+                    out.extend([b, g, r]);
                 }
-                _ => panic!("invalid format code"),
             }
         }
-    }
-}
 
-pub fn read_ilda(source: &[u8], kpps: u8) -> BTreeMap<String, Path> {
-    let mut parser = Parser::new(source, kpps);
-    parser.parse_file()
+        let end = IldaHeader {
+            format: self.format.code(),
+            name: String::new(),
+            author: String::new(),
+            num_records: 0,
+            frame_number: total,
+            total_frames: total,
+            projector_number: self.projector,
+        };
+        end.write(&mut out);
+        Ok(out)
+    }
 }
@@ -0,0 +1,240 @@
+use core::fmt;
+
+use alloc::vec::Vec;
+use embedded_io::{Read, Seek, SeekFrom};
+
+use crate::utils::ilda::{
+    HEADER_REST_LEN, ILDA_DEFAULT_PALETTE, IldaError, IldaFormat, IldaFrame, IldaHeader,
+    decode_record,
+};
+
+const HEADER_LEN: usize = 4 + HEADER_REST_LEN;
+
+/// Records are read this many bytes at a time, which is a whole number of
+/// records in every format.
+const CHUNK_LEN: usize = 240;
+
+#[derive(Debug)]
+pub enum IldaStreamError<E> {
+    /// The source failed to read or seek.
+    Io(E),
+    /// The file itself is bad.
+    Ilda(IldaError),
+}
+
+impl<E: fmt::Debug> fmt::Display for IldaStreamError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IldaStreamError::Io(e) => write!(f, "reading failed: {:?}", e),
+            IldaStreamError::Ilda(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl<E: fmt::Debug> core::error::Error for IldaStreamError<E> {}
+
+/// A source that counts the bytes read from it, for errors.
+struct Counted<R> {
+    inner: R,
+    offset: u64,
+}
+
+impl<R: Read> Counted<R> {
+    /// Read as much of `buf` as the source has, which is less only at the
+    /// end of the file.
+    fn fill(&mut self, buf: &mut [u8]) -> Result<usize, IldaStreamError<R::Error>> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.inner.read(&mut buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) => return Err(IldaStreamError::Io(e)),
+            }
+        }
+        self.offset += filled as u64;
+        Ok(filled)
+    }
+
+    /// Read `records` records of `len` bytes, passing each to `f` with its
+    /// offset.
+    fn read_records(
+        &mut self,
+        records: u16,
+        len: usize,
+        mut f: impl FnMut(&[u8], usize) -> Result<(), IldaError>,
+    ) -> Result<(), IldaStreamError<R::Error>> {
+        let mut buf = [0; CHUNK_LEN];
+        let mut left = records as usize;
+        while left > 0 {
+            let count = left.min(CHUNK_LEN / len);
+            let start = self.offset as usize;
+            let filled = self.fill(&mut buf[..count * len])?;
+
+            let whole = filled / len;
+            for (i, record) in buf[..whole * len].chunks_exact(len).enumerate() {
+                f(record, start + i * len).map_err(IldaStreamError::Ilda)?;
+            }
+            if whole < count {
+                let offset = start + whole * len;
+                return Err(IldaStreamError::Ilda(IldaError::Truncated { offset }));
+            }
+            left -= count;
+        }
+        Ok(())
+    }
+}
+
+/// Reads an ILDA file a frame at a time, for files too big to hold in
+/// memory. Only the palette and the frame being read are kept.
+pub struct IldaReader<R> {
+    source: Counted<R>,
+    palette: Vec<(u8, u8, u8)>,
+    finished: bool,
+}
+
+impl<R: Read> IldaReader<R> {
+    pub fn new(source: R) -> Self {
+        Self {
+            source: Counted {
+                inner: source,
+                offset: 0,
+            },
+            palette: ILDA_DEFAULT_PALETTE.to_vec(),
+            finished: false,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.source.inner
+    }
+
+    /// The next section header, or `None` at the end of the file. Errors
+    /// end the file too.
+    fn read_header(&mut self) -> Result<Option<(usize, IldaHeader)>, IldaStreamError<R::Error>> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        let offset = self.source.offset as usize;
+        let mut buf = [0; HEADER_LEN];
+        let filled = self.source.fill(&mut buf);
+        let filled = self.check(filled)?;
+        let error = if filled == 0 {
+            // Plenty of files leave off the empty header that should end them
+            None
+        } else if filled >= 4 && buf[..4] != *b"ILDA" {
+            Some(IldaError::BadMagic { offset })
+        } else if filled < HEADER_LEN {
+            Some(IldaError::Truncated { offset })
+        } else {
+            let header = IldaHeader::decode(buf[4..].try_into().unwrap());
+            if header.num_records != 0 {
+                return Ok(Some((offset, header)));
+            }
+            None
+        };
+
+        self.finished = true;
+        match error {
+            Some(e) => Err(IldaStreamError::Ilda(e)),
+            None => Ok(None),
+        }
+    }
+
+    /// The next frame's header, after reading any palettes before it.
+    fn next_header(
+        &mut self,
+    ) -> Result<Option<(IldaFormat, IldaHeader)>, IldaStreamError<R::Error>> {
+        while let Some((offset, header)) = self.read_header()? {
+            if header.format != 2 {
+                let format = IldaFormat::from_code(header.format).ok_or(IldaStreamError::Ilda(
+                    IldaError::BadFormat {
+                        offset,
+                        format: header.format,
+                    },
+                ));
+                return self.check(format.map(|format| Some((format, header))));
+            }
+
+            self.palette.clear();
+            let palette = &mut self.palette;
+            let result = self
+                .source
+                .read_records(header.num_records, 3, |record, _| {
+                    palette.push((record[0], record[1], record[2]));
+                    Ok(())
+                });
+            self.check(result)?;
+        }
+        Ok(None)
+    }
+
+    /// Pass `result` on, ending the file if it's an error.
+    fn check<T>(
+        &mut self,
+        result: Result<T, IldaStreamError<R::Error>>,
+    ) -> Result<T, IldaStreamError<R::Error>> {
+        if result.is_err() {
+            self.finished = true;
+        }
+        result
+    }
+
+    /// Read the next frame into `frame`, reusing its buffers. Gives `false`
+    /// once the file has no more frames, leaving `frame` as it was.
+    pub fn next_frame(&mut self, frame: &mut IldaFrame) -> Result<bool, IldaStreamError<R::Error>> {
+        let Some((format, header)) = self.next_header()? else {
+            return Ok(false);
+        };
+
+        frame.format = format;
+        frame.name = header.name;
+        frame.author = header.author;
+        frame.number = header.frame_number;
+        frame.total = header.total_frames;
+        frame.projector = header.projector_number;
+        frame.points.clear();
+
+        let palette = &self.palette;
+        let points = &mut frame.points;
+        let result =
+            self.source
+                .read_records(header.num_records, format.record_len(), |record, offset| {
+                    let point = decode_record(record, format, palette)
+                        .map_err(|index| IldaError::BadPaletteIndex { offset, index })?;
+                    points.push(point);
+                    Ok(())
+                });
+        self.check(result).map(|_| true)
+    }
+}
+
+impl<R: Read + Seek> IldaReader<R> {
+    /// Skip up to `frames` frames without decoding their points, and give
+    /// how many there were. Palettes along the way are still read.
+    pub fn skip_frames(&mut self, frames: usize) -> Result<usize, IldaStreamError<R::Error>> {
+        let mut skipped = 0;
+        while skipped < frames {
+            let Some((format, header)) = self.next_header()? else {
+                break;
+            };
+
+            let len = header.num_records as u64 * format.record_len() as u64;
+            let result = self.source.inner.seek(SeekFrom::Current(len as i64));
+            self.check(result.map_err(IldaStreamError::Io))?;
+            self.source.offset += len;
+            skipped += 1;
+        }
+        Ok(skipped)
+    }
+
+    /// Go back to the first frame.
+    pub fn rewind(&mut self) -> Result<(), IldaStreamError<R::Error>> {
+        self.source.inner.rewind().map_err(IldaStreamError::Io)?;
+        self.source.offset = 0;
+        self.palette.clear();
+        self.palette.extend_from_slice(&ILDA_DEFAULT_PALETTE);
+        self.finished = false;
+        Ok(())
+    }
+}
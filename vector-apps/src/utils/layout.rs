@@ -0,0 +1,387 @@
+use alloc::{string::String, vec::Vec};
+use stroke_font::{StrokeFont, render_text, text_width};
+
+use crate::{
+    point::Point,
+    utils::{
+        font_style::FontStyle,
+        math::Affine,
+        text::{Line, strokes_to_path},
+        text_animation::TextAnimation,
+    },
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HAlign {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VAlign {
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// An axis-aligned box in DAC units.
+#[derive(Debug, Copy, Clone)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    /// The whole scan field.
+    pub const FULL: Rect = Rect::new(0.0, 0.0, 256.0, 256.0);
+
+    pub const fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+/// Width of a single line of text in glyph units, including kerning.
+/// Characters the font has no glyph for take up no space, like when they're
+/// drawn.
+pub fn measure(font: &dyn StrokeFont, text: &str) -> i16 {
+    text_width(font, text)
+}
+
+/// Greedily break `text` into lines no wider than `max_width` glyph units.
+/// Newlines always start a new line, and words too long to fit on their own
+/// are split between characters. Widths add up in `i32`, so lines of wide
+/// words can't overflow.
+pub fn wrap(font: &dyn StrokeFont, text: &str, max_width: i32) -> Vec<String> {
+    let space = measure(font, " ") as i32;
+    let mut lines = Vec::new();
+
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        let mut width = 0;
+
+        for word in paragraph.split(' ').filter(|w| !w.is_empty()) {
+            let word_width = measure(font, word) as i32;
+
+            if !line.is_empty() && width + space + word_width <= max_width {
+                line.push(' ');
+                line.push_str(word);
+                width += space + word_width;
+                continue;
+            }
+
+            if !line.is_empty() {
+                lines.push(core::mem::take(&mut line));
+                width = 0;
+            }
+
+            if word_width <= max_width {
+                line.push_str(word);
+                width = word_width;
+                continue;
+            }
+
+            // Word is wider than a whole line, break it wherever it overflows
+            for c in word.chars() {
+                let w = font.glyph(c).map_or(0, |g| g.advance() as i32);
+                if !line.is_empty() && width + w > max_width {
+                    lines.push(core::mem::take(&mut line));
+                    width = 0;
+                }
+                line.push(c);
+                width += w;
+            }
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Places text inside a box. Scales are DAC units per glyph unit.
+pub struct TextLayout<'a> {
+    font: &'a dyn StrokeFont,
+    bounds: Rect,
+    h_align: HAlign,
+    v_align: VAlign,
+    x_scale: f32,
+    y_scale: f32,
+    line_spacing: f32,
+    wrap: bool,
+    fit: bool,
+    style: FontStyle,
+}
+
+impl<'a> TextLayout<'a> {
+    pub fn new(font: &'a dyn StrokeFont, bounds: Rect) -> Self {
+        Self {
+            font,
+            bounds,
+            h_align: HAlign::Left,
+            v_align: VAlign::Top,
+            x_scale: 1.0,
+            y_scale: 1.0,
+            line_spacing: 1.0,
+            wrap: false,
+            fit: false,
+            style: FontStyle::PLAIN,
+        }
+    }
+
+    pub fn align(mut self, h_align: HAlign, v_align: VAlign) -> Self {
+        self.h_align = h_align;
+        self.v_align = v_align;
+        self
+    }
+
+    pub fn scale(mut self, x_scale: f32, y_scale: f32) -> Self {
+        self.x_scale = x_scale;
+        self.y_scale = y_scale;
+        self
+    }
+
+    /// Multiplier on the font's line height.
+    pub fn line_spacing(mut self, line_spacing: f32) -> Self {
+        self.line_spacing = line_spacing;
+        self
+    }
+
+    /// Word wrap to the width of the box.
+    pub fn wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    /// Shrink the text, keeping its aspect ratio, until it fits in the box.
+    /// The configured scale becomes the largest allowed.
+    pub fn fit(mut self, fit: bool) -> Self {
+        self.fit = fit;
+        self
+    }
+
+    /// Draw the text in `style`, rotated around the middle of the box. Lines
+    /// are laid out as if upright. Vertical text stacks the characters of
+    /// each line into a column, with the columns running left to right.
+    pub fn style(mut self, style: FontStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Lines of text, or columns of it when it's vertical.
+    fn break_lines(&self, text: &str, x_scale: f32, y_scale: f32) -> Vec<String> {
+        if !self.wrap {
+            return text.split('\n').map(String::from).collect();
+        }
+        if !self.style.vertical {
+            return wrap(self.font, text, (self.bounds.width / x_scale) as i32);
+        }
+
+        // Columns wrap between any two characters
+        let metrics = self.font.metrics();
+        let room = self.bounds.height / y_scale - (metrics.ascent + metrics.descent) as f32;
+        let rows = (room / metrics.line_height as f32) as usize + 1;
+        let mut columns = Vec::new();
+        for paragraph in text.split('\n') {
+            let chars: Vec<char> = paragraph.chars().collect();
+            if chars.is_empty() {
+                columns.push(String::new());
+            }
+            columns.extend(chars.chunks(rows).map(|c| c.iter().collect()));
+        }
+        columns
+    }
+
+    /// Size of the laid out lines in glyph units.
+    fn block_size(&self, lines: &[String]) -> (f32, f32) {
+        let metrics = self.font.metrics();
+        let pitch = metrics.line_height as f32 * self.line_spacing;
+        let count = lines.len().max(1) - 1;
+        if self.style.vertical {
+            let rows = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+            let width = count as f32 * pitch + metrics.line_height as f32;
+            let height = (rows.max(1) - 1) as f32 * metrics.line_height as f32
+                + (metrics.ascent + metrics.descent) as f32;
+            return (width, height);
+        }
+
+        let width = lines
+            .iter()
+            .map(|l| measure(self.font, l))
+            .max()
+            .unwrap_or(0);
+        let height = count as f32 * pitch + (metrics.ascent + metrics.descent) as f32;
+        (width as f32, height)
+    }
+
+    fn fits(&self, lines: &[String], x_scale: f32, y_scale: f32) -> bool {
+        let (width, height) = self.block_size(lines);
+        width * x_scale <= self.bounds.width && height * y_scale <= self.bounds.height
+    }
+
+    /// The scale factors actually used for `text`, after fitting.
+    pub fn fitted_scale(&self, text: &str) -> (f32, f32) {
+        let (sx, sy) = (self.x_scale, self.y_scale);
+        if !self.fit || self.fits(&self.break_lines(text, sx, sy), sx, sy) {
+            return (sx, sy);
+        }
+
+        // Wrapping changes with the scale, so search for the largest factor
+        // that fits rather than solving for it
+        let (mut lo, mut hi) = (0.0, 1.0);
+        for _ in 0..16 {
+            let k = (lo + hi) / 2.0;
+            if self.fits(&self.break_lines(text, sx * k, sy * k), sx * k, sy * k) {
+                lo = k;
+            } else {
+                hi = k;
+            }
+        }
+        (sx * lo, sy * lo)
+    }
+
+    /// The laid out lines of `text`. Vertical text is a line for each
+    /// character.
+    pub fn lines(&self, text: &str) -> Vec<Line> {
+        let metrics = self.font.metrics();
+        let center = (
+            self.bounds.x + self.bounds.width / 2.0,
+            self.bounds.y + self.bounds.height / 2.0,
+        );
+
+        let mut lines = self.upright_lines(text);
+        for line in &mut lines {
+            line.transform = self.style.placement(&metrics, &line.transform, center);
+        }
+        lines
+    }
+
+    fn upright_lines(&self, text: &str) -> Vec<Line> {
+        let metrics = self.font.metrics();
+        let (sx, sy) = self.fitted_scale(text);
+        if sx <= 0.0 || sy <= 0.0 {
+            return Vec::new();
+        }
+
+        let lines = self.break_lines(text, sx, sy);
+        let (width, height) = self.block_size(&lines);
+
+        let top = match self.v_align {
+            VAlign::Top => self.bounds.y,
+            VAlign::Middle => self.bounds.y + (self.bounds.height - height * sy) / 2.0,
+            VAlign::Bottom => self.bounds.y + self.bounds.height - height * sy,
+        };
+        let place = |text: String, x: f32, row: f32, pitch: f32| {
+            let baseline = top + row * pitch + metrics.ascent as f32 * sy;
+            let transform = Affine::scale(sx, sy).then(Affine::translate(
+                x,
+                baseline - metrics.baseline as f32 * sy,
+            ));
+            Line { text, transform }
+        };
+
+        if self.style.vertical {
+            let left = match self.h_align {
+                HAlign::Left => self.bounds.x,
+                HAlign::Center => self.bounds.x + (self.bounds.width - width * sx) / 2.0,
+                HAlign::Right => self.bounds.x + self.bounds.width - width * sx,
+            };
+            let column_pitch = metrics.line_height as f32 * self.line_spacing * sx;
+            let row_pitch = metrics.line_height as f32 * sy;
+
+            // Each character is centred in its column
+            let mut placed = Vec::new();
+            for (i, column) in lines.iter().enumerate() {
+                let middle = left + i as f32 * column_pitch + metrics.line_height as f32 * sx / 2.0;
+                for (row, c) in column.chars().enumerate() {
+                    let text = String::from(c);
+                    let x = middle - measure(self.font, &text) as f32 * sx / 2.0;
+                    placed.push(place(text, x, row as f32, row_pitch));
+                }
+            }
+            return placed;
+        }
+
+        let pitch = metrics.line_height as f32 * self.line_spacing * sy;
+
+        lines
+            .into_iter()
+            .enumerate()
+            .map(|(i, text)| {
+                let width = measure(self.font, &text) as f32 * sx;
+                let x = match self.h_align {
+                    HAlign::Left => self.bounds.x,
+                    HAlign::Center => self.bounds.x + (self.bounds.width - width) / 2.0,
+                    HAlign::Right => self.bounds.x + self.bounds.width - width,
+                };
+                place(text, x, i as f32, pitch)
+            })
+            .collect()
+    }
+
+    /// The box the text will take up, in DAC units, before styling.
+    pub fn bounding_box(&self, text: &str) -> Rect {
+        let metrics = self.font.metrics();
+        let lines = self.upright_lines(text);
+        if lines.is_empty() {
+            return Rect::new(self.bounds.x, self.bounds.y, 0.0, 0.0);
+        }
+
+        let (mut left, mut top) = (f32::MAX, f32::MAX);
+        let (mut right, mut bottom) = (f32::MIN, f32::MIN);
+        for line in &lines {
+            let (x, y) = line
+                .transform
+                .apply(0.0, (metrics.baseline - metrics.ascent) as f32);
+            let (end, below) = line.transform.apply(
+                measure(self.font, &line.text) as f32,
+                (metrics.baseline + metrics.descent) as f32,
+            );
+            left = left.min(x);
+            top = top.min(y);
+            right = right.max(end);
+            bottom = bottom.max(below);
+        }
+
+        Rect::new(left, top, right - left, bottom - top)
+    }
+
+    /// Draw `text` with `color` given the distance drawn so far along each
+    /// line.
+    pub fn to_path_gradient<F>(&self, text: &str, color: F) -> Vec<Point>
+    where
+        F: Fn(f32) -> (u8, u8, u8),
+    {
+        let path = self
+            .lines(text)
+            .iter()
+            .flat_map(|line| {
+                strokes_to_path(&render_text(self.font, &line.text), &line.transform, &color)
+            })
+            .collect();
+        self.style.decorate(path)
+    }
+
+    pub fn to_path(&self, text: &str, color: (u8, u8, u8)) -> Vec<Point> {
+        self.to_path_gradient(text, |_| color)
+    }
+
+    /// Draw `text` with `animation` at `time`.
+    pub fn to_path_animated(
+        &self,
+        text: &str,
+        animation: &TextAnimation,
+        time: f32,
+        color: (u8, u8, u8),
+    ) -> Vec<Point> {
+        let path = animation.lines_to_path(self.font, &self.lines(text), time, color);
+        self.style.decorate(path)
+    }
+}
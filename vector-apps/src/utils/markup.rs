@@ -0,0 +1,359 @@
+//! Inline markup for text with several colors, sizes and fonts, like
+//! `{red}HELLO {size=2}{font=hershey:romans}world`.
+//!
+//! A tag in braces changes the style of the text after it:
+//!
+//! - `{red}`, `{#ff8000}` or `{color=red}` sets the color
+//! - `{size=2}` draws text at twice the size of the base style
+//! - `{font=newstroke}` switches font, see [`font_by_name`] for the names
+//! - `{reset}` or `{/}` goes back to the base style
+//!
+//! `{{` and `}}` stand for literal braces.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use chr_text::Litt;
+use hershey_text::HersheyFont;
+#[cfg(feature = "cjk")]
+use hershey_text::JAPANESE;
+use newstroke_text::Newstroke;
+use stroke_font::{StrokeFont, render_text, text_width};
+
+use crate::{
+    point::Point,
+    utils::{
+        layout::{HAlign, Rect, VAlign},
+        math::Affine,
+        text::{DEFAULT_FONT, UNICODE_FONT, imported_font, strokes_to_path},
+    },
+};
+
+/// Largest size a tag can ask for, so a typo can't blow text up past the
+/// edges of the scan field.
+const MAX_SIZE: f32 = 16.0;
+
+const COLORS: [(&str, (u8, u8, u8)); 10] = [
+    ("white", (255, 255, 255)),
+    ("red", (255, 0, 0)),
+    ("green", (0, 255, 0)),
+    ("blue", (0, 0, 255)),
+    ("yellow", (255, 255, 0)),
+    ("cyan", (0, 255, 255)),
+    ("magenta", (255, 0, 255)),
+    ("orange", (255, 128, 0)),
+    ("purple", (128, 0, 255)),
+    ("pink", (255, 96, 160)),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkupError {
+    /// A `{` at this byte offset has no `}` after it.
+    Unclosed {
+        at: usize,
+    },
+    UnknownTag(String),
+    BadColor(String),
+    BadSize(String),
+    UnknownFont(String),
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkupError::Unclosed { at } => write!(f, "tag at byte {} is never closed", at),
+            MarkupError::UnknownTag(tag) => write!(f, "unknown tag {{{}}}", tag),
+            MarkupError::BadColor(color) => write!(f, "unknown color {:?}", color),
+            MarkupError::BadSize(size) => write!(f, "bad size {:?}", size),
+            MarkupError::UnknownFont(font) => write!(f, "unknown font {:?}", font),
+        }
+    }
+}
+
+impl core::error::Error for MarkupError {}
+
+/// How a run of text is drawn.
+#[derive(Copy, Clone)]
+pub struct TextStyle {
+    pub font: &'static dyn StrokeFont,
+    pub color: (u8, u8, u8),
+    /// Multiplier on the height of capitals, which is the same for every
+    /// font at the same size.
+    pub size: f32,
+}
+
+impl TextStyle {
+    pub const fn new(font: &'static dyn StrokeFont, color: (u8, u8, u8)) -> Self {
+        Self {
+            font,
+            color,
+            size: 1.0,
+        }
+    }
+}
+
+/// Some text drawn in a single style, as returned by [`parse_markup`].
+#[derive(Clone)]
+pub struct TextRun {
+    pub text: String,
+    pub style: TextStyle,
+}
+
+const FONTS: &[(&str, &dyn StrokeFont)] = &[
+    ("default", DEFAULT_FONT),
+    ("unicode", UNICODE_FONT),
+    ("newstroke", &Newstroke),
+    ("chr:litt", &Litt),
+    #[cfg(feature = "cjk")]
+    ("japanese", &JAPANESE),
+];
+
+/// Look up a font for the `font=` tag: `default`, `unicode`, `newstroke`,
+/// `chr:litt`, `japanese` with the `cjk` feature, or `hershey:` followed by
+/// a Hershey font name like `romans`. Fonts dropped into `vector-apps/fonts`
+/// are there too, as `svg:` or `jhf:` and their file name. Names ignore case.
+pub fn font_by_name(name: &str) -> Option<&'static dyn StrokeFont> {
+    if let Some(hershey) = name.get(..8)
+        && hershey.eq_ignore_ascii_case("hershey:")
+    {
+        return HersheyFont::by_name(&name[8..]).map(|f| f as &dyn StrokeFont);
+    }
+
+    FONTS
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, font)| *font)
+        .or_else(|| imported_font(name))
+}
+
+fn parse_color(color: &str) -> Result<(u8, u8, u8), MarkupError> {
+    let bad = || MarkupError::BadColor(color.to_string());
+
+    if let Some(hex) = color.strip_prefix('#') {
+        if hex.len() != 6 {
+            return Err(bad());
+        }
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .ok_or_else(bad)
+        };
+        return Ok((channel(0)?, channel(2)?, channel(4)?));
+    }
+
+    COLORS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(color))
+        .map(|(_, rgb)| *rgb)
+        .ok_or_else(bad)
+}
+
+/// The style after `tag`, the text between a pair of braces.
+fn apply_tag(tag: &str, style: TextStyle, base: &TextStyle) -> Result<TextStyle, MarkupError> {
+    let tag = tag.trim();
+    if tag == "/" || tag.eq_ignore_ascii_case("reset") {
+        return Ok(*base);
+    }
+
+    let Some((key, value)) = tag.split_once('=') else {
+        return match parse_color(tag) {
+            Ok(color) => Ok(TextStyle { color, ..style }),
+            Err(_) => Err(MarkupError::UnknownTag(tag.to_string())),
+        };
+    };
+
+    let value = value.trim();
+    match key.trim() {
+        "color" => Ok(TextStyle {
+            color: parse_color(value)?,
+            ..style
+        }),
+        "size" => match value.parse::<f32>() {
+            Ok(size) if size > 0.0 && size <= MAX_SIZE => Ok(TextStyle {
+                size: base.size * size,
+                ..style
+            }),
+            _ => Err(MarkupError::BadSize(value.to_string())),
+        },
+        "font" => match font_by_name(value) {
+            Some(font) => Ok(TextStyle { font, ..style }),
+            None => Err(MarkupError::UnknownFont(value.to_string())),
+        },
+        _ => Err(MarkupError::UnknownTag(tag.to_string())),
+    }
+}
+
+/// Split `markup` into runs of text, starting in the `base` style. Sizes in
+/// tags are relative to the base size.
+pub fn parse_markup(markup: &str, base: TextStyle) -> Result<Vec<TextRun>, MarkupError> {
+    let mut runs = Vec::new();
+    let mut style = base;
+    let mut text = String::new();
+    let mut rest = markup;
+
+    while let Some(i) = rest.find(['{', '}']) {
+        text.push_str(&rest[..i]);
+        let brace = rest[i..].chars().next().unwrap_or('{');
+        let after = &rest[i + 1..];
+
+        // Doubled braces are escapes, and a lone `}` can only be text
+        if let Some(after) = after.strip_prefix(brace) {
+            text.push(brace);
+            rest = after;
+            continue;
+        }
+        if brace == '}' {
+            text.push(brace);
+            rest = after;
+            continue;
+        }
+
+        let at = markup.len() - rest.len() + i;
+        let end = after.find('}').ok_or(MarkupError::Unclosed { at })?;
+        let next = apply_tag(&after[..end], style, &base)?;
+
+        if !text.is_empty() {
+            runs.push(TextRun {
+                text: core::mem::take(&mut text),
+                style,
+            });
+        }
+        style = next;
+        rest = &after[end + 1..];
+    }
+
+    text.push_str(rest);
+    if !text.is_empty() {
+        runs.push(TextRun { text, style });
+    }
+
+    Ok(runs)
+}
+
+/// Size of one of `style`'s glyph units, where capitals at size 1 are one
+/// unit tall.
+fn glyph_scale(style: &TextStyle) -> f32 {
+    style.size / style.font.metrics().cap_height.max(1) as f32
+}
+
+/// A line of runs, measured with capitals of size 1 one unit tall.
+struct RichLine<'a> {
+    /// Pieces of runs, including empty ones so blank lines keep the height
+    /// of their style.
+    pieces: Vec<(&'a TextStyle, &'a str)>,
+    width: f32,
+    ascent: f32,
+    descent: f32,
+    line_height: f32,
+}
+
+fn split_lines(runs: &[TextRun]) -> Vec<RichLine<'_>> {
+    let mut lines = Vec::new();
+    let mut pieces = Vec::new();
+    for run in runs {
+        let mut parts = run.text.split('\n');
+        if let Some(first) = parts.next() {
+            pieces.push((&run.style, first));
+        }
+        for part in parts {
+            lines.push(core::mem::take(&mut pieces));
+            pieces.push((&run.style, part));
+        }
+    }
+    if !pieces.is_empty() {
+        lines.push(pieces);
+    }
+
+    lines
+        .into_iter()
+        .map(|pieces| {
+            let mut line = RichLine {
+                pieces,
+                width: 0.0,
+                ascent: 0.0,
+                descent: 0.0,
+                line_height: 0.0,
+            };
+            for &(style, text) in &line.pieces {
+                let (metrics, k) = (style.font.metrics(), glyph_scale(style));
+                line.width += text_width(style.font, text) as f32 * k;
+                line.ascent = line.ascent.max(metrics.ascent as f32 * k);
+                line.descent = line.descent.max(metrics.descent as f32 * k);
+                line.line_height = line.line_height.max(metrics.line_height as f32 * k);
+            }
+            line
+        })
+        .collect()
+}
+
+/// Distance from the top of the first line to the baseline of each line,
+/// and the height of the whole block.
+fn baselines(lines: &[RichLine]) -> (Vec<f32>, f32) {
+    let mut baselines = Vec::with_capacity(lines.len());
+    let mut y = lines.first().map_or(0.0, |l| l.ascent);
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            y += line.line_height;
+        }
+        baselines.push(y);
+    }
+    let height = y + lines.last().map_or(0.0, |l| l.descent);
+    (baselines, height)
+}
+
+/// Lay out `runs` in `bounds` and draw them, with capitals `cap_height` DAC
+/// units tall at size 1. Lines break at newlines only, and the text shrinks
+/// if it would not fit otherwise.
+pub fn runs_to_path(
+    runs: &[TextRun],
+    bounds: Rect,
+    h_align: HAlign,
+    v_align: VAlign,
+    cap_height: f32,
+) -> Vec<Point> {
+    let lines = split_lines(runs);
+    let (baselines, height) = baselines(&lines);
+    let width = lines.iter().map(|l| l.width).fold(0.0, f32::max);
+
+    let mut cap = cap_height;
+    if width * cap > bounds.width {
+        cap = bounds.width / width;
+    }
+    if height * cap > bounds.height {
+        cap = bounds.height / height;
+    }
+
+    let top = match v_align {
+        VAlign::Top => bounds.y,
+        VAlign::Middle => bounds.y + (bounds.height - height * cap) / 2.0,
+        VAlign::Bottom => bounds.y + bounds.height - height * cap,
+    };
+
+    let mut points = Vec::new();
+    for (line, baseline) in lines.iter().zip(baselines) {
+        let mut x = match h_align {
+            HAlign::Left => bounds.x,
+            HAlign::Center => bounds.x + (bounds.width - line.width * cap) / 2.0,
+            HAlign::Right => bounds.x + bounds.width - line.width * cap,
+        };
+        let baseline = top + baseline * cap;
+
+        for &(style, text) in &line.pieces {
+            let (metrics, k) = (style.font.metrics(), glyph_scale(style) * cap);
+            let transform = Affine::scale(k, k)
+                .then(Affine::translate(x, baseline - metrics.baseline as f32 * k));
+            let color = style.color;
+            points.append(&mut strokes_to_path(
+                &render_text(style.font, text),
+                &transform,
+                |_| color,
+            ));
+            x += text_width(style.font, text) as f32 * k;
+        }
+    }
+
+    points
+}
@@ -58,3 +58,87 @@ fn wrap(v: f32) -> f32 {
         v
     }
 }
+
+/// A 2D affine transform: `x' = a*x + b*y + tx`, `y' = c*x + d*y + ty`.
+#[derive(Clone, Copy, Debug)]
+pub struct Affine {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub tx: f32,
+    pub ty: f32,
+}
+
+impl Affine {
+    pub const IDENTITY: Affine = Affine {
+        a: 1.0,
+        b: 0.0,
+        c: 0.0,
+        d: 1.0,
+        tx: 0.0,
+        ty: 0.0,
+    };
+
+    pub fn translate(x: f32, y: f32) -> Affine {
+        Affine {
+            tx: x,
+            ty: y,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn scale(sx: f32, sy: f32) -> Affine {
+        Affine {
+            a: sx,
+            d: sy,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Rotate by `angle` radians. With y pointing down, positive angles turn
+    /// clockwise on the display.
+    pub fn rotate(angle: f32) -> Affine {
+        let (s, c) = (libm::sinf(angle), libm::cosf(angle));
+        Affine {
+            a: c,
+            b: -s,
+            c: s,
+            d: c,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Slide x by `kx` times y, and y by `ky` times x.
+    pub fn shear(kx: f32, ky: f32) -> Affine {
+        Affine {
+            b: kx,
+            c: ky,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Apply `self`, then `next`.
+    pub fn then(self, next: Affine) -> Affine {
+        Affine {
+            a: next.a * self.a + next.b * self.c,
+            b: next.a * self.b + next.b * self.d,
+            c: next.c * self.a + next.d * self.c,
+            d: next.c * self.b + next.d * self.d,
+            tx: next.a * self.tx + next.b * self.ty + next.tx,
+            ty: next.c * self.tx + next.d * self.ty + next.ty,
+        }
+    }
+
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            self.a * x + self.b * y + self.tx,
+            self.c * x + self.d * y + self.ty,
+        )
+    }
+
+    /// Apply only the linear part, for directions and distances.
+    pub fn apply_vector(&self, dx: f32, dy: f32) -> (f32, f32) {
+        (self.a * dx + self.b * dy, self.c * dx + self.d * dy)
+    }
+}
@@ -1,4 +1,11 @@
 pub mod colors;
+pub mod font_style;
 pub mod ilda;
+pub mod ilda_stream;
+pub mod layout;
+pub mod markup;
 pub mod math;
+pub mod path_text;
+pub mod scanner;
 pub mod text;
+pub mod text_animation;
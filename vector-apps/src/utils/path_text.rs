@@ -0,0 +1,133 @@
+use alloc::vec::Vec;
+use stroke_font::{PositionedGlyph, StrokeFont, glyphs};
+
+use crate::{
+    point::Point,
+    utils::{
+        layout::{HAlign, measure},
+        math::Affine,
+        text::strokes_to_path,
+    },
+};
+
+/// Points this far apart, in DAC units, approximate arcs.
+const ARC_STEP: f32 = 4.0;
+
+/// A polyline in DAC units that can be sampled by distance along it.
+pub struct Polyline {
+    points: Vec<(f32, f32)>,
+    /// Distance from the first point to each point.
+    lengths: Vec<f32>,
+}
+
+impl Polyline {
+    pub fn new(points: Vec<(f32, f32)>) -> Self {
+        let mut lengths = Vec::with_capacity(points.len());
+        let mut total = 0.0;
+        for (i, &(x, y)) in points.iter().enumerate() {
+            if let Some(&(px, py)) = i.checked_sub(1).map(|p| &points[p]) {
+                total += libm::sqrtf((x - px) * (x - px) + (y - py) * (y - py));
+            }
+            lengths.push(total);
+        }
+
+        Self { points, lengths }
+    }
+
+    /// An arc around `center` from angle `start` to `end` in radians. With y
+    /// pointing down, increasing angles go clockwise, so text runs along the
+    /// outside of the circle when `end > start`.
+    pub fn arc(center: (f32, f32), radius: f32, start: f32, end: f32) -> Self {
+        let segments = libm::ceilf(libm::fabsf(end - start) * radius / ARC_STEP).max(1.0) as usize;
+        let points = (0..=segments)
+            .map(|i| {
+                let a = start + (end - start) * i as f32 / segments as f32;
+                (
+                    center.0 + radius * libm::cosf(a),
+                    center.1 + radius * libm::sinf(a),
+                )
+            })
+            .collect();
+
+        Self::new(points)
+    }
+
+    pub fn length(&self) -> f32 {
+        self.lengths.last().copied().unwrap_or(0.0)
+    }
+
+    /// The point `distance` along the line and the direction of the line
+    /// there, in radians. Distances past either end carry on in a straight
+    /// line.
+    pub fn sample(&self, distance: f32) -> Option<((f32, f32), f32)> {
+        if self.points.len() < 2 {
+            return None;
+        }
+
+        // Index of the segment containing `distance`, skipping zero length
+        // segments so the direction is always defined
+        let i = self
+            .lengths
+            .partition_point(|&l| l <= distance)
+            .clamp(1, self.points.len() - 1);
+        let i = (i..self.points.len())
+            .chain((1..i).rev())
+            .find(|&i| self.lengths[i] > self.lengths[i - 1])?;
+
+        let (x0, y0) = self.points[i - 1];
+        let (x1, y1) = self.points[i];
+        let t = (distance - self.lengths[i - 1]) / (self.lengths[i] - self.lengths[i - 1]);
+
+        Some((
+            (x0 + (x1 - x0) * t, y0 + (y1 - y0) * t),
+            libm::atan2f(y1 - y0, x1 - x0),
+        ))
+    }
+}
+
+/// Draw `text` with its baseline following `line`, each glyph turned to the
+/// direction of the line under its middle. `scale` is DAC units per glyph
+/// unit, and `color` is given the distance along the line.
+pub fn text_along_path<F>(
+    font: &dyn StrokeFont,
+    text: &str,
+    line: &Polyline,
+    align: HAlign,
+    scale: f32,
+    color: F,
+) -> Vec<Point>
+where
+    F: Fn(f32) -> (u8, u8, u8),
+{
+    let width = measure(font, text) as f32 * scale;
+    let start = match align {
+        HAlign::Left => 0.0,
+        HAlign::Center => (line.length() - width) / 2.0,
+        HAlign::Right => line.length() - width,
+    };
+    let baseline = font.metrics().baseline as f32;
+
+    let mut points = Vec::new();
+    // Placed the way `measure` adds them up, kerning and all
+    for PositionedGlyph { x, glyph, .. } in glyphs(font, text) {
+        let pen = start + x as f32 * scale;
+        let advance = glyph.advance() as f32 * scale;
+        let Some((at, angle)) = line.sample(pen + advance / 2.0) else {
+            break;
+        };
+
+        let strokes: Vec<_> = glyph.points().collect();
+
+        // Centre the glyph on its baseline, then stand it on the line
+        let transform = Affine::translate(-glyph.advance() as f32 / 2.0, -baseline)
+            .then(Affine::scale(scale, scale))
+            .then(Affine::rotate(angle))
+            .then(Affine::translate(at.0, at.1));
+
+        points.append(&mut strokes_to_path(&strokes, &transform, |d| {
+            color(pen + d)
+        }));
+    }
+
+    points
+}
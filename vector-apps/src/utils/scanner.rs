@@ -0,0 +1,177 @@
+//! What the galvos can draw, and fitting paths from elsewhere to it.
+
+use crate::point::{Path, Point};
+
+const BLACK: (u8, u8, u8) = (0, 0, 0);
+
+/// Corners turning less than this, as 1 - cos of the angle, are drawn
+/// without stopping.
+const MIN_SHARPNESS: f32 = 0.3;
+
+/// A place to move the beam to, in DAC units, and the color of the line
+/// there.
+type Target = (f32, f32, (u8, u8, u8));
+
+fn distance(a: Target, b: Target) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    libm::sqrtf(dx * dx + dy * dy)
+}
+
+/// How fast the galvos move, which paths made for other scanners are
+/// resampled to.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ScannerProfile {
+    /// Points a second the galvos are driven at.
+    pub points_per_second: u32,
+    /// Furthest the beam moves between points while lit, in DAC units.
+    pub max_draw_step: f32,
+    /// Furthest the beam moves between points while blanked, in DAC units.
+    pub max_travel_step: f32,
+    /// Points nearer than this to the one before, in the same color, are
+    /// dropped.
+    pub min_step: f32,
+    /// Microseconds to wait at a corner that doubles back, for the mirrors
+    /// to catch up. Gentler corners wait less, and the beam turning on or
+    /// off waits the whole time.
+    pub corner_dwell_us: u16,
+}
+
+impl Default for ScannerProfile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScannerProfile {
+    pub fn new() -> Self {
+        Self {
+            points_per_second: 12_000,
+            max_draw_step: 4.0,
+            max_travel_step: 16.0,
+            min_step: 1.0,
+            corner_dwell_us: 250,
+        }
+    }
+
+    /// Draw every point where it's given and nowhere else, at
+    /// `points_per_second`, for paths already made for these galvos.
+    pub const fn as_given(points_per_second: u32) -> Self {
+        Self {
+            points_per_second,
+            max_draw_step: f32::INFINITY,
+            max_travel_step: f32::INFINITY,
+            min_step: 0.0,
+            corner_dwell_us: 0,
+        }
+    }
+
+    pub fn with_points_per_second(mut self, points_per_second: u32) -> Self {
+        self.points_per_second = points_per_second;
+        self
+    }
+
+    /// Move at most `draw` DAC units a point while lit, and `travel` while
+    /// blanked.
+    pub fn with_max_steps(mut self, draw: f32, travel: f32) -> Self {
+        self.max_draw_step = draw;
+        self.max_travel_step = travel;
+        self
+    }
+
+    pub fn with_min_step(mut self, min_step: f32) -> Self {
+        self.min_step = min_step;
+        self
+    }
+
+    pub fn with_corner_dwell(mut self, us: u16) -> Self {
+        self.corner_dwell_us = us;
+        self
+    }
+
+    /// Microseconds each point is drawn for.
+    pub fn point_us(&self) -> u16 {
+        (1_000_000 / self.points_per_second.max(1)).min(u16::MAX as u32) as u16
+    }
+
+    /// Draw `points`, given as DAC coordinates and the color of the line to
+    /// each one, with black for blanked. Long moves get points in between,
+    /// points too close together to matter are dropped, and corners and
+    /// blanking are waited on.
+    pub fn resample(&self, points: &[Target]) -> Path {
+        let point_us = self.point_us();
+        let mut path = Path::new();
+        let Some(&first) = points.first() else {
+            return path;
+        };
+        push(&mut path, first, point_us);
+
+        let mut last = first;
+        for (i, &point) in points.iter().enumerate().skip(1) {
+            let color = point.2;
+            let gap = distance(last, point);
+
+            let dwell = self.dwell(last, point, &points[i + 1..]);
+            let end = i + 1 == points.len();
+            if gap < self.min_step && color == last.2 && dwell == 0 && !end {
+                continue;
+            }
+
+            let max_step = if color == BLACK {
+                self.max_travel_step
+            } else {
+                self.max_draw_step
+            };
+            let steps = libm::ceilf(gap / max_step.max(f32::MIN_POSITIVE)).max(1.0) as usize;
+            for s in 1..steps {
+                let t = s as f32 / steps as f32;
+                let x = last.0 + (point.0 - last.0) * t;
+                let y = last.1 + (point.1 - last.1) * t;
+                push(&mut path, (x, y, color), point_us);
+            }
+            push(&mut path, point, point_us.saturating_add(dwell));
+            last = point;
+        }
+
+        path
+    }
+
+    /// Microseconds to wait at `point`, coming from `last` and going on to
+    /// the first of `rest` far enough away to say which way.
+    fn dwell(&self, last: Target, point: Target, rest: &[Target]) -> u16 {
+        if (last.2 == BLACK) != (point.2 == BLACK) {
+            return self.corner_dwell_us;
+        }
+        if point.2 == BLACK {
+            return 0;
+        }
+
+        let Some(&next) = rest.iter().find(|&&p| distance(point, p) >= self.min_step) else {
+            return 0;
+        };
+        if next.2 == BLACK {
+            return 0;
+        }
+
+        let (ax, ay) = (point.0 - last.0, point.1 - last.1);
+        let (bx, by) = (next.0 - point.0, next.1 - point.1);
+        let (a, b) = (distance(last, point), distance(point, next));
+        if a == 0.0 || b == 0.0 {
+            return 0;
+        }
+
+        let sharpness = 1.0 - ((ax * bx + ay * by) / (a * b)).clamp(-1.0, 1.0);
+        if sharpness < MIN_SHARPNESS {
+            return 0;
+        }
+        (self.corner_dwell_us as f32 * sharpness / 2.0) as u16
+    }
+}
+
+fn push(path: &mut Path, (x, y, color): Target, delay: u16) {
+    path.push(Point {
+        x: libm::roundf(x).clamp(0.0, 255.0) as u8,
+        y: libm::roundf(y).clamp(0.0, 255.0) as u8,
+        color,
+        delay,
+    });
+}
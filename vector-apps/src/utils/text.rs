@@ -1,9 +1,60 @@
-use crate::point::Point;
-// use hershey_text::{FontMapping, render_text};
-// use newstroke_text::render_text;
-use chr_text::render_text;
+use crate::{point::Point, utils::math::Affine};
+use chr_text::Litt;
+#[cfg(feature = "cjk")]
+use hershey_text::JAPANESE;
+use hershey_text::{HersheyFont, fonts};
+use newstroke_text::Newstroke;
+use stroke_font::{FallbackFont, StrokeFont, render_text};
+
+use alloc::{string::String, vec::Vec};
+
+/// Font used by apps that don't pick one themselves. Characters it lacks
+/// show up as a box.
+pub const DEFAULT_FONT: &dyn StrokeFont = &FallbackFont::new(&[&Litt]);
+
+const HERSHEY_ROMAN: HersheyFont<'static> = HersheyFont::new(&fonts::ROMANS);
+/// Greek and Cyrillic by code point only, since their Latin-1 mappings draw
+/// Greek and Cyrillic letters for ASCII.
+const HERSHEY_GREEK: HersheyFont<'static> =
+    HersheyFont::new(&[0; 256]).with_unicode(&fonts::GREEKS_UNICODE);
+const HERSHEY_CYRILLIC: HersheyFont<'static> =
+    HersheyFont::new(&[0; 256]).with_unicode(&fonts::CYRILC_UNICODE);
+
+/// Font for text from outside, like user input and Mastodon posts, covering
+/// as much of Unicode as the font crates can. Hershey shares Newstroke's
+/// metrics, so fills in for it unscaled.
+pub const UNICODE_FONT: &dyn StrokeFont = &FallbackFont::new(&[
+    &Newstroke,
+    &HERSHEY_ROMAN,
+    &HERSHEY_GREEK,
+    &HERSHEY_CYRILLIC,
+    #[cfg(feature = "cjk")]
+    &JAPANESE,
+    &Litt,
+]);
+
+/// Fonts built from the `.svg` and `.jhf` files in `vector-apps/fonts`.
+mod imported {
+    use stroke_font::{FontMetrics, GlyphTable, TableFont};
+
+    include!(concat!(env!("OUT_DIR"), "/imported_fonts.rs"));
+}
+
+/// A font built from a file in `vector-apps/fonts`, named by its type and
+/// file name, like `svg:segment` for `segment.svg`.
+pub fn imported_font(name: &str) -> Option<&'static dyn StrokeFont> {
+    imported::IMPORTED_FONTS
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|&(_, font)| font as &dyn StrokeFont)
+}
 
-use alloc::vec::Vec;
+/// A line of laid out text and where to draw it.
+pub struct Line {
+    pub text: String,
+    /// Maps the line's glyph coordinates to DAC coordinates.
+    pub transform: Affine,
+}
 
 fn map_to_dac(v: f32) -> u8 {
     v.clamp(0.0, 255.0) as u8
@@ -16,9 +67,8 @@ const DT_MIN: u16 = 0; // µs
 const DT_MAX: u16 = 500; // µs
 const CORNER_DWELL_US: u16 = 10; // µs at sharp corners
 
-fn distance(a: &chr_text::Point, b: &chr_text::Point, xs: f32, ys: f32) -> f32 {
-    let dx = (b.x as f32 - a.x as f32) * xs;
-    let dy = (b.y as f32 - a.y as f32) * ys;
+fn distance(a: &stroke_font::Point, b: &stroke_font::Point, transform: &Affine) -> f32 {
+    let (dx, dy) = transform.apply_vector(b.x as f32 - a.x as f32, b.y as f32 - a.y as f32);
     libm::sqrtf(dx * dx + dy * dy)
 }
 
@@ -26,18 +76,21 @@ fn clamp_dt(dt: f32) -> u16 {
     dt.clamp(DT_MIN as f32, DT_MAX as f32) as u16
 }
 
-pub fn text_to_path_gradient<F>(
-    text: &str,
-    x: u8,
-    y: u8,
-    x_scale: f32,
-    y_scale: f32,
+/// Turn pen strokes in glyph units into galvo points, placing them with
+/// `transform`. `color` is given the distance drawn so far.
+pub fn strokes_to_path<F>(
+    strokes: &[stroke_font::Point],
+    transform: &Affine,
     color: F,
 ) -> Vec<Point>
 where
     F: Fn(f32) -> (u8, u8, u8),
 {
-    let strokes = render_text(text);
+    let place = |x: f32, y: f32| {
+        let (px, py) = transform.apply(x, y);
+        (map_to_dac(px), map_to_dac(py))
+    };
+
     let mut points = Vec::new();
 
     if strokes.is_empty() {
@@ -48,9 +101,10 @@ where
 
     // Emit first point
     let first = &strokes[0];
+    let (px, py) = place(first.x as f32, first.y as f32);
     points.push(Point {
-        x: map_to_dac(first.x as f32 * x_scale + x as f32),
-        y: map_to_dac(first.y as f32 * y_scale + y as f32),
+        x: px,
+        y: py,
         color: if first.pen {
             color(cum_dist)
         } else {
@@ -63,7 +117,7 @@ where
         let from = &strokes[i - 1];
         let to = &strokes[i];
 
-        let d = distance(from, to, x_scale, y_scale);
+        let d = distance(from, to, transform);
         if d == 0.0 {
             continue;
         }
@@ -88,9 +142,10 @@ where
 
             cum_dist += step_dist;
 
+            let (px, py) = place(fx, fy);
             points.push(Point {
-                x: map_to_dac(fx * x_scale + x as f32),
-                y: map_to_dac(fy * y_scale + y as f32),
+                x: px,
+                y: py,
                 color: if to.pen { color(cum_dist) } else { (0, 0, 0) },
                 delay: dt,
             });
@@ -114,9 +169,10 @@ where
                 let sharpness = 1.0 - cos_theta;
 
                 if sharpness > 0.3 {
+                    let (px, py) = place(to.x as f32, to.y as f32);
                     points.push(Point {
-                        x: map_to_dac(to.x as f32 * x_scale + x as f32),
-                        y: map_to_dac(to.y as f32 * y_scale + y as f32),
+                        x: px,
+                        y: py,
                         color: color(cum_dist),
                         delay: (CORNER_DWELL_US as f32 * sharpness) as u16,
                     });
@@ -128,7 +184,24 @@ where
     points
 }
 
+pub fn text_to_path_gradient<F>(
+    font: &dyn StrokeFont,
+    text: &str,
+    x: u8,
+    y: u8,
+    x_scale: f32,
+    y_scale: f32,
+    color: F,
+) -> Vec<Point>
+where
+    F: Fn(f32) -> (u8, u8, u8),
+{
+    let transform = Affine::scale(x_scale, y_scale).then(Affine::translate(x as f32, y as f32));
+    strokes_to_path(&render_text(font, text), &transform, color)
+}
+
 pub fn text_to_path(
+    font: &dyn StrokeFont,
     text: &str,
     x: u8,
     y: u8,
@@ -136,5 +209,5 @@ pub fn text_to_path(
     y_scale: f32,
     color: (u8, u8, u8),
 ) -> Vec<Point> {
-    text_to_path_gradient(text, x, y, x_scale, y_scale, |_| color)
+    text_to_path_gradient(font, text, x, y, x_scale, y_scale, |_| color)
 }
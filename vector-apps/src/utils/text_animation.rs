@@ -0,0 +1,198 @@
+use alloc::vec::Vec;
+use core::f32::consts::TAU;
+use stroke_font::{StrokeFont, glyphs};
+
+use crate::{
+    point::Point,
+    utils::{
+        colors::hsl_to_rgb,
+        math::Affine,
+        text::{Line, strokes_to_path},
+    },
+};
+
+/// Something that happens to each character of animated text. Times are in
+/// frames, and sizes in glyph units.
+#[derive(Debug, Copy, Clone)]
+pub enum TextEffect {
+    /// Characters appear one at a time, `rate` characters per frame.
+    Typewriter { rate: f32 },
+    /// Characters bob up and down in a sine wave `wavelength` characters
+    /// long, which moves along at `speed` cycles per frame.
+    Wave {
+        amplitude: f32,
+        wavelength: f32,
+        speed: f32,
+    },
+    /// Each character gets its own hue, `spread` around the colour wheel
+    /// from the one before, turning at `speed` turns per frame.
+    Rainbow { spread: f32, speed: f32 },
+    /// Characters shake up to `amount` in each direction, moving to a new
+    /// spot every `every` frames.
+    Jitter { amount: f32, every: f32 },
+    /// Characters grow from nothing over `duration` frames, each starting
+    /// `stagger` frames after the one before.
+    ScaleIn { duration: f32, stagger: f32 },
+}
+
+/// How one character is drawn at one moment.
+#[derive(Debug, Copy, Clone)]
+pub struct GlyphStyle {
+    /// Applied around the middle of the glyph, in glyph units.
+    pub transform: Affine,
+    /// Replaces the text's color.
+    pub color: Option<(u8, u8, u8)>,
+    pub visible: bool,
+}
+
+impl GlyphStyle {
+    pub const PLAIN: GlyphStyle = GlyphStyle {
+        transform: Affine::IDENTITY,
+        color: None,
+        visible: true,
+    };
+}
+
+/// A number between -1 and 1 that looks random but is always the same for
+/// the same arguments.
+fn noise(index: u32, step: u32, axis: u32) -> f32 {
+    let mut h = index
+        .wrapping_mul(0x9E37_79B9)
+        .wrapping_add(step.wrapping_mul(0x85EB_CA6B))
+        .wrapping_add(axis.wrapping_mul(0xC2B2_AE35));
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7FEB_352D);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846C_A68B);
+    h ^= h >> 16;
+    h as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
+
+impl TextEffect {
+    /// Apply this effect to the style of character number `index` at `time`.
+    pub fn apply(&self, style: &mut GlyphStyle, index: usize, time: f32) {
+        let i = index as f32;
+        match *self {
+            TextEffect::Typewriter { rate } => {
+                if i >= time * rate {
+                    style.visible = false;
+                }
+            }
+            TextEffect::Wave {
+                amplitude,
+                wavelength,
+                speed,
+            } => {
+                let phase = TAU * (i / wavelength - time * speed);
+                style.transform = style
+                    .transform
+                    .then(Affine::translate(0.0, amplitude * libm::sinf(phase)));
+            }
+            TextEffect::Rainbow { spread, speed } => {
+                let hue = libm::fmodf(i * spread + time * speed, 1.0);
+                style.color = Some(hsl_to_rgb(hue, 1.0, 0.5));
+            }
+            TextEffect::Jitter { amount, every } => {
+                let step = libm::floorf(time / every.max(1.0)) as u32;
+                let (dx, dy) = (noise(index as u32, step, 0), noise(index as u32, step, 1));
+                style.transform = style
+                    .transform
+                    .then(Affine::translate(dx * amount, dy * amount));
+            }
+            TextEffect::ScaleIn { duration, stagger } => {
+                let t = ((time - i * stagger) / duration.max(1.0)).clamp(0.0, 1.0);
+                // Ease out, so characters slow down as they reach full size
+                let k = 1.0 - (1.0 - t) * (1.0 - t);
+                if k <= 0.0 {
+                    style.visible = false;
+                }
+                style.transform = style.transform.then(Affine::scale(k, k));
+            }
+        }
+    }
+}
+
+/// Draws text with effects applied to each character.
+#[derive(Debug, Clone, Default)]
+pub struct TextAnimation {
+    effects: Vec<TextEffect>,
+}
+
+impl TextAnimation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an effect, applied after the ones already added.
+    pub fn with(mut self, effect: TextEffect) -> Self {
+        self.effects.push(effect);
+        self
+    }
+
+    pub fn style(&self, index: usize, time: f32) -> GlyphStyle {
+        let mut style = GlyphStyle::PLAIN;
+        for effect in &self.effects {
+            effect.apply(&mut style, index, time);
+        }
+        style
+    }
+
+    /// Draw a line of `text` at `time`, placed with `transform` like
+    /// [`strokes_to_path`]. Characters are numbered from `first_index`, so
+    /// effects can carry on across lines. Returns the points and the number
+    /// of characters drawn.
+    pub fn line_to_path(
+        &self,
+        font: &dyn StrokeFont,
+        text: &str,
+        transform: &Affine,
+        time: f32,
+        first_index: usize,
+        color: (u8, u8, u8),
+    ) -> (Vec<Point>, usize) {
+        let metrics = font.metrics();
+        let middle = (metrics.baseline - metrics.cap_height / 2) as f32;
+
+        let mut points = Vec::new();
+        let mut count = 0;
+        for (i, placed) in glyphs(font, text).enumerate() {
+            count += 1;
+            let style = self.style(first_index + i, time);
+            if !style.visible {
+                continue;
+            }
+
+            let center = placed.x as f32 + placed.glyph.advance() as f32 / 2.0;
+            let strokes: Vec<_> = placed.glyph.points().collect();
+            let glyph_transform = Affine::translate(-placed.glyph.advance() as f32 / 2.0, -middle)
+                .then(style.transform)
+                .then(Affine::translate(center, middle))
+                .then(*transform);
+
+            let color = style.color.unwrap_or(color);
+            points.append(&mut strokes_to_path(&strokes, &glyph_transform, |_| color));
+        }
+
+        (points, count)
+    }
+
+    /// Draw laid out `lines` at `time`, numbering characters continuously
+    /// from one line to the next.
+    pub fn lines_to_path(
+        &self,
+        font: &dyn StrokeFont,
+        lines: &[Line],
+        time: f32,
+        color: (u8, u8, u8),
+    ) -> Vec<Point> {
+        let mut points = Vec::new();
+        let mut index = 0;
+        for line in lines {
+            let (mut line_points, count) =
+                self.line_to_path(font, &line.text, &line.transform, time, index, color);
+            points.append(&mut line_points);
+            index += count;
+        }
+        points
+    }
+}
@@ -0,0 +1,69 @@
+use vector_apps::{
+    apps::{Controls, VectorApp, cycle::Cycle},
+    point::Path,
+    storage::{MemoryStorage, Storage},
+};
+
+struct Blank(Path);
+
+impl VectorApp for Blank {
+    fn get_path(&mut self, _frame: u64) -> &Path {
+        &self.0
+    }
+}
+
+fn cycle(apps: usize) -> Cycle {
+    Cycle::new(
+        (0..apps)
+            .map(|_| Box::new(Blank(Path::new())) as Box<dyn VectorApp>)
+            .collect(),
+    )
+}
+
+/// The index of the app `cycle` is showing, as it saves it.
+fn selected(cycle: &Cycle) -> Option<u32> {
+    let mut storage = MemoryStorage::new();
+    cycle.save_state(&mut storage).unwrap();
+    storage.get_u32("cycle.idx")
+}
+
+#[test]
+fn selects_apps_wrapping_around() {
+    let mut apps = cycle(3);
+    assert_eq!(apps.app_count(), 3);
+    assert_eq!(selected(&apps), Some(0));
+
+    apps.select(2);
+    assert_eq!(selected(&apps), Some(2));
+    apps.select(4);
+    assert_eq!(selected(&apps), Some(1));
+
+    // `b` moves on to the next app, back to the first after the last
+    apps.select(2);
+    apps.handle_controls(Controls {
+        b: true,
+        ..Controls::default()
+    });
+    assert_eq!(selected(&apps), Some(0));
+}
+
+#[test]
+fn restores_the_selected_app() {
+    let mut storage = MemoryStorage::new();
+    storage.set_u32("cycle.idx", 5).unwrap();
+    let mut apps = cycle(3);
+    apps.load_state(&storage);
+    assert_eq!(selected(&apps), Some(2));
+}
+
+#[test]
+fn selects_nothing_without_apps() {
+    let mut apps = cycle(0);
+    assert_eq!(apps.app_count(), 0);
+    apps.select(3);
+
+    let mut storage = MemoryStorage::new();
+    storage.set_u32("cycle.idx", 1).unwrap();
+    apps.load_state(&storage);
+    assert_eq!(selected(&apps), Some(0));
+}
@@ -0,0 +1,156 @@
+use vector_apps::{
+    point::Point,
+    utils::{
+        font_style::{FontStyle, embolden, outline},
+        layout::{HAlign, Rect, TextLayout, VAlign, measure},
+        text::DEFAULT_FONT,
+    },
+};
+
+fn point(x: u8, y: u8, lit: bool) -> Point {
+    Point {
+        x,
+        y,
+        color: if lit { (255, 0, 0) } else { (0, 0, 0) },
+        delay: if lit { 10 } else { 300 },
+    }
+}
+
+fn positions(path: &[Point]) -> Vec<(u8, u8, bool)> {
+    path.iter()
+        .map(|p| (p.x, p.y, p.color != (0, 0, 0)))
+        .collect()
+}
+
+#[test]
+fn emboldens_with_alternating_copies() {
+    let path = [
+        point(10, 10, false),
+        point(20, 10, true),
+        point(30, 10, true),
+    ];
+    assert_eq!(
+        positions(&embolden(&path, 2, 2.0)),
+        [
+            (10, 10, false),
+            (20, 10, true),
+            (30, 10, true),
+            // Back along one side, then forwards along the other
+            (30, 8, false),
+            (20, 8, true),
+            (10, 8, true),
+            (10, 12, false),
+            (20, 12, true),
+            (30, 12, true),
+        ]
+    );
+}
+
+#[test]
+fn leaves_blanked_travel_alone() {
+    let path = [
+        point(0, 0, false),
+        point(5, 5, false),
+        point(5, 9, true),
+        point(50, 50, false),
+    ];
+    let bold = embolden(&path, 1, 1.0);
+    assert_eq!(
+        positions(&bold),
+        [
+            (0, 0, false),
+            (5, 5, false),
+            (5, 9, true),
+            (6, 9, false),
+            (6, 5, true),
+            (50, 50, false),
+        ]
+    );
+    assert_eq!(FontStyle::new().decorate(path.to_vec()).len(), path.len());
+}
+
+#[test]
+fn outlines_strokes() {
+    let path = [
+        point(10, 10, false),
+        point(20, 10, true),
+        point(30, 10, true),
+        point(50, 50, false),
+    ];
+    assert_eq!(
+        positions(&outline(&path, 4.0)),
+        [
+            (10, 8, false),
+            (20, 8, true),
+            (30, 8, true),
+            // Across the end and back along the other side
+            (30, 12, true),
+            (20, 12, true),
+            (10, 12, true),
+            (10, 8, true),
+            (50, 50, false),
+        ]
+    );
+
+    // Outlining comes before bold, which thickens the outline
+    let style = FontStyle::new().with_outline(4.0);
+    let outlined = positions(&outline(&path, 4.0));
+    assert_eq!(positions(&style.decorate(path.to_vec())), outlined);
+    let bold = positions(&style.with_bold(1, 1.0).decorate(path.to_vec()));
+    assert_eq!(bold[..7], outlined[..7]);
+    assert!(bold.len() > 8);
+}
+
+#[test]
+fn lays_out_vertical_text() {
+    let metrics = DEFAULT_FONT.metrics();
+    let layout = TextLayout::new(DEFAULT_FONT, Rect::FULL)
+        .align(HAlign::Left, VAlign::Top)
+        .style(FontStyle::new().with_vertical(true));
+
+    let lines = layout.lines("AW\nI");
+    let texts: Vec<_> = lines.iter().map(|l| l.text.as_str()).collect();
+    assert_eq!(texts, ["A", "W", "I"]);
+
+    // Centred in columns a line height wide, left to right
+    let middle = |i: usize| {
+        let (x, y) = lines[i].transform.apply(0.0, 0.0);
+        (x + measure(DEFAULT_FONT, &lines[i].text) as f32 / 2.0, y)
+    };
+    let height = metrics.line_height as f32;
+    assert_eq!(middle(0).0, height / 2.0);
+    assert_eq!(middle(1), (middle(0).0, middle(0).1 + height));
+    assert_eq!(middle(2), (middle(0).0 + height, middle(0).1));
+
+    let bounds = layout.bounding_box("AW\nI");
+    assert_eq!(bounds.y, 0.0);
+    assert_eq!(
+        bounds.height,
+        height + (metrics.ascent + metrics.descent) as f32
+    );
+    assert!(bounds.width > height && bounds.width < 2.0 * height);
+}
+
+#[test]
+fn fits_vertical_text_to_columns() {
+    // Too tall for the box until it's shrunk
+    let tall = TextLayout::new(DEFAULT_FONT, Rect::new(0.0, 0.0, 64.0, 100.0))
+        .style(FontStyle::new().with_vertical(true))
+        .scale(8.0, 8.0)
+        .fit(true);
+    let (sx, sy) = tall.fitted_scale("ABCDE");
+    assert!(sx < 8.0 && sx == sy);
+    let bounds = tall.bounding_box("ABCDE");
+    assert!(bounds.height <= 100.0 && bounds.height > 90.0);
+
+    // Or wrapped into more columns, three characters tall
+    let wrapped = TextLayout::new(DEFAULT_FONT, Rect::new(0.0, 0.0, 256.0, 100.0))
+        .style(FontStyle::new().with_vertical(true))
+        .scale(4.0, 4.0)
+        .wrap(true);
+    let lines = wrapped.lines("ABCDE");
+    let origin = |i: usize| lines[i].transform.apply(0.0, 0.0);
+    assert_eq!(origin(3).1, origin(0).1);
+    assert!(origin(3).0 > origin(0).0);
+    assert!(!wrapped.to_path("ABCDE", (255, 255, 255)).is_empty());
+}
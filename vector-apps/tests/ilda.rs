@@ -0,0 +1,392 @@
+use vector_apps::{
+    apps::{
+        Controls, VectorApp,
+        ilda::{Ilda, PlaybackMode},
+    },
+    utils::{
+        ilda::{
+            IldaAnimation, IldaError, IldaFormat, IldaPoint, Projection, read_ilda,
+            read_ilda_animation, read_ilda_frames,
+        },
+        scanner::ScannerProfile,
+    },
+};
+
+/// A point per record, each drawn for 100µs.
+const AS_GIVEN: ScannerProfile = ScannerProfile::as_given(10_000);
+
+const TEST_FILE: &[u8] = include_bytes!("../src/apps/ildatest.ild");
+
+/// A section header with `records` records of `format`.
+fn header(format: u8, name: &[u8; 8], records: u16) -> Vec<u8> {
+    frame_header(format, name, records, 0, 1, 0)
+}
+
+fn frame_header(
+    format: u8,
+    name: &[u8; 8],
+    records: u16,
+    number: u16,
+    total: u16,
+    projector: u8,
+) -> Vec<u8> {
+    let mut out = b"ILDA\0\0\0".to_vec();
+    out.push(format);
+    out.extend_from_slice(name);
+    out.extend_from_slice(b"author\0\0");
+    out.extend(records.to_be_bytes());
+    out.extend(number.to_be_bytes());
+    out.extend(total.to_be_bytes());
+    out.extend([projector, 0]);
+    out
+}
+
+/// An animation of `frames` frames, all called `anim`, each a single
+/// point with x = 0x1000 times the frame number.
+fn animation(frames: u16) -> Vec<u8> {
+    let mut file = Vec::new();
+    for number in 0..frames {
+        file.extend(frame_header(5, b"anim\0\0\0\0", 1, number, frames, 0));
+        file.extend((number as i16 * 0x1000).to_be_bytes());
+        file.extend([0, 0, 0, 255, 255, 255]);
+    }
+    file
+}
+
+/// A format 1 record at the centre with a palette color.
+fn indexed_point(status: u8, index: u8) -> [u8; 6] {
+    [0, 0, 0, 0, status, index]
+}
+
+/// Tiny random number generator, so the tests don't need a crate for it.
+struct XorShift(u32);
+
+impl XorShift {
+    fn next(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+}
+
+#[test]
+fn reads_frames() {
+    let mut file = header(1, b"one\0\0\0\0\0", 1);
+    file.extend(indexed_point(0, 24));
+    file.extend(header(5, b"\xffdd\0\0\0\0\0", 1));
+    // Blue first
+    file.extend([0x7f, 0xff, 0x80, 0x00, 0, 3, 2, 1]);
+    file.extend(header(1, b"\0\0\0\0\0\0\0\0", 0));
+
+    let paths = read_ilda(&file, &AS_GIVEN).unwrap();
+    let names: Vec<_> = paths.keys().map(String::as_str).collect();
+    assert_eq!(names, ["one", "\u{fffd}dd"]);
+
+    let one = &paths["one"][0];
+    assert_eq!(
+        (one.x, one.y, one.color, one.delay),
+        (128, 127, (0, 255, 0), 100)
+    );
+    let odd = &paths["\u{fffd}dd"][0];
+    assert_eq!((odd.x, odd.y, odd.color), (255, 255, (1, 2, 3)));
+}
+
+#[test]
+fn keeps_full_precision() {
+    let mut file = header(0, b"depth\0\0\0", 2);
+    file.extend([0x12, 0x34, 0xfe, 0xdc, 0x40, 0x00, 0x01, 5]);
+    // Blanked, with an index past the palette
+    file.extend([0x40, 0x00, 0x40, 0x00, 0xc0, 0x00, 0xc0, 99]);
+
+    let frames = read_ilda_frames(&file).unwrap();
+    assert_eq!(frames[0].format, IldaFormat::Indexed3d);
+    assert_eq!(
+        frames[0].points,
+        [
+            IldaPoint {
+                x: 0x1234,
+                y: -0x124,
+                z: 0x4000,
+                status: 0x01,
+                index: Some(5),
+                color: (255, 80, 0),
+            },
+            IldaPoint {
+                x: 0x4000,
+                y: 0x4000,
+                z: -0x4000,
+                status: 0xc0,
+                index: Some(99),
+                color: (0, 0, 0),
+            },
+        ]
+    );
+    assert!(!frames[0].points[0].is_last());
+    assert!(frames[0].points[1].is_last() && frames[0].points[1].is_blanked());
+
+    let flat = frames[0].to_path(Projection::Flat, 50);
+    let flat: Vec<_> = flat.iter().map(|p| (p.x, p.y, p.color, p.delay)).collect();
+    assert_eq!(
+        flat,
+        [(146, 129, (255, 80, 0), 50), (192, 63, (0, 0, 0), 50)]
+    );
+
+    // Nearer points spread out from the middle, and further ones close in
+    let deep = frames[0].to_path(Projection::Perspective { distance: 32768.0 }, 50);
+    let deep: Vec<_> = deep.iter().map(|p| (p.x, p.y)).collect();
+    assert_eq!(deep, [(164, 130), (170, 85)]);
+}
+
+#[test]
+fn reads_files_without_an_end_header() {
+    let mut file = header(1, b"frame\0\0\0", 1);
+    file.extend(indexed_point(0, 0));
+    assert_eq!(read_ilda(&file, &AS_GIVEN).unwrap().len(), 1);
+    assert!(read_ilda(&[], &AS_GIVEN).unwrap().is_empty());
+}
+
+#[test]
+fn uses_file_palettes() {
+    let mut file = header(2, b"palette\0", 2);
+    file.extend([1, 2, 3, 4, 5, 6]);
+    file.extend(header(0, b"frame\0\0\0", 3));
+    for index in [1, 0] {
+        file.extend([0, 0, 0, 0, 0, 0, 0, index]);
+    }
+    // Blanked points can have any index
+    file.extend([0, 0, 0, 0, 0, 0, 0x40, 200]);
+
+    let paths = read_ilda(&file, &AS_GIVEN).unwrap();
+    let colors: Vec<_> = paths["frame"].iter().map(|p| p.color).collect();
+    assert_eq!(colors, [(4, 5, 6), (1, 2, 3), (0, 0, 0)]);
+
+    // But lit ones have to be in the palette
+    file.extend(header(0, b"frame2\0\0", 1));
+    let offset = file.len();
+    file.extend([0, 0, 0, 0, 0, 0, 0, 2]);
+    assert_eq!(
+        read_ilda(&file, &AS_GIVEN).err(),
+        Some(IldaError::BadPaletteIndex { offset, index: 2 })
+    );
+}
+
+#[test]
+fn reports_bad_files() {
+    let mut file = header(1, b"frame\0\0\0", 2);
+    file.extend(indexed_point(0, 0));
+    file.extend(indexed_point(0, 64));
+    assert_eq!(
+        read_ilda(&file, &AS_GIVEN).err(),
+        Some(IldaError::BadPaletteIndex {
+            offset: 38,
+            index: 64
+        })
+    );
+
+    file.truncate(35);
+    assert_eq!(
+        read_ilda(&file, &AS_GIVEN).err(),
+        Some(IldaError::Truncated { offset: 32 })
+    );
+    file.truncate(20);
+    assert_eq!(
+        read_ilda(&file, &AS_GIVEN).err(),
+        Some(IldaError::Truncated { offset: 0 })
+    );
+
+    let mut file = header(1, b"frame\0\0\0", 1);
+    file.extend(indexed_point(0, 0));
+    file.extend(b"ILDB");
+    assert_eq!(
+        read_ilda(&file, &AS_GIVEN).err(),
+        Some(IldaError::BadMagic { offset: 38 })
+    );
+
+    for format in [3, 6, 255] {
+        assert_eq!(
+            read_ilda(&header(format, b"frame\0\0\0", 1), &AS_GIVEN).err(),
+            Some(IldaError::BadFormat { offset: 0, format })
+        );
+    }
+}
+
+#[test]
+fn survives_truncation() {
+    let full = read_ilda(TEST_FILE, &AS_GIVEN).unwrap();
+
+    for len in 0..TEST_FILE.len() {
+        match read_ilda(&TEST_FILE[..len], &AS_GIVEN) {
+            // Cut between sections, so some frames are missing
+            Ok(paths) => assert!(paths.len() <= full.len()),
+            Err(IldaError::Truncated { offset }) => assert!(offset <= len),
+            Err(e) => panic!("{} bytes: {}", len, e),
+        }
+    }
+}
+
+#[test]
+fn survives_corruption() {
+    let mut rng = XorShift(0x1234_5678);
+
+    for _ in 0..2000 {
+        let mut file = TEST_FILE.to_vec();
+        for _ in 0..1 + rng.next() % 8 {
+            let i = rng.next() as usize % file.len();
+            file[i] = rng.next() as u8;
+        }
+        if rng.next().is_multiple_of(4) {
+            file.truncate(rng.next() as usize % file.len());
+        }
+
+        // Anything goes, as long as it doesn't panic
+        let _ = read_ilda(&file, &AS_GIVEN);
+    }
+}
+
+#[test]
+fn keeps_animation_frames() {
+    let mut file = animation(3);
+    file.extend(frame_header(1, b"other\0\0\0", 1, 0, 1, 2));
+    file.extend(indexed_point(0, 0));
+
+    let animation = read_ilda_animation(&file, &AS_GIVEN).unwrap();
+    let frames: Vec<_> = animation
+        .frames
+        .iter()
+        .map(|f| (f.name.as_str(), f.number, f.total, f.projector, f.path[0].x))
+        .collect();
+    assert_eq!(
+        frames,
+        [
+            ("anim", 0, 3, 0, 128),
+            ("anim", 1, 3, 0, 144),
+            ("anim", 2, 3, 0, 160),
+            ("other", 0, 1, 2, 128),
+        ]
+    );
+    assert_eq!(animation.projectors(), [0, 2]);
+    assert_eq!(animation.for_projector(0).len(), 3);
+
+    // By name, the last frame of the animation wins
+    let paths = read_ilda(&file, &AS_GIVEN).unwrap();
+    assert_eq!(paths["anim"][0].x, 160);
+}
+
+/// The frame shown by each of `n` calls to `get_path`.
+fn play(app: &mut Ilda, n: usize) -> Vec<usize> {
+    (0..n)
+        .map(|i| {
+            app.get_path(i as u64);
+            app.frame()
+        })
+        .collect()
+}
+
+#[test]
+fn plays_animations() {
+    let file = animation(3);
+    // Each frame takes 100µs to draw, so 3500 fps moves on 0.35 frames a call
+    let open = |mode| {
+        Ilda::from_animation(read_ilda_animation(&file, &AS_GIVEN).unwrap())
+            .with_fps(3500.0)
+            .with_mode(mode)
+    };
+
+    let mut app = open(PlaybackMode::Loop);
+    assert_eq!(play(&mut app, 11), [0, 0, 0, 1, 1, 1, 2, 2, 2, 0, 0]);
+
+    let mut app = open(PlaybackMode::PingPong);
+    assert_eq!(
+        play(&mut app, 16),
+        [0, 0, 0, 1, 1, 1, 2, 2, 2, 1, 1, 1, 0, 0, 0, 1]
+    );
+
+    let mut app = open(PlaybackMode::Once);
+    assert_eq!(play(&mut app, 11), [0, 0, 0, 1, 1, 1, 2, 2, 2, 2, 2]);
+
+    let mut app = open(PlaybackMode::Loop).with_speed(2.5);
+    assert_eq!(play(&mut app, 5), [0, 0, 1, 2, 0]);
+}
+
+#[test]
+fn plays_empty_animations() {
+    for mode in [
+        PlaybackMode::Loop,
+        PlaybackMode::PingPong,
+        PlaybackMode::Once,
+    ] {
+        let mut app = Ilda::from_animation(IldaAnimation::default()).with_mode(mode);
+        assert_eq!(play(&mut app, 3), [0, 0, 0]);
+        assert!(app.get_path(3).is_empty());
+    }
+}
+
+#[test]
+fn controls_playback() {
+    let file = animation(3);
+    let mut app =
+        Ilda::from_animation(read_ilda_animation(&file, &AS_GIVEN).unwrap()).with_fps(4000.0);
+    let press = |app: &mut Ilda, controls| {
+        app.handle_controls(controls);
+        app.handle_controls(Controls::default());
+    };
+
+    // Stepping pauses, and wraps around
+    press(
+        &mut app,
+        Controls {
+            x: -1,
+            ..Controls::default()
+        },
+    );
+    assert!(app.is_paused());
+    assert_eq!(play(&mut app, 3), [2, 2, 2]);
+    press(
+        &mut app,
+        Controls {
+            x: 1,
+            ..Controls::default()
+        },
+    );
+    assert_eq!(app.frame(), 0);
+
+    // Holding the stick only steps once
+    app.handle_controls(Controls {
+        x: 1,
+        ..Controls::default()
+    });
+    app.handle_controls(Controls {
+        x: 1,
+        ..Controls::default()
+    });
+    assert_eq!(app.frame(), 1);
+
+    press(
+        &mut app,
+        Controls {
+            a: true,
+            ..Controls::default()
+        },
+    );
+    assert!(!app.is_paused());
+
+    press(
+        &mut app,
+        Controls {
+            y: 1,
+            ..Controls::default()
+        },
+    );
+    assert_eq!(app.speed(), 2.0);
+    for _ in 0..10 {
+        press(
+            &mut app,
+            Controls {
+                y: -1,
+                ..Controls::default()
+            },
+        );
+    }
+    assert_eq!(app.speed(), 0.125);
+}
@@ -0,0 +1,185 @@
+use embedded_io::{ErrorKind, ErrorType, Read, Seek, SeekFrom};
+use vector_apps::{
+    point::Point,
+    utils::{
+        ilda::{IldaError, IldaFormat, IldaFrame, IldaPalette, IldaWriter, read_ilda_frames},
+        ilda_stream::{IldaReader, IldaStreamError},
+    },
+};
+
+const TEST_FILE: &[u8] = include_bytes!("../src/apps/ildatest.ild");
+
+/// A file in memory that hands out at most `chunk` bytes a read, like a
+/// flash driver or USB endpoint, and fails once it gets to `fail_at`.
+struct Storage {
+    data: Vec<u8>,
+    pos: usize,
+    chunk: usize,
+    fail_at: usize,
+}
+
+impl Storage {
+    fn new(data: &[u8]) -> Self {
+        Self {
+            data: data.to_vec(),
+            pos: 0,
+            chunk: 7,
+            fail_at: usize::MAX,
+        }
+    }
+}
+
+impl ErrorType for Storage {
+    type Error = ErrorKind;
+}
+
+impl Read for Storage {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, ErrorKind> {
+        if self.pos >= self.fail_at {
+            return Err(ErrorKind::Other);
+        }
+        let n = buf
+            .len()
+            .min(self.chunk)
+            .min(self.data.len().saturating_sub(self.pos));
+        buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl Seek for Storage {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, ErrorKind> {
+        self.pos = match pos {
+            SeekFrom::Start(n) => n as usize,
+            SeekFrom::End(n) => (self.data.len() as i64 + n) as usize,
+            SeekFrom::Current(n) => (self.pos as i64 + n) as usize,
+        };
+        Ok(self.pos as u64)
+    }
+}
+
+fn point(x: u8, color: (u8, u8, u8)) -> Point {
+    Point {
+        x,
+        y: 0,
+        color,
+        delay: 100,
+    }
+}
+
+/// Frames `0..frames` of `x` points each, in the colors of a palette the
+/// file gives first.
+fn animation(frames: u8) -> Vec<u8> {
+    let palette = vec![(0, 0, 0), (10, 20, 30), (40, 50, 60)];
+    let mut writer =
+        IldaWriter::new(IldaFormat::Indexed2d).with_palette(IldaPalette::Custom(palette));
+    for frame in 0..frames {
+        let path: Vec<_> = (0..frame + 1).map(|x| point(x, (40, 50, 60))).collect();
+        writer.push_frame(&path);
+    }
+    writer.to_bytes().unwrap()
+}
+
+/// Every frame of `file`, or the first error.
+fn read_all(file: &[u8]) -> Result<Vec<IldaFrame>, IldaStreamError<ErrorKind>> {
+    let mut reader = IldaReader::new(Storage::new(file));
+    let mut frames = Vec::new();
+    let mut frame = IldaFrame::from_path(&[]);
+    while reader.next_frame(&mut frame)? {
+        frames.push(frame.clone());
+    }
+    Ok(frames)
+}
+
+#[test]
+fn reads_like_slices() {
+    for file in [TEST_FILE, &animation(4)] {
+        assert_eq!(read_all(file).unwrap(), read_ilda_frames(file).unwrap());
+    }
+
+    let frames = read_all(&animation(4)).unwrap();
+    assert_eq!(frames.len(), 4);
+    assert_eq!(frames[3].points.len(), 4);
+    assert_eq!(frames[3].points[0].color, (40, 50, 60));
+}
+
+#[test]
+fn reuses_the_frame() {
+    let mut reader = IldaReader::new(Storage::new(&animation(3)));
+    let mut frame = IldaFrame::from_path(&[]);
+    frame.points.reserve(16);
+    let buffer = frame.points.as_ptr();
+
+    for number in 0..3 {
+        assert!(reader.next_frame(&mut frame).unwrap());
+        assert_eq!(frame.number, number);
+        assert_eq!(frame.points.as_ptr(), buffer);
+    }
+
+    // The last frame stays once the file runs out
+    assert!(!reader.next_frame(&mut frame).unwrap());
+    assert!(!reader.next_frame(&mut frame).unwrap());
+    assert_eq!(frame.number, 2);
+}
+
+#[test]
+fn skips_and_rewinds() {
+    let mut reader = IldaReader::new(Storage::new(&animation(5)));
+    let mut frame = IldaFrame::from_path(&[]);
+
+    // Skipping still reads the palette first
+    assert_eq!(reader.skip_frames(2).unwrap(), 2);
+    assert!(reader.next_frame(&mut frame).unwrap());
+    assert_eq!((frame.number, frame.points.len()), (2, 3));
+    assert_eq!(frame.points[2].color, (40, 50, 60));
+
+    assert_eq!(reader.skip_frames(10).unwrap(), 2);
+    assert!(!reader.next_frame(&mut frame).unwrap());
+
+    reader.rewind().unwrap();
+    assert!(reader.next_frame(&mut frame).unwrap());
+    assert_eq!(frame.number, 0);
+    assert_eq!(reader.into_inner().pos, 32 + 3 * 3 + 32 + 6);
+}
+
+#[test]
+fn reports_errors_like_slices() {
+    // Every way of cutting a file short fails at the same place
+    for len in 0..TEST_FILE.len() {
+        let file = &TEST_FILE[..len];
+        match (read_all(file), read_ilda_frames(file)) {
+            (Ok(stream), Ok(slice)) => assert_eq!(stream, slice),
+            (Err(IldaStreamError::Ilda(stream)), Err(slice)) => assert_eq!(stream, slice),
+            (stream, slice) => panic!("{} bytes: {:?} {:?}", len, stream.err(), slice.err()),
+        }
+    }
+
+    let mut file = animation(2);
+    file[32 + 9 + 32 + 5] = 9;
+    assert_eq!(
+        read_all(&file).err().unwrap().to_string(),
+        "color 9 at byte 73 is not in the palette"
+    );
+    file[41] = b'X';
+    assert!(matches!(
+        read_all(&file),
+        Err(IldaStreamError::Ilda(IldaError::BadMagic { offset: 41 }))
+    ));
+}
+
+#[test]
+fn passes_on_read_errors() {
+    let mut storage = Storage::new(&animation(3));
+    storage.fail_at = 80;
+    let mut reader = IldaReader::new(storage);
+    let mut frame = IldaFrame::from_path(&[]);
+
+    assert!(reader.next_frame(&mut frame).unwrap());
+    assert!(matches!(
+        reader.next_frame(&mut frame),
+        Err(IldaStreamError::Io(ErrorKind::Other))
+    ));
+    // Where the file got to is lost, so it ends there
+    assert!(!reader.next_frame(&mut frame).unwrap());
+}
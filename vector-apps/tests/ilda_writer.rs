@@ -0,0 +1,250 @@
+use vector_apps::{
+    apps::{VectorApp, cube::CubeDemo},
+    point::{Path, Point},
+    utils::{
+        ilda::{
+            ILDA_DEFAULT_PALETTE, IldaError, IldaFormat, IldaFrame, IldaPalette, IldaPoint,
+            IldaWriter, nearest_color, read_ilda_animation, read_ilda_frames,
+        },
+        scanner::ScannerProfile,
+    },
+};
+
+/// A point per record.
+const AS_GIVEN: ScannerProfile = ScannerProfile::as_given(10_000);
+
+const FORMATS: [IldaFormat; 4] = [
+    IldaFormat::Indexed3d,
+    IldaFormat::Indexed2d,
+    IldaFormat::TrueColor3d,
+    IldaFormat::TrueColor2d,
+];
+
+fn point(x: u8, y: u8, color: (u8, u8, u8)) -> Point {
+    Point {
+        x,
+        y,
+        color,
+        delay: 100,
+    }
+}
+
+/// A triangle in palette colors, starting from a blanked corner.
+fn triangle() -> Path {
+    vec![
+        point(0, 255, (0, 0, 0)),
+        point(255, 255, (255, 0, 0)),
+        point(128, 0, (0, 255, 0)),
+        point(0, 255, (0, 0, 255)),
+    ]
+}
+
+fn summary(path: &[Point]) -> Vec<(u8, u8, (u8, u8, u8))> {
+    path.iter().map(|p| (p.x, p.y, p.color)).collect()
+}
+
+#[test]
+fn round_trips_frames() {
+    let frames = [triangle(), triangle()[1..].to_vec()];
+
+    for format in FORMATS {
+        let mut writer = IldaWriter::new(format)
+            .with_name("triangle")
+            .with_author("galvo")
+            .with_projector(3);
+        for frame in &frames {
+            writer.push_frame(frame);
+        }
+
+        let file = writer.to_bytes().unwrap();
+        assert_eq!(file[7], format.code());
+
+        let animation = read_ilda_animation(&file, &AS_GIVEN).unwrap();
+        assert_eq!(animation.len(), 2);
+        for (read, written) in animation.frames.iter().zip(&frames) {
+            assert_eq!(read.name, "triangle");
+            assert_eq!(read.author, "galvo");
+            assert_eq!(read.projector, 3);
+            assert_eq!(read.total, 2);
+            assert_eq!(summary(&read.path), summary(written), "{:?}", format);
+        }
+        assert_eq!(animation.frames[1].number, 1);
+    }
+}
+
+#[test]
+fn keeps_frames_after_empty_ones() {
+    for format in FORMATS {
+        let mut writer = IldaWriter::new(format);
+        writer.push_frame(&triangle());
+        writer.push_frame(&[]);
+        writer.push_frame(&triangle());
+
+        // An empty frame is a blanked point, not the end of the file
+        let animation = read_ilda_animation(&writer.to_bytes().unwrap(), &AS_GIVEN).unwrap();
+        assert_eq!(animation.len(), 3, "{:?}", format);
+        assert_eq!(summary(&animation.frames[1].path), [(128, 127, (0, 0, 0))]);
+        assert_eq!(
+            summary(&animation.frames[2].path),
+            summary(&triangle()),
+            "{:?}",
+            format
+        );
+    }
+}
+
+#[test]
+fn keeps_frame_numbers() {
+    let empty = IldaFrame::from_path(&[]);
+    assert_eq!(empty.points, [IldaPoint::BLANK]);
+
+    let mut writer = IldaWriter::new(IldaFormat::TrueColor2d);
+    writer.push_ilda_frame(IldaFrame {
+        number: 7,
+        ..empty.clone()
+    });
+    writer.push_frame(&triangle());
+    let frames = read_ilda_frames(&writer.to_bytes().unwrap()).unwrap();
+    assert_eq!(frames[0].number, 7);
+    assert_eq!(frames[0].points, empty.points);
+    // Frames from paths are numbered in order
+    assert_eq!(frames[1].number, 1);
+}
+
+#[test]
+fn marks_last_points() {
+    let mut writer = IldaWriter::new(IldaFormat::TrueColor2d);
+    writer.push_frame(&triangle());
+    let file = writer.to_bytes().unwrap();
+
+    // Four 8 byte records after the header, then the empty end header
+    let statuses: Vec<u8> = (0..4).map(|i| file[32 + i * 8 + 4]).collect();
+    assert_eq!(statuses, [0x40, 0, 0, 0x80]);
+    assert_eq!(&file[file.len() - 32..file.len() - 28], b"ILDA");
+    assert_eq!(file[file.len() - 8..file.len() - 6], [0, 0]);
+
+    // Colors are stored blue first
+    assert_eq!(file[32 + 8 + 5..32 + 8 + 8], [0, 0, 255]);
+}
+
+#[test]
+fn matches_palette_colors() {
+    assert_eq!(nearest_color(&ILDA_DEFAULT_PALETTE, (250, 0, 5)), 0);
+    assert_eq!(nearest_color(&ILDA_DEFAULT_PALETTE, (0, 250, 250)), 31);
+    assert_eq!(
+        nearest_color(&[(0, 0, 0), (100, 100, 100)], (60, 60, 60)),
+        1
+    );
+
+    // Off-palette colors come back as the nearest one
+    let mut writer = IldaWriter::new(IldaFormat::Indexed2d);
+    writer.push_frame(&[point(1, 2, (250, 126, 10))]);
+    let animation = read_ilda_animation(&writer.to_bytes().unwrap(), &AS_GIVEN).unwrap();
+    assert_eq!(animation.frames[0].path[0].color, (255, 128, 0));
+}
+
+#[test]
+fn writes_palettes() {
+    let orange = (250, 120, 10);
+    let mut path = vec![point(0, 0, orange); 3];
+    path.push(point(0, 0, (1, 2, 3)));
+
+    let mut writer = IldaWriter::new(IldaFormat::Indexed3d).with_palette(IldaPalette::Generated);
+    writer.push_frame(&path);
+    let file = writer.to_bytes().unwrap();
+
+    // Most used first
+    assert_eq!(file[7], 2);
+    assert_eq!(file[24..26], [0, 2]);
+    assert_eq!(file[32..38], [250, 120, 10, 1, 2, 3]);
+
+    let animation = read_ilda_animation(&file, &AS_GIVEN).unwrap();
+    let colors: Vec<_> = animation.frames[0].path.iter().map(|p| p.color).collect();
+    assert_eq!(colors, [orange, orange, orange, (1, 2, 3)]);
+
+    // Blank frames still get a palette, since an empty one would end the file
+    let mut writer = IldaWriter::new(IldaFormat::Indexed2d).with_palette(IldaPalette::Generated);
+    writer.push_frame(&[point(0, 0, (0, 0, 0))]);
+    let file = writer.to_bytes().unwrap();
+    assert_eq!(read_ilda_animation(&file, &AS_GIVEN).unwrap().len(), 1);
+
+    // True color formats leave the palette out
+    let mut writer =
+        IldaWriter::new(IldaFormat::TrueColor2d).with_palette(IldaPalette::Custom(vec![(1, 2, 3)]));
+    writer.push_frame(&path);
+    assert_eq!(writer.to_bytes().unwrap()[7], 5);
+}
+
+#[test]
+fn reports_impossible_files() {
+    let bad_palette = |colors: usize| {
+        let mut writer = IldaWriter::new(IldaFormat::Indexed2d)
+            .with_palette(IldaPalette::Custom(vec![(1, 1, 1); colors]));
+        writer.push_frame(&triangle());
+        writer.to_bytes().err()
+    };
+    assert_eq!(bad_palette(0), Some(IldaError::BadPalette { colors: 0 }));
+    assert_eq!(
+        bad_palette(257),
+        Some(IldaError::BadPalette { colors: 257 })
+    );
+    assert!(bad_palette(256).is_none());
+
+    let mut writer = IldaWriter::new(IldaFormat::TrueColor2d);
+    writer.push_frame(&vec![point(0, 0, (0, 0, 0)); 70_000]);
+    assert_eq!(writer.to_bytes().err(), Some(IldaError::TooLarge));
+}
+
+#[test]
+fn cuts_long_names() {
+    let mut writer = IldaWriter::new(IldaFormat::TrueColor2d).with_name("abcdefg\u{e9}");
+    writer.push_frame(&triangle());
+    let animation = read_ilda_animation(&writer.to_bytes().unwrap(), &AS_GIVEN).unwrap();
+    assert_eq!(animation.frames[0].name, "abcdefg");
+}
+
+#[test]
+fn records_apps() {
+    let mut writer = IldaWriter::new(IldaFormat::TrueColor3d).with_name("cube");
+    writer.record(&mut CubeDemo::new(), 5);
+    let animation = read_ilda_animation(&writer.to_bytes().unwrap(), &AS_GIVEN).unwrap();
+    assert_eq!(animation.len(), 5);
+
+    let mut cube = CubeDemo::new();
+    for (frame, recorded) in (1..).zip(&animation.frames) {
+        assert_eq!(summary(&recorded.path), summary(cube.get_path(frame)));
+    }
+}
+
+#[test]
+fn rewrites_files_losslessly() {
+    let original = include_bytes!("../src/apps/ildatest.ild");
+    let frames = read_ilda_frames(original).unwrap();
+    assert_eq!(frames[0].format, IldaFormat::Indexed3d);
+
+    let mut writer = IldaWriter::new(IldaFormat::Indexed3d);
+    for frame in &frames {
+        writer.push_ilda_frame(frame.clone());
+    }
+    let file = writer.to_bytes().unwrap();
+
+    // Everything but the end header, whose name fields are padded with spaces
+    let end = original.len() - 32;
+    assert_eq!(file.len(), original.len());
+    assert_eq!(file[..end], original[..end]);
+    assert_eq!(read_ilda_frames(&file).unwrap(), frames);
+
+    // Converting keeps the coordinates and colors
+    let mut writer = IldaWriter::new(IldaFormat::TrueColor3d);
+    for frame in &frames {
+        writer.push_ilda_frame(frame.clone());
+    }
+    let converted = read_ilda_frames(&writer.to_bytes().unwrap()).unwrap();
+    for (a, b) in converted[0].points.iter().zip(&frames[0].points) {
+        assert_eq!(
+            (a.x, a.y, a.z, a.status, a.color),
+            (b.x, b.y, b.z, b.status, b.color)
+        );
+        assert_eq!(a.index, None);
+    }
+}
@@ -0,0 +1,119 @@
+use vector_apps::utils::{
+    layout::{HAlign, Rect, TextLayout, VAlign, measure, wrap},
+    text::DEFAULT_FONT,
+};
+
+fn texts(layout: &TextLayout, text: &str) -> Vec<String> {
+    layout.lines(text).into_iter().map(|l| l.text).collect()
+}
+
+/// Where the top left of line `i` of `text` goes, in DAC units.
+fn line_origin(layout: &TextLayout, text: &str, i: usize) -> (f32, f32) {
+    let metrics = DEFAULT_FONT.metrics();
+    layout.lines(text)[i]
+        .transform
+        .apply(0.0, (metrics.baseline - metrics.ascent) as f32)
+}
+
+#[test]
+fn breaks_long_words_between_characters() {
+    // Letters are 6 units wide, so 5 fit on a 30 unit line
+    assert_eq!(measure(DEFAULT_FONT, "ABCDE"), 30);
+    assert_eq!(
+        wrap(DEFAULT_FONT, "HE ABCDEFGH", 30),
+        ["HE", "ABCDE", "FGH"]
+    );
+
+    let layout = TextLayout::new(DEFAULT_FONT, Rect::new(0.0, 0.0, 30.0, 256.0)).wrap(true);
+    assert_eq!(texts(&layout, "HE ABCDEFGH"), ["HE", "ABCDE", "FGH"]);
+    // Words that fit are kept together
+    assert_eq!(texts(&layout, "AB CD EF"), ["AB CD", "EF"]);
+}
+
+#[test]
+fn wraps_lines_wider_than_i16() {
+    let wide = "X".repeat(6000);
+    let text = format!("{} {}", wide, wide);
+    assert_eq!(wrap(DEFAULT_FONT, &text, i32::MAX), [text.as_str()]);
+
+    // Scaled so small that the box is wider than an `i16` reaches
+    let layout = TextLayout::new(DEFAULT_FONT, Rect::new(0.0, 0.0, 256.0, 256.0))
+        .scale(0.001, 0.001)
+        .wrap(true);
+    assert_eq!(texts(&layout, &text), [text]);
+}
+
+#[test]
+fn starts_lines_at_newlines() {
+    let layout = TextLayout::new(DEFAULT_FONT, Rect::FULL).line_spacing(1.5);
+    assert_eq!(texts(&layout, "AB\nC"), ["AB", "C"]);
+    assert_eq!(texts(&layout.wrap(true), "AB\n\nC"), ["AB", "", "C"]);
+
+    let layout = TextLayout::new(DEFAULT_FONT, Rect::FULL).line_spacing(1.5);
+    let (x0, y0) = line_origin(&layout, "AB\nC", 0);
+    let (x1, y1) = line_origin(&layout, "AB\nC", 1);
+    assert_eq!((x1, y1 - y0), (x0, 12.0));
+}
+
+#[test]
+fn aligns_in_the_box() {
+    let bounds = Rect::new(10.0, 20.0, 100.0, 50.0);
+    // 12 units wide and 8 tall
+    let at = |h, v| {
+        let layout = TextLayout::new(DEFAULT_FONT, bounds).align(h, v);
+        line_origin(&layout, "AB", 0)
+    };
+
+    assert_eq!(at(HAlign::Left, VAlign::Top), (10.0, 20.0));
+    assert_eq!(at(HAlign::Center, VAlign::Top).0, 54.0);
+    assert_eq!(at(HAlign::Right, VAlign::Top).0, 98.0);
+    assert_eq!(at(HAlign::Left, VAlign::Middle).1, 41.0);
+    assert_eq!(at(HAlign::Left, VAlign::Bottom).1, 62.0);
+
+    // Lines are aligned one by one
+    let layout = TextLayout::new(DEFAULT_FONT, bounds).align(HAlign::Right, VAlign::Top);
+    assert_eq!(line_origin(&layout, "AB\nABCD", 1).0, 86.0);
+}
+
+#[test]
+fn fits_text_to_the_box() {
+    let bounds = Rect::new(0.0, 0.0, 60.0, 100.0);
+    let layout = TextLayout::new(DEFAULT_FONT, bounds)
+        .scale(10.0, 5.0)
+        .fit(true);
+
+    // 30 units wide shrinks to 60, keeping the aspect ratio
+    let (sx, sy) = layout.fitted_scale("HELLO");
+    assert!((sx - 2.0).abs() < 0.01, "{}", sx);
+    assert!((sy - 1.0).abs() < 0.01, "{}", sy);
+    assert!(layout.bounding_box("HELLO").width <= 60.0);
+
+    // Text that already fits keeps its scale
+    assert_eq!(layout.fitted_scale("A"), (10.0, 5.0));
+    // And without fitting, text can spill out
+    assert_eq!(layout.fit(false).fitted_scale("HELLO"), (10.0, 5.0));
+}
+
+#[test]
+fn measures_the_laid_out_text() {
+    let layout =
+        || TextLayout::new(DEFAULT_FONT, Rect::new(10.0, 20.0, 200.0, 200.0)).scale(2.0, 2.0);
+
+    // Two lines 8 units apart, each 6 above and 2 below the baseline
+    let bounds = layout().bounding_box("AB\nHELLO");
+    assert_eq!(
+        (bounds.x, bounds.y, bounds.width, bounds.height),
+        (10.0, 20.0, 60.0, 32.0)
+    );
+
+    let centered = layout()
+        .align(HAlign::Center, VAlign::Middle)
+        .bounding_box("AB");
+    assert_eq!(
+        (centered.x, centered.y, centered.width, centered.height),
+        (98.0, 112.0, 24.0, 16.0)
+    );
+
+    let empty = layout().bounding_box("");
+    assert_eq!((empty.width, empty.height), (0.0, 16.0));
+}
@@ -0,0 +1,73 @@
+use vector_apps::utils::{
+    markup::{MarkupError, TextRun, TextStyle, font_by_name, parse_markup},
+    text::DEFAULT_FONT,
+};
+
+const BASE: TextStyle = TextStyle::new(DEFAULT_FONT, (255, 255, 255));
+
+fn summary(runs: &[TextRun]) -> Vec<(&str, (u8, u8, u8), f32)> {
+    runs.iter()
+        .map(|r| (r.text.as_str(), r.style.color, r.style.size))
+        .collect()
+}
+
+#[test]
+fn styles_runs() {
+    let runs = parse_markup("{red}HELLO {size=2}{#00ff80}world{/}!", BASE).unwrap();
+    assert_eq!(
+        summary(&runs),
+        [
+            ("HELLO ", (255, 0, 0), 1.0),
+            ("world", (0, 255, 128), 2.0),
+            ("!", (255, 255, 255), 1.0),
+        ]
+    );
+}
+
+#[test]
+fn switches_fonts() {
+    let runs = parse_markup("a{font=Hershey:RomanS}b{reset}c", BASE).unwrap();
+    let caps: Vec<_> = runs
+        .iter()
+        .map(|r| r.style.font.metrics().cap_height)
+        .collect();
+    assert_eq!(caps, [6, 21, 6]);
+
+    assert!(font_by_name("newstroke").is_some());
+    assert!(font_by_name("hershey:nope").is_none());
+    assert!(font_by_name("hershey").is_none());
+
+    // Built from fonts/segment.svg
+    let segment = font_by_name("SVG:Segment").unwrap();
+    assert_eq!(segment.metrics().cap_height, 21);
+    assert!(segment.glyph('8').is_some());
+    assert!(segment.glyph('x').is_none());
+}
+
+#[test]
+fn escapes_braces() {
+    let runs = parse_markup("{{x}} } {{{blue}y", BASE).unwrap();
+    assert_eq!(
+        summary(&runs),
+        [("{x} } {", (255, 255, 255), 1.0), ("y", (0, 0, 255), 1.0)]
+    );
+}
+
+#[test]
+fn reports_mistakes() {
+    let err = |markup| parse_markup(markup, BASE).err();
+    assert_eq!(err("ok {red"), Some(MarkupError::Unclosed { at: 3 }));
+    assert_eq!(err("{}"), Some(MarkupError::UnknownTag("".into())));
+    assert_eq!(err("{bold}"), Some(MarkupError::UnknownTag("bold".into())));
+    assert_eq!(
+        err("{color=#12}"),
+        Some(MarkupError::BadColor("#12".into()))
+    );
+    assert_eq!(err("{size=0}"), Some(MarkupError::BadSize("0".into())));
+    assert_eq!(err("{size=NaN}"), Some(MarkupError::BadSize("NaN".into())));
+    assert_eq!(
+        err("{font=comic}"),
+        Some(MarkupError::UnknownFont("comic".into()))
+    );
+    assert!(parse_markup("", BASE).unwrap().is_empty());
+}
@@ -0,0 +1,35 @@
+use vector_apps::{
+    apps::{VectorApp, marquee::Marquee},
+    utils::{layout::measure, text::UNICODE_FONT},
+};
+
+/// Where the lit points drawn in the next frame are across the window.
+fn lit_xs(marquee: &mut Marquee) -> Vec<u8> {
+    let path = marquee.get_path(0);
+    path.iter()
+        .filter(|p| p.color != (0, 0, 0))
+        .map(|p| p.x)
+        .collect()
+}
+
+#[test]
+fn scrolls_to_the_end_of_long_messages() {
+    // Well over the 32767 glyph units an `i16` reaches
+    let message = "x".repeat(3000);
+    let metrics = UNICODE_FONT.metrics();
+    let scale = 64.0 / (metrics.ascent + metrics.descent) as f32;
+    let width = 3000.0 * measure(UNICODE_FONT, "x") as f32 * scale;
+    let gap = measure(UNICODE_FONT, "    ") as f32 * scale;
+
+    // The last letters fill the left quarter of the window, then comes the
+    // gap before the message starts again
+    let mut marquee = Marquee::new(&message).with_speed(width - 64.0);
+    let xs = lit_xs(&mut marquee);
+    assert!(xs.iter().any(|&x| x < 8));
+    assert!(xs.iter().any(|&x| x as f32 > 64.0 + gap + 8.0));
+    assert!(
+        xs.iter().all(|&x| x <= 64 || x as f32 >= 64.0 + gap),
+        "letters in the gap: {:?}",
+        xs
+    );
+}
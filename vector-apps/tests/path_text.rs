@@ -0,0 +1,80 @@
+use std::f32::consts::FRAC_PI_2;
+
+use chr_text::Litt;
+use stroke_font::{KernedFont, StrokeFont};
+use vector_apps::utils::{
+    layout::HAlign,
+    path_text::{Polyline, text_along_path},
+};
+
+/// An L: right 30 from the origin, then down 40.
+fn corner() -> Polyline {
+    Polyline::new(vec![(0.0, 0.0), (30.0, 0.0), (30.0, 40.0)])
+}
+
+fn assert_near(sample: Option<((f32, f32), f32)>, point: (f32, f32), angle: f32) {
+    let ((x, y), a) = sample.expect("a sample");
+    assert!(
+        (x - point.0).abs() < 1e-4 && (y - point.1).abs() < 1e-4 && (a - angle).abs() < 1e-4,
+        "got {:?} at {}, expected {:?} at {}",
+        (x, y),
+        a,
+        point,
+        angle
+    );
+}
+
+#[test]
+fn samples_along_segments() {
+    let line = corner();
+    assert_eq!(line.length(), 70.0);
+    assert_near(line.sample(0.0), (0.0, 0.0), 0.0);
+    assert_near(line.sample(15.0), (15.0, 0.0), 0.0);
+    // The corner belongs to the second segment
+    assert_near(line.sample(30.0), (30.0, 0.0), FRAC_PI_2);
+    assert_near(line.sample(70.0), (30.0, 40.0), FRAC_PI_2);
+}
+
+#[test]
+fn carries_on_past_the_ends() {
+    let line = corner();
+    assert_near(line.sample(80.0), (30.0, 50.0), FRAC_PI_2);
+    assert_near(line.sample(-10.0), (-10.0, 0.0), 0.0);
+}
+
+#[test]
+fn needs_a_segment_with_length() {
+    assert!(Polyline::new(vec![]).sample(0.0).is_none());
+    assert_eq!(Polyline::new(vec![]).length(), 0.0);
+    assert!(Polyline::new(vec![(5.0, 5.0)]).sample(0.0).is_none());
+    assert!(Polyline::new(vec![(5.0, 5.0); 3]).sample(0.0).is_none());
+
+    // Zero length segments at either end are skipped
+    let line = Polyline::new(vec![(0.0, 0.0), (0.0, 0.0), (10.0, 0.0), (10.0, 0.0)]);
+    assert_near(line.sample(-1.0), (-1.0, 0.0), 0.0);
+    assert_near(line.sample(12.0), (12.0, 0.0), 0.0);
+}
+
+#[test]
+fn places_glyphs_with_kerning() {
+    let line = Polyline::new(vec![(0.0, 128.0), (256.0, 128.0)]);
+    let draw = |font: &dyn StrokeFont, text| -> Vec<(u8, u8)> {
+        text_along_path(font, text, &line, HAlign::Right, 4.0, |_| (255, 255, 255))
+            .iter()
+            .map(|p| (p.x, p.y))
+            .collect()
+    };
+
+    // Right aligned, so the V ends up in the same place however close the
+    // A is kerned to it
+    let kerned = KernedFont::new(&Litt, &[('A', 'V', -2)]);
+    let (plain, tight) = (draw(&Litt, "AV"), draw(&kerned, "AV"));
+    let v = plain.len() - draw(&Litt, "V").len();
+    assert_eq!(tight[v..], plain[v..]);
+    assert!(
+        tight[..v]
+            .iter()
+            .zip(&plain[..v])
+            .all(|(t, p)| t.0 == p.0 + 8 && t.1 == p.1)
+    );
+}
@@ -0,0 +1,128 @@
+use vector_apps::{
+    point::Point,
+    utils::{
+        ilda::{Projection, read_ilda_frames},
+        scanner::ScannerProfile,
+    },
+};
+
+const RED: (u8, u8, u8) = (255, 0, 0);
+const BLACK: (u8, u8, u8) = (0, 0, 0);
+
+/// 100µs a point, moving at most 4 units lit and 16 blanked, with 250µs
+/// at corners.
+fn profile() -> ScannerProfile {
+    ScannerProfile::new()
+        .with_points_per_second(10_000)
+        .with_max_steps(4.0, 16.0)
+        .with_min_step(1.0)
+        .with_corner_dwell(250)
+}
+
+fn summary(path: &[Point]) -> Vec<(u8, u8, u16)> {
+    path.iter().map(|p| (p.x, p.y, p.delay)).collect()
+}
+
+fn longest_step(path: &[Point], lit: bool) -> f32 {
+    path.windows(2)
+        .filter(|w| (w[1].color != BLACK) == lit)
+        .map(|w| {
+            let dx = w[1].x as f32 - w[0].x as f32;
+            let dy = w[1].y as f32 - w[0].y as f32;
+            (dx * dx + dy * dy).sqrt()
+        })
+        .fold(0.0, f32::max)
+}
+
+#[test]
+fn subdivides_long_moves() {
+    let path = profile().resample(&[(0.0, 0.0, BLACK), (40.0, 0.0, RED)]);
+    let xs: Vec<_> = path.iter().map(|p| p.x).collect();
+    assert_eq!(xs, [0, 4, 8, 12, 16, 20, 24, 28, 32, 36, 40]);
+    assert!(path[1..].iter().all(|p| p.color == RED));
+
+    // Blanked moves can go further a point
+    let path = profile().resample(&[(0.0, 0.0, RED), (40.0, 30.0, BLACK)]);
+    assert_eq!(path.len(), 5);
+    assert!(path[1..].iter().all(|p| p.color == BLACK));
+}
+
+#[test]
+fn drops_dense_points() {
+    // A line drawn in steps of a tenth of a unit
+    let line: Vec<_> = (0..=100).map(|i| (i as f32 / 10.0, 5.0, RED)).collect();
+    let path = profile().resample(&line);
+    assert_eq!(path.len(), 11);
+    assert_eq!(summary(&path)[..2], [(0, 5, 100), (1, 5, 100)]);
+    assert_eq!(path.last().map(|p| p.x), Some(10));
+
+    // Color changes are kept however close
+    let path = profile().resample(&[(0.0, 0.0, RED), (0.5, 0.0, (0, 255, 0))]);
+    assert_eq!(path.len(), 2);
+}
+
+#[test]
+fn waits_at_corners() {
+    // An L with the corner repeated, the way ILDA files dwell
+    let mut points = vec![(0.0, 0.0, BLACK), (0.0, 0.0, RED), (4.0, 0.0, RED)];
+    points.extend([(8.0, 0.0, RED); 4]);
+    points.extend([(8.0, 4.0, RED), (8.0, 8.0, RED)]);
+    let path = profile().resample(&points);
+
+    assert_eq!(
+        summary(&path),
+        [
+            (0, 0, 100),
+            // The beam turning on
+            (0, 0, 350),
+            (4, 0, 100),
+            // Half the dwell for a right angle, and the repeats dropped
+            (8, 0, 225),
+            (8, 4, 100),
+            (8, 8, 100),
+        ]
+    );
+
+    // Doubling back waits the whole time, and gentle bends not at all
+    let back = profile().resample(&[(0.0, 0.0, RED), (8.0, 0.0, RED), (0.0, 0.0, RED)]);
+    assert_eq!(back[2].delay, 350);
+    let bend = profile().resample(&[(0.0, 0.0, RED), (8.0, 0.0, RED), (16.0, 2.0, RED)]);
+    assert!(bend.iter().all(|p| p.delay == 100));
+}
+
+#[test]
+fn keeps_points_as_given() {
+    let points = [
+        (0.0, 0.0, RED),
+        (0.0, 0.0, RED),
+        (200.0, 100.0, BLACK),
+        (200.5, 100.0, BLACK),
+    ];
+    let path = ScannerProfile::as_given(25_000).resample(&points);
+    assert_eq!(
+        summary(&path),
+        [(0, 0, 40), (0, 0, 40), (200, 100, 40), (201, 100, 40)]
+    );
+    assert!(profile().resample(&[]).is_empty());
+}
+
+#[test]
+fn fits_files_to_the_galvos() {
+    let frames = read_ilda_frames(include_bytes!("../src/apps/ildatest.ild")).unwrap();
+    let profile = profile();
+
+    for frame in &frames {
+        let path = frame.resample(Projection::Flat, &profile);
+        // Rounding to DAC units can stretch a step by up to one diagonal
+        assert!(longest_step(&path, true) <= 4.0 + 1.5);
+        assert!(longest_step(&path, false) <= 16.0 + 1.5);
+
+        let drawn = frame.to_path(Projection::Flat, 100);
+        assert_eq!(
+            (path[0].x, path[0].y),
+            (drawn[0].x, drawn[0].y),
+            "{}",
+            frame.name
+        );
+    }
+}
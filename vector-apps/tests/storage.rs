@@ -0,0 +1,244 @@
+use std::{cell::Cell, rc::Rc};
+
+use embedded_storage::nor_flash::{ErrorType, NorFlash, NorFlashErrorKind, ReadNorFlash};
+use vector_apps::storage::{MemoryStorage, Storage, StorageError, flash::FlashStore};
+
+const SECTOR: usize = 256;
+
+/// NOR flash in RAM. Writes can only clear bits, like the real thing, and
+/// the power can be cut after a number of erases and writes.
+#[derive(Clone)]
+struct Flash {
+    data: Vec<u8>,
+    /// Erases and writes left before the power goes.
+    budget: Rc<Cell<usize>>,
+}
+
+impl Flash {
+    fn new(sectors: usize) -> Self {
+        Self {
+            data: vec![0xff; sectors * SECTOR],
+            budget: Rc::new(Cell::new(usize::MAX)),
+        }
+    }
+
+    /// A copy of what's on the flash, with its own power supply.
+    fn fork(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+            budget: Rc::new(Cell::new(usize::MAX)),
+        }
+    }
+
+    fn spend(&self) -> Result<(), NorFlashErrorKind> {
+        match self.budget.get() {
+            0 => Err(NorFlashErrorKind::Other),
+            n => {
+                self.budget.set(n - 1);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl ErrorType for Flash {
+    type Error = NorFlashErrorKind;
+}
+
+impl ReadNorFlash for Flash {
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        let offset = offset as usize;
+        bytes.copy_from_slice(&self.data[offset..offset + bytes.len()]);
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.data.len()
+    }
+}
+
+impl NorFlash for Flash {
+    const WRITE_SIZE: usize = 4;
+    const ERASE_SIZE: usize = SECTOR;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        self.spend()?;
+        self.data[from as usize..to as usize].fill(0xff);
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.spend()?;
+        for (old, &new) in self.data[offset as usize..].iter_mut().zip(bytes) {
+            assert_eq!(*old & new, new, "writing {:#x} over {:#x}", new, *old);
+            *old &= new;
+        }
+        Ok(())
+    }
+}
+
+fn contents(storage: &dyn Storage) -> Vec<(String, Vec<u8>)> {
+    storage
+        .keys()
+        .into_iter()
+        .map(|key| {
+            let value = storage.get(&key).unwrap();
+            (key, value)
+        })
+        .collect()
+}
+
+#[test]
+fn keeps_values_across_reboots() {
+    let mut flash = Flash::new(4);
+    {
+        let mut store = FlashStore::new(&mut flash).unwrap();
+        assert!(store.keys().is_empty());
+        store.set("maps.lat", b"north").unwrap();
+        store.set_u32("cycle.idx", 3).unwrap();
+        store.set("gone", b"soon").unwrap();
+        store.remove("gone").unwrap();
+    }
+
+    let mut store = FlashStore::new(&mut flash).unwrap();
+    assert_eq!(store.get("maps.lat").as_deref(), Some(&b"north"[..]));
+    assert_eq!(store.get_u32("cycle.idx"), Some(3));
+    assert_eq!(store.get("gone"), None);
+    assert_eq!(store.keys(), ["cycle.idx", "maps.lat"]);
+
+    store.clear().unwrap();
+    drop(store);
+    assert!(FlashStore::new(&mut flash).unwrap().keys().is_empty());
+}
+
+#[test]
+fn wears_across_every_sector() {
+    let mut flash = Flash::new(4);
+    let mut store = FlashStore::new(&mut flash).unwrap();
+    for i in 0..500u32 {
+        store.set_u32(&format!("key{}", i % 7), i).unwrap();
+    }
+    drop(store);
+
+    let store = FlashStore::new(&mut flash).unwrap();
+    for k in 0..7u32 {
+        let last = (0..500).rev().find(|i| i % 7 == k).unwrap();
+        assert_eq!(store.get_u32(&format!("key{}", k)), Some(last));
+    }
+    // Every sector has been written to
+    assert!(flash.data.chunks(SECTOR).all(|s| s[..4] != [0xff; 4]));
+}
+
+#[test]
+fn survives_losing_power_at_any_point() {
+    let mut flash = Flash::new(3);
+    let mut model = MemoryStorage::new();
+    drop(FlashStore::new(&mut flash).unwrap());
+
+    for i in 0..80u8 {
+        let key = format!("key{}", i % 5);
+        let value = [i; 10];
+
+        // Cut the power after each number of flash operations in turn,
+        // until the update gets through
+        for budget in 0.. {
+            let mut trial = flash.fork();
+            let power = trial.budget.clone();
+            let mut store = FlashStore::new(&mut trial).unwrap();
+            power.set(budget);
+            let done = store.set(&key, &value).is_ok();
+            drop(store);
+
+            power.set(usize::MAX);
+            let rebooted = FlashStore::new(&mut trial).unwrap();
+            let mut expected = model.clone();
+            if done || rebooted.get(&key).as_deref() == Some(&value[..]) {
+                expected.set(&key, &value).unwrap();
+            }
+            assert_eq!(
+                contents(&rebooted),
+                contents(&expected),
+                "update {} losing power after {} operations",
+                i,
+                budget
+            );
+
+            if done {
+                flash = trial;
+                model = expected;
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+fn writes_after_a_torn_record_only_once_compacted() {
+    let mut flash = Flash::new(3);
+    let power = flash.budget.clone();
+    FlashStore::new(&mut flash)
+        .unwrap()
+        .set("kept", b"value")
+        .unwrap();
+
+    // Half a record after the first one, and no power to tidy up at boot
+    let end = (8..SECTOR)
+        .step_by(4)
+        .find(|&i| flash.data[i] == 0xff)
+        .unwrap();
+    flash.data[end..end + 4].copy_from_slice(&[4, 5, 0, 0]);
+    power.set(0);
+    let mut store = FlashStore::new(&mut flash).unwrap();
+    assert_eq!(store.get("kept").as_deref(), Some(&b"value"[..]));
+    assert_eq!(store.set("new", b"one"), Err(StorageError::Io));
+
+    // Writing over the torn record would trip the flash's assert
+    power.set(usize::MAX);
+    store.set("new", b"two").unwrap();
+    drop(store);
+    let store = FlashStore::new(&mut flash).unwrap();
+    assert_eq!(store.keys(), ["kept", "new"]);
+    assert_eq!(store.get("new").as_deref(), Some(&b"two"[..]));
+}
+
+#[test]
+fn needs_two_sectors() {
+    let mut flash = Flash::new(1);
+    assert_eq!(
+        FlashStore::new(&mut flash).err(),
+        Some(StorageError::TooSmall)
+    );
+    // And leaves the flash alone
+    assert!(flash.data.iter().all(|&b| b == 0xff));
+}
+
+#[test]
+fn refuses_oversized_entries() {
+    let mut flash = Flash::new(2);
+    let mut store = FlashStore::new(&mut flash).unwrap();
+    let long_key = "k".repeat(300);
+    assert_eq!(store.set(&long_key, b""), Err(StorageError::TooLarge));
+    // Too big to ever fit in a sector
+    assert_eq!(store.set("big", &[0; SECTOR]), Err(StorageError::Full));
+    assert_eq!(store.get("big"), None);
+}
+
+#[test]
+fn keeps_values_in_memory() {
+    let mut storage = MemoryStorage::new();
+    storage.set_u32("b", 7).unwrap();
+    storage.set_f32("a", 1.5).unwrap();
+    storage.set("c", b"hello").unwrap();
+    assert_eq!(storage.keys(), ["a", "b", "c"]);
+    assert_eq!(storage.get_u32("b"), Some(7));
+    assert_eq!(storage.get_f32("a"), Some(1.5));
+    // Values of the wrong size don't read as numbers
+    assert_eq!(storage.get_u32("c"), None);
+
+    storage.remove("b").unwrap();
+    assert_eq!(storage.get("b"), None);
+    storage.clear().unwrap();
+    assert!(storage.keys().is_empty());
+}
@@ -0,0 +1,33 @@
+use hershey_text::HersheyFont;
+use newstroke_text::Newstroke;
+use stroke_font::{Glyph, StrokeFont};
+use vector_apps::utils::text::UNICODE_FONT;
+
+fn strokes(glyph: Option<Glyph>) -> Vec<(i16, i16, bool)> {
+    glyph
+        .expect("a glyph")
+        .points()
+        .map(|p| (p.x, p.y, p.pen))
+        .collect()
+}
+
+#[test]
+fn falls_back_from_newstroke_to_hershey() {
+    let romans = HersheyFont::by_name("romans").unwrap();
+    assert_eq!(
+        strokes(UNICODE_FONT.glyph('A')),
+        strokes(Newstroke.glyph('A'))
+    );
+
+    // Whatever Newstroke is missing comes from Hershey unscaled
+    for c in (' '..='\u{24f}').filter(|&c| Newstroke.glyph(c).is_none()) {
+        if let Some(glyph) = romans.glyph(c) {
+            assert_eq!(
+                strokes(UNICODE_FONT.glyph(c)),
+                strokes(Some(glyph)),
+                "{:?}",
+                c
+            );
+        }
+    }
+}
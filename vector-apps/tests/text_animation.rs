@@ -0,0 +1,93 @@
+use vector_apps::utils::text_animation::{GlyphStyle, TextAnimation, TextEffect};
+
+fn styled(effect: TextEffect, index: usize, time: f32) -> GlyphStyle {
+    let mut style = GlyphStyle::PLAIN;
+    effect.apply(&mut style, index, time);
+    style
+}
+
+/// Characters of a 10 character string visible at `time`.
+fn visible(effect: TextEffect, time: f32) -> usize {
+    (0..10).filter(|&i| styled(effect, i, time).visible).count()
+}
+
+/// Where the glyph's middle and a point a unit right of it end up.
+fn moved(style: &GlyphStyle) -> [(f32, f32); 2] {
+    [
+        style.transform.apply(0.0, 0.0),
+        style.transform.apply(1.0, 0.0),
+    ]
+}
+
+#[test]
+fn types_characters_one_at_a_time() {
+    let typewriter = TextEffect::Typewriter { rate: 0.5 };
+    assert_eq!(visible(typewriter, 0.0), 0);
+    assert_eq!(visible(typewriter, 1.0), 1);
+    assert_eq!(visible(typewriter, 4.0), 2);
+    assert_eq!(visible(typewriter, 4.5), 3);
+    assert_eq!(visible(typewriter, 100.0), 10);
+
+    // It only ever hides characters, leaving where they are alone
+    assert_eq!(
+        moved(&styled(typewriter, 0, 10.0)),
+        [(0.0, 0.0), (1.0, 0.0)]
+    );
+}
+
+#[test]
+fn scales_characters_in_after_their_start() {
+    let scale_in = TextEffect::ScaleIn {
+        duration: 10.0,
+        stagger: 5.0,
+    };
+
+    // Character 2 starts at frame 10
+    assert!(!styled(scale_in, 2, 0.0).visible);
+    assert!(!styled(scale_in, 2, 10.0).visible);
+    let growing = styled(scale_in, 2, 15.0);
+    assert!(growing.visible);
+    let [middle, right] = moved(&growing);
+    assert_eq!(middle, (0.0, 0.0));
+    assert!(right.0 > 0.5 && right.0 < 1.0, "{:?}", right);
+
+    // Full size once it's done
+    assert_eq!(moved(&styled(scale_in, 2, 20.0)), [(0.0, 0.0), (1.0, 0.0)]);
+    assert_eq!(visible(scale_in, 12.0), 3);
+}
+
+#[test]
+fn jitters_the_same_way_each_step() {
+    let jitter = TextEffect::Jitter {
+        amount: 2.0,
+        every: 4.0,
+    };
+
+    // Frames 4 to 7 are one step
+    let spot = moved(&styled(jitter, 3, 4.0));
+    assert_eq!(moved(&styled(jitter, 3, 7.5)), spot);
+    assert_eq!(
+        moved(&TextAnimation::new().with(jitter).style(3, 5.0)),
+        spot
+    );
+    assert_ne!(moved(&styled(jitter, 3, 8.0)), spot);
+    assert_ne!(moved(&styled(jitter, 4, 4.0)), spot);
+
+    // Moved, not scaled, by no more than the amount
+    let [(x, y), (x1, y1)] = spot;
+    assert!(x.abs() <= 2.0 && y.abs() <= 2.0);
+    assert_eq!((x1 - x, y1 - y), (1.0, 0.0));
+}
+
+#[test]
+fn applies_effects_in_order() {
+    let animation = TextAnimation::new()
+        .with(TextEffect::Typewriter { rate: 1.0 })
+        .with(TextEffect::Rainbow {
+            spread: 0.25,
+            speed: 0.0,
+        });
+    assert!(!animation.style(5, 2.0).visible);
+    assert_eq!(animation.style(0, 2.0).color, Some((255, 0, 0)));
+    assert_ne!(animation.style(1, 2.0).color, animation.style(0, 2.0).color);
+}